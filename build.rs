@@ -0,0 +1,557 @@
+//
+// Part of the-romans-are-coming
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+//! Compiles the decrees in assets/decrees.txt into a Rust table that is
+//! included by src/decree.rs, and the maps in assets/maps into constants
+//! that are included by src/map.rs. Also shrinks the release cart.
+
+use std::fmt::Write;
+
+const DECREES_PATH: &str = "assets/decrees.txt";
+const MAPS_PATH: &str = "assets/maps";
+
+const MARKERS: [&str; 6] = [
+	"Worker",
+	"DeadWorker",
+	"Roman",
+	"DeadRoman",
+	"Occupied",
+	"FogOfWar",
+];
+const TERRAIN_TYPES: [&str; 6] =
+	["Village", "Grass", "Forest", "Hill", "Mountain", "Water"];
+const QUANTIFIERS: [&str; 3] = ["AtLeast", "AtMost", "Exactly"];
+
+/// The symbol of each terrain type on a map, with the open sea as None.
+const TERRAIN_SYMBOLS: [(char, Option<&str>); 7] = [
+	('~', None),
+	('o', Some("Village")),
+	('.', Some("Grass")),
+	('f', Some("Forest")),
+	('n', Some("Hill")),
+	('A', Some("Mountain")),
+	('w', Some("Water")),
+];
+
+/// Counts are saved in six bits.
+const MAX_COUNT: u8 = 63;
+
+/// As in src/map.rs and src/rules.rs.
+const GRID_SIZE: usize = 16;
+const GRID_CELL_SIZE: usize = 10;
+const MAX_NUM_REGIONS: usize = 35;
+
+pub fn main()
+{
+	compile_decrees();
+	compile_maps();
+	compress_relocations();
+}
+
+/// Shrinks the padded indices and addresses in the code section of the
+/// release cart. The linker refuses to do so if there is debug information,
+/// so debug builds are left as they are.
+fn compress_relocations()
+{
+	let target = std::env::var("TARGET").unwrap();
+	let profile = std::env::var("PROFILE").unwrap();
+	if target.starts_with("wasm32") && profile == "release"
+	{
+		println!("cargo:rustc-cdylib-link-arg=--compress-relocations");
+	}
+}
+
+fn compile_decrees()
+{
+	println!("cargo:rerun-if-changed={}", DECREES_PATH);
+	let text = std::fs::read_to_string(DECREES_PATH).unwrap();
+	let mut decrees = String::new();
+	let mut num_decrees = 0;
+	let mut deck = Vec::new();
+	for (i, line) in text.lines().enumerate()
+	{
+		let line = line.split('#').next().unwrap().trim();
+		if line.is_empty()
+		{
+			continue;
+		}
+		match parse_decree(line)
+		{
+			Ok((definition, is_in_deck)) =>
+			{
+				if is_in_deck
+				{
+					deck.push(num_decrees);
+				}
+				decrees.push_str(&definition);
+				num_decrees += 1;
+			}
+			Err(error) =>
+			{
+				panic!("{}:{}: {}", DECREES_PATH, i + 1, error);
+			}
+		}
+	}
+
+	let mut code = String::new();
+	writeln!(
+		code,
+		"pub const DECREES: [DecreeDefinition; {}] = [",
+		num_decrees
+	)
+	.unwrap();
+	code.push_str(&decrees);
+	writeln!(code, "];").unwrap();
+	writeln!(
+		code,
+		"pub const DECREE_DECK_SIZE: usize = {};",
+		deck.len()
+	)
+	.unwrap();
+	writeln!(
+		code,
+		"pub const DECREE_DECK: [u8; DECREE_DECK_SIZE] = {:?};",
+		deck
+	)
+	.unwrap();
+	let out_dir = std::env::var("OUT_DIR").unwrap();
+	let path = std::path::Path::new(&out_dir).join("decrees.rs");
+	std::fs::write(path, code).unwrap();
+}
+
+fn compile_maps()
+{
+	println!("cargo:rerun-if-changed={}", MAPS_PATH);
+	let mut paths: Vec<std::path::PathBuf> = std::fs::read_dir(MAPS_PATH)
+		.unwrap()
+		.map(|entry| entry.unwrap().path())
+		.filter(|path| path.extension().is_some_and(|x| x == "map"))
+		.collect();
+	paths.sort();
+	let mut code = String::new();
+	for path in paths
+	{
+		println!("cargo:rerun-if-changed={}", path.display());
+		let name = path.file_stem().unwrap().to_str().unwrap();
+		let text = std::fs::read_to_string(&path).unwrap();
+		match parse_map(&name.to_uppercase(), &text)
+		{
+			Ok(definition) => code.push_str(&definition),
+			Err((i, error)) =>
+			{
+				panic!("{}:{}: {}", path.display(), i + 1, error);
+			}
+		}
+	}
+	let out_dir = std::env::var("OUT_DIR").unwrap();
+	let path = std::path::Path::new(&out_dir).join("maps.rs");
+	std::fs::write(path, code).unwrap();
+}
+
+struct AuthoredRegion
+{
+	x: usize,
+	y: usize,
+	terrain_type: &'static str,
+	marker: Option<String>,
+}
+
+/// Turns a map file into an `AuthoredMap` constant, along with a constant for
+/// the id of each named region. Errors come with the index of the line.
+fn parse_map(name: &str, text: &str) -> Result<String, (usize, String)>
+{
+	let mut rows: Vec<(usize, Vec<Option<&str>>)> = Vec::new();
+	let mut regions: Vec<AuthoredRegion> = Vec::new();
+	let mut cells = [[-1i8; GRID_SIZE]; GRID_SIZE];
+	let mut constants = String::new();
+	for (i, line) in text.lines().enumerate()
+	{
+		let line = line.split('#').next().unwrap().trim();
+		if line.is_empty()
+		{
+			continue;
+		}
+		if rows.len() < GRID_SIZE
+		{
+			let row = parse_row(line).map_err(|error| (i, error))?;
+			rows.push((i, row));
+			continue;
+		}
+		let region_id = regions.len();
+		let (region, label) =
+			parse_region(line, &rows).map_err(|error| (i, error))?;
+		let (r, c) = (region.y / GRID_CELL_SIZE, region.x / GRID_CELL_SIZE);
+		if cells[r][c] >= 0
+		{
+			return Err((
+				i,
+				format!("cell shared with region {}", cells[r][c]),
+			));
+		}
+		if region_id >= MAX_NUM_REGIONS
+		{
+			return Err((i, format!("more than {} regions", MAX_NUM_REGIONS)));
+		}
+		cells[r][c] = region_id as i8;
+		if let Some(label) = label
+		{
+			writeln!(
+				constants,
+				"pub const {}_{}: usize = {};",
+				name,
+				label.to_uppercase(),
+				region_id
+			)
+			.unwrap();
+		}
+		regions.push(region);
+	}
+	if rows.len() < GRID_SIZE
+	{
+		let error = format!("expected {} rows of cells", GRID_SIZE);
+		return Err((text.lines().count(), error));
+	}
+	// Every other land cell belongs to the closest region of its terrain.
+	for (r, (i, row)) in rows.iter().enumerate()
+	{
+		for (c, terrain_type) in row.iter().enumerate()
+		{
+			let Some(terrain_type) = terrain_type
+			else
+			{
+				continue;
+			};
+			if cells[r][c] >= 0
+			{
+				continue;
+			}
+			let x = (c * GRID_CELL_SIZE + GRID_CELL_SIZE / 2) as i32;
+			let y = (r * GRID_CELL_SIZE + GRID_CELL_SIZE / 2) as i32;
+			let closest = regions
+				.iter()
+				.enumerate()
+				.filter(|(_, region)| region.terrain_type == *terrain_type)
+				.min_by_key(|(_, region)| {
+					let dx = region.x as i32 - x;
+					let dy = region.y as i32 - y;
+					dx * dx + dy * dy
+				});
+			match closest
+			{
+				Some((region_id, _)) => cells[r][c] = region_id as i8,
+				None =>
+				{
+					let error = format!("no region of {}", terrain_type);
+					return Err((*i, error));
+				}
+			}
+		}
+	}
+
+	let mut code = String::new();
+	writeln!(code, "pub const {}: AuthoredMap = AuthoredMap {{", name).unwrap();
+	writeln!(code, "\tcells: [").unwrap();
+	for row in cells
+	{
+		writeln!(code, "\t\t{:?},", row).unwrap();
+	}
+	writeln!(code, "\t],").unwrap();
+	writeln!(code, "\tregions: &[").unwrap();
+	for region in regions
+	{
+		let marker = match region.marker
+		{
+			Some(marker) => format!("Some(Marker::{})", marker),
+			None => "None".to_string(),
+		};
+		writeln!(
+			code,
+			"\t\tAuthoredRegion {{
+			x: {},
+			y: {},
+			terrain_type: TerrainType::{},
+			marker: {},
+		}},",
+			region.x, region.y, region.terrain_type, marker
+		)
+		.unwrap();
+	}
+	writeln!(code, "\t],").unwrap();
+	writeln!(code, "}};").unwrap();
+	code.push_str(&constants);
+	Ok(code)
+}
+
+/// Parses a row of cells such as "~~..ffnA~~".
+fn parse_row(line: &str) -> Result<Vec<Option<&'static str>>, String>
+{
+	let row = line
+		.chars()
+		.map(|symbol| {
+			TERRAIN_SYMBOLS
+				.iter()
+				.find(|(x, _)| *x == symbol)
+				.map(|(_, terrain_type)| *terrain_type)
+				.ok_or_else(|| format!("unknown terrain '{}'", symbol))
+		})
+		.collect::<Result<Vec<_>, String>>()?;
+	if row.len() != GRID_SIZE
+	{
+		return Err(format!("expected {} cells, got {}", GRID_SIZE, row.len()));
+	}
+	Ok(row)
+}
+
+/// Parses "74 38 FogOfWar first_roman" into a region and its name, if any.
+fn parse_region(
+	line: &str,
+	rows: &[(usize, Vec<Option<&'static str>>)],
+) -> Result<(AuthoredRegion, Option<String>), String>
+{
+	let words: Vec<&str> = line.split_whitespace().collect();
+	let [x, y, ref rest @ ..] = words[..]
+	else
+	{
+		return Err(format!("expected a centroid, got '{}'", line));
+	};
+	let map_size = GRID_SIZE * GRID_CELL_SIZE;
+	let parse_coordinate = |text: &str| match text.parse::<usize>()
+	{
+		Ok(value) if (2..(map_size - 2)).contains(&value) => Ok(value),
+		_ => Err(format!("invalid coordinate '{}'", text)),
+	};
+	let (x, y) = (parse_coordinate(x)?, parse_coordinate(y)?);
+	let (_, row) = &rows[y / GRID_CELL_SIZE];
+	let Some(terrain_type) = row[x / GRID_CELL_SIZE]
+	else
+	{
+		return Err(format!("centroid ({}, {}) is in the sea", x, y));
+	};
+	let mut marker = None;
+	let mut label = None;
+	for word in rest
+	{
+		if MARKERS.contains(word) && marker.is_none()
+		{
+			marker = Some(word.to_string());
+		}
+		else if word.chars().all(|c| c.is_ascii_lowercase() || c == '_')
+			&& label.is_none()
+		{
+			label = Some(word.to_string());
+		}
+		else
+		{
+			return Err(format!("unexpected '{}'", word));
+		}
+	}
+	let region = AuthoredRegion {
+		x,
+		y,
+		terrain_type,
+		marker,
+	};
+	Ok((region, label))
+}
+
+/// Turns a line of the data file into a `DecreeDefinition` literal, and
+/// whether the decree is shuffled into the deck. Besides the decrees that are
+/// drawn, the deck holds those issued last in a game, as it always has.
+fn parse_decree(line: &str) -> Result<(String, bool), String>
+{
+	let mut words: Vec<&str> = line.split_whitespace().collect();
+	let min_year = match words[..]
+	{
+		[.., "from", "year", year] => match year.parse::<u8>()
+		{
+			Ok(year) if year > 0 =>
+			{
+				words.truncate(words.len() - 3);
+				year
+			}
+			_ => return Err(format!("invalid year '{}'", year)),
+		},
+		_ => 1,
+	};
+	let [mode, threat, issue, ref wording @ ..] = words[..]
+	else
+	{
+		return Err(format!("expected at least 3 words, got {}", words.len()));
+	};
+	let is_tutorial = match mode
+	{
+		"game" => false,
+		"tutorial" => true,
+		_ => return Err(format!("unknown mode '{}'", mode)),
+	};
+	let (min_threat, max_threat) = parse_threat(threat)
+		.ok_or_else(|| format!("invalid threat levels '{}'", threat))?;
+	let (issue, is_in_deck) = match issue
+	{
+		"first" => ("Issue::First".to_string(), false),
+		"last" => ("Issue::Last".to_string(), !is_tutorial),
+		_ => match issue.parse::<u8>()
+		{
+			Ok(weight) if weight > 0 && !is_tutorial =>
+			{
+				(format!("Issue::Drawn {{ weight: {} }}", weight), true)
+			}
+			Ok(weight) if weight > 0 =>
+			{
+				return Err("tutorial decrees cannot be drawn".to_string());
+			}
+			_ => return Err(format!("invalid issue '{}'", issue)),
+		},
+	};
+	let decree = match *wording
+	{
+		[quantifier, count, marker, relation, terrain_type]
+			if QUANTIFIERS.contains(&quantifier) =>
+		{
+			parse_count(quantifier, count, marker, relation, terrain_type)?
+		}
+		[all_or_none, marker, relation, target] =>
+		{
+			parse_order(all_or_none, marker, relation, target)?
+		}
+		_ =>
+		{
+			return Err(format!("cannot parse decree '{}'", wording.join(" ")))
+		}
+	};
+	let definition = format!(
+		"\tDecreeDefinition {{
+		is_tutorial: {},
+		min_threat: {},
+		max_threat: {},
+		min_year: {},
+		issue: {},
+		decree: {},
+	}},\n",
+		is_tutorial, min_threat, max_threat, min_year, issue, decree
+	);
+	Ok((definition, is_in_deck))
+}
+
+/// Parses "All Roman near Water" or "No Roman as DeadRoman".
+fn parse_order(
+	all_or_none: &str,
+	marker: &str,
+	relation: &str,
+	target: &str,
+) -> Result<String, String>
+{
+	let all_or_none = match all_or_none
+	{
+		"All" => "All",
+		"No" => "None",
+		_ => return Err(format!("expected All or No, got '{}'", all_or_none)),
+	};
+	parse_marker(marker)?;
+	let target = if MARKERS.contains(&target)
+	{
+		match relation
+		{
+			"near" | "as" => format!("Target::Marker(Marker::{})", target),
+			_ => return Err(format!("cannot be {} a marker", relation)),
+		}
+	}
+	else if TERRAIN_TYPES.contains(&target)
+	{
+		match relation
+		{
+			"in" | "near" =>
+			{
+				format!("Target::Terrain(TerrainType::{})", target)
+			}
+			_ => return Err(format!("cannot be {} a terrain type", relation)),
+		}
+	}
+	else
+	{
+		return Err(format!("unknown target '{}'", target));
+	};
+	let relation = match relation
+	{
+		"in" => "In",
+		"near" => "Near",
+		_ => "As",
+	};
+	Ok(format!(
+		"Decree::Order {{
+			all_or_none: AllOrNone::{},
+			marker: Marker::{},
+			relation: Relation::{},
+			target: {},
+		}}",
+		all_or_none, marker, relation, target
+	))
+}
+
+/// Parses "AtMost 2 Roman in Forest".
+fn parse_count(
+	quantifier: &str,
+	count: &str,
+	marker: &str,
+	relation: &str,
+	terrain_type: &str,
+) -> Result<String, String>
+{
+	let count: u8 = match count.parse()
+	{
+		Ok(count) if count > 0 && count <= MAX_COUNT => count,
+		_ => return Err(format!("invalid count '{}'", count)),
+	};
+	parse_marker(marker)?;
+	let relation = match relation
+	{
+		"in" => "In",
+		"near" => "Near",
+		_ => return Err(format!("expected in or near, got '{}'", relation)),
+	};
+	if !TERRAIN_TYPES.contains(&terrain_type)
+	{
+		return Err(format!("unknown terrain type '{}'", terrain_type));
+	}
+	Ok(format!(
+		"Decree::Count {{
+			quantifier: Quantifier::{},
+			count: {},
+			marker: Marker::{},
+			relation: Relation::{},
+			terrain_type: TerrainType::{},
+		}}",
+		quantifier, count, marker, relation, terrain_type
+	))
+}
+
+fn parse_marker(marker: &str) -> Result<(), String>
+{
+	match marker
+	{
+		"Worker" | "Roman" => Ok(()),
+		_ => Err(format!("expected Worker or Roman, got '{}'", marker)),
+	}
+}
+
+/// Parses "4", "4+" or "4-6" into an inclusive range of threat levels.
+fn parse_threat(threat: &str) -> Option<(u8, u8)>
+{
+	if let Some(min) = threat.strip_suffix('+')
+	{
+		Some((min.parse().ok()?, u8::MAX))
+	}
+	else if let Some((min, max)) = threat.split_once('-')
+	{
+		let (min, max) = (min.parse().ok()?, max.parse().ok()?);
+		(min <= max).then_some((min, max))
+	}
+	else
+	{
+		let level = threat.parse().ok()?;
+		Some((level, level))
+	}
+}
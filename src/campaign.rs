@@ -0,0 +1,180 @@
+//
+// Part of the-romans-are-coming
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+//! A series of scenarios with a goal each. A chapter unlocks once the one
+//! before it has been completed.
+
+use crate::disk;
+use crate::map::Preset;
+use crate::scenario::{Scenario, CAMPAIGN};
+
+const CAMPAIGN_FORMAT_VERSION: u8 = 1;
+
+#[derive(Debug)]
+pub struct Chapter
+{
+	pub name: &'static str,
+	pub scenario: &'static Scenario,
+	/// The island is generated, but always the same one.
+	pub rng_seed: u64,
+	pub preset: Preset,
+}
+
+pub static CHAPTERS: [Chapter; 10] = [
+	Chapter {
+		name: "First Harvest",
+		scenario: &CAMPAIGN[0],
+		rng_seed: 1101,
+		preset: Preset::Classic,
+	},
+	Chapter {
+		name: "Village Green",
+		scenario: &CAMPAIGN[1],
+		rng_seed: 2317,
+		preset: Preset::CoastalPlain,
+	},
+	Chapter {
+		name: "Wine Country",
+		scenario: &CAMPAIGN[2],
+		rng_seed: 3044,
+		preset: Preset::Highlands,
+	},
+	Chapter {
+		name: "The Isles",
+		scenario: &CAMPAIGN[3],
+		rng_seed: 4260,
+		preset: Preset::Archipelago,
+	},
+	Chapter {
+		name: "Timber",
+		scenario: &CAMPAIGN[4],
+		rng_seed: 5512,
+		preset: Preset::ForestRealm,
+	},
+	Chapter {
+		name: "Heavy Tribute",
+		scenario: &CAMPAIGN[5],
+		rng_seed: 6085,
+		preset: Preset::Classic,
+	},
+	Chapter {
+		name: "Landfall",
+		scenario: &CAMPAIGN[6],
+		rng_seed: 7731,
+		preset: Preset::CoastalPlain,
+	},
+	Chapter {
+		name: "Sacred Grove",
+		scenario: &CAMPAIGN[7],
+		rng_seed: 8197,
+		preset: Preset::ForestRealm,
+	},
+	Chapter {
+		name: "Occupation",
+		scenario: &CAMPAIGN[8],
+		rng_seed: 9403,
+		preset: Preset::Highlands,
+	},
+	Chapter {
+		name: "Last Stand",
+		scenario: &CAMPAIGN[9],
+		rng_seed: 10968,
+		preset: Preset::Classic,
+	},
+];
+
+impl Chapter
+{
+	/// The chapter that is played in this scenario, if any.
+	pub fn of(scenario: &Scenario) -> Option<usize>
+	{
+		// The scenarios of the campaign come right after the tutorials.
+		let chapter = scenario.id.checked_sub(CAMPAIGN[0].id)? as usize;
+		(chapter < CHAPTERS.len()).then_some(chapter)
+	}
+}
+
+/// Which chapters have been completed, one bit each.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress
+{
+	completed: u16,
+}
+
+impl Progress
+{
+	pub fn load() -> Self
+	{
+		let mut buffer = [0u8; 3];
+		disk::read(disk::CAMPAIGN, &mut buffer);
+		let completed = if buffer[0] == CAMPAIGN_FORMAT_VERSION
+		{
+			u16::from_le_bytes([buffer[1], buffer[2]])
+		}
+		else
+		{
+			0
+		};
+		Self { completed }
+	}
+
+	fn save(&self)
+	{
+		let [low, high] = self.completed.to_le_bytes();
+		disk::write(disk::CAMPAIGN, &[CAMPAIGN_FORMAT_VERSION, low, high]);
+	}
+
+	pub fn is_completed(&self, chapter: usize) -> bool
+	{
+		self.completed & (1 << chapter) != 0
+	}
+
+	pub fn is_unlocked(&self, chapter: usize) -> bool
+	{
+		chapter == 0 || self.is_completed(chapter - 1)
+	}
+
+	pub fn complete(&mut self, chapter: usize)
+	{
+		if !self.is_completed(chapter)
+		{
+			self.completed |= 1 << chapter;
+			self.save();
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::mock::Mock;
+	use crate::platform::run_with;
+
+	#[test]
+	fn chapters_unlock_in_order()
+	{
+		let mut mock = Mock::new();
+		run_with(&mut mock, || {
+			let mut progress = Progress::load();
+			assert!(progress.is_unlocked(0));
+			assert!(!progress.is_unlocked(1));
+			progress.complete(0);
+			let progress = Progress::load();
+			assert!(progress.is_completed(0));
+			assert!(progress.is_unlocked(1));
+			assert!(!progress.is_unlocked(2));
+		});
+		for (i, chapter) in CHAPTERS.iter().enumerate()
+		{
+			assert!(chapter.scenario.goal.is_some(), "{}", chapter.name);
+			let scenario = Scenario::from_id(chapter.scenario.id).unwrap();
+			assert!(std::ptr::eq(scenario, chapter.scenario));
+			assert_eq!(Chapter::of(scenario), Some(i));
+		}
+		assert_eq!(Chapter::of(&crate::scenario::GENERATED), None);
+	}
+}
@@ -4,10 +4,10 @@
 // License: MIT
 //
 
-use crate::wasm4::*;
+use crate::platform::*;
 
-use crate::level::Marker;
-use crate::level::TerrainType;
+use crate::rules::Marker;
+use crate::rules::TerrainType;
 use crate::sprites;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -18,25 +18,48 @@ pub enum AllOrNone
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum InOrNear
+pub enum Quantifier
+{
+	AtLeast,
+	AtMost,
+	Exactly,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Relation
 {
 	In,
 	Near,
+	As,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target
+{
+	Terrain(TerrainType),
+	Marker(Marker),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Decree
 {
-	Regional
+	Order
 	{
 		all_or_none: AllOrNone,
 		marker: Marker,
-		in_or_near: InOrNear,
+		relation: Relation,
+		target: Target,
+	},
+	/// Limits how many markers are placed in or near a terrain type each
+	/// day, i.e. with each shuffled deck of cards.
+	Count
+	{
+		quantifier: Quantifier,
+		count: u8,
+		marker: Marker,
+		relation: Relation,
 		terrain_type: TerrainType,
 	},
-	AllRomansAdjacent,
-	NoWorkersAdjacent,
-	NoRomansInAmbush,
 	Dummy,
 }
 
@@ -51,37 +74,29 @@ impl Decree
 	{
 		match self
 		{
-			Decree::Regional {
+			Decree::Order {
 				all_or_none,
 				marker,
-				in_or_near,
-				terrain_type,
+				relation,
+				target,
 			} => [
 				all_or_none.into(),
 				marker.into(),
-				in_or_near.into(),
-				terrain_type.into(),
-				Part::Period,
-			],
-			Decree::AllRomansAdjacent => [
-				AllOrNone::All.into(),
-				Marker::Roman.into(),
-				InOrNear::Near.into(),
-				Marker::Roman.into(),
-				Part::Period,
-			],
-			Decree::NoWorkersAdjacent => [
-				AllOrNone::None.into(),
-				Marker::Worker.into(),
-				InOrNear::Near.into(),
-				Marker::Worker.into(),
+				relation.into(),
+				target.into(),
 				Part::Period,
 			],
-			Decree::NoRomansInAmbush => [
-				AllOrNone::None.into(),
-				Marker::Roman.into(),
-				Part::Word("as"),
-				Marker::DeadRoman.into(),
+			Decree::Count {
+				quantifier,
+				count,
+				marker,
+				relation,
+				terrain_type,
+			} => [
+				Part::Quantity(*quantifier, *count),
+				marker.into(),
+				relation.into(),
+				terrain_type.into(),
 				Part::Period,
 			],
 			Decree::Dummy => [
@@ -96,237 +111,50 @@ impl Decree
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum Tutorial
+pub enum Issue
 {
-	PlaceBanners,
-	FeedWorkers,
-	Harvest,
-	FirstKill,
-	RomansHaveCome,
-	Tribute,
-	PlaceRomans,
-	PlaceRomans2,
-	Village,
+	/// Always issued, before the drawn decrees.
+	First,
+	/// Drawn from the deck, adding this much to the difficulty.
+	Drawn
+	{
+		weight: u8
+	},
+	/// Always issued, after the drawn decrees.
+	Last,
 }
 
-impl Tutorial
+/// A decree as described in assets/decrees.txt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecreeDefinition
 {
-	pub fn draw(&self, x: i32, y: i32)
+	pub is_tutorial: bool,
+	pub min_threat: u8,
+	pub max_threat: u8,
+	pub min_year: u8,
+	pub issue: Issue,
+	pub decree: Decree,
+}
+
+impl DecreeDefinition
+{
+	pub fn is_in_play(
+		&self,
+		is_tutorial: bool,
+		threat_level: u8,
+		year: u8,
+	) -> bool
 	{
-		match self
-		{
-			Tutorial::PlaceBanners =>
-			{
-				let parts = &[
-					Part::Word("Greetings,"),
-					Part::Newline,
-					Part::Word("your highness!"),
-					Part::Newline,
-					Part::Newline,
-					Part::Word("Place"),
-					Marker::Worker.into(),
-					Part::Word("on"),
-					Icon::Town.into(),
-					Part::Newline,
-					Part::Word("to score 1"),
-					Icon::Score.into(),
-					Part::Newline,
-					Part::Word("and gather"),
-					Part::Newline,
-					Part::Word("1"),
-					Icon::Grain.into(),
-					Part::Word("/"),
-					Icon::Wood.into(),
-					Part::Word("/"),
-					Icon::Gold.into(),
-					Part::Word("/"),
-					Icon::Wine.into(),
-					Part::Period,
-					Part::Newline,
-					Part::Newline,
-					Part::Word("After 4"),
-					Marker::Worker.into(),
-					Part::Newline,
-					Part::Word("a new day"),
-					Part::Newline,
-					Part::Word("begins."),
-				];
-				draw_parts(parts, x, y);
-			}
-			Tutorial::FeedWorkers =>
-			{
-				let parts = &[
-					Part::Word("Each"),
-					Marker::Worker.into(),
-					Part::Word("consumes"),
-					Part::Newline,
-					Part::Word("1"),
-					Icon::Grain.into(),
-					Part::Word(" (or 1"),
-					Icon::Wine.into(),
-					Part::Word(")"),
-					Part::Newline,
-					Part::Word("per day."),
-					Part::Newline,
-					Part::Newline,
-					Part::Word("Keep placing"),
-					Marker::Worker.into(),
-					Part::Newline,
-					Part::Word("until the map"),
-					Part::Newline,
-					Part::Word("is full."),
-					Part::Newline,
-					Part::Newline,
-					Part::Word("(Hover the"),
-					Part::Newline,
-					Part::Word("resource bar"),
-					Part::Newline,
-					Part::Word("for hints.)"),
-				];
-				draw_parts(parts, x, y);
-			}
-			Tutorial::Harvest =>
-			{
-				let parts = &[
-					Part::Word("Wonderful!"),
-					Part::Newline,
-					Part::Newline,
-					Part::Word("During harvest"),
-					Part::Newline,
-					Part::Word("each"),
-					Marker::Worker.into(),
-					Part::Word("gathers"),
-					Part::Newline,
-					Part::Word("an additional"),
-					Part::Newline,
-					Part::Word("1"),
-					Icon::Grain.into(),
-					Part::Word("/"),
-					Icon::Wood.into(),
-					Part::Word("/"),
-					Icon::Gold.into(),
-					Part::Word("/"),
-					Icon::Wine.into(),
-					Part::Period,
-				];
-				draw_parts(parts, x, y);
-			}
-			Tutorial::FirstKill =>
-			{
-				let parts = &[
-					Part::Word("Ah!"),
-					Part::Newline,
-					Part::Newline,
-					Part::Word("Your grace,"),
-					Part::Newline,
-					Part::Word("one of your"),
-					Marker::Worker.into(),
-					Part::Newline,
-					Part::Word("seems to have"),
-					Part::Newline,
-					Part::Word("fought and"),
-					Part::Newline,
-					Part::Word("killed a"),
-					Marker::Roman.into(),
-					Part::Period,
-					Part::Newline,
-					Part::Newline,
-					Part::Word("Let us pray"),
-					Part::Newline,
-					Part::Word("the Romans"),
-					Part::Newline,
-					Part::Word("forgive you..."),
-				];
-				draw_parts(parts, x, y);
-			}
-			Tutorial::RomansHaveCome =>
-			{
-				let parts = &[
-					Part::Word("The Romans"),
-					Part::Newline,
-					Part::Word("have come!"),
-					Part::Newline,
-					Part::Newline,
-					Part::Word("Your highness,"),
-					Part::Newline,
-					Part::Word("a"),
-					Marker::Roman.into(),
-					Part::Word("has seized"),
-					Part::Newline,
-					Part::Word("control of the"),
-					Part::Newline,
-					Part::Word("beach."),
-					Part::Newline,
-					Part::Word("Our"),
-					Marker::Worker.into(),
-					Part::Word("cannot"),
-					Part::Newline,
-					Part::Word("defeat"),
-					Marker::Roman.into(),
-					Part::Word("in"),
-					Part::Newline,
-					Part::Word("a region in"),
-					Part::Newline,
-					Part::Word("Roman hands."),
-				];
-				draw_parts(parts, x, y);
-			}
-			Tutorial::Tribute =>
-			{
-				let parts = &[
-					Part::Word("The Romans"),
-					Part::Newline,
-					Part::Word("demand that"),
-					Part::Newline,
-					Part::Word("a tribute of"),
-					Part::Newline,
-					Part::Word("15"),
-					Icon::Wine.into(),
-					Part::Word("is paid."),
-					Part::Newline,
-					Part::Newline,
-					Part::Word("What they ask"),
-					Part::Newline,
-					Part::Word("is impossible!"),
-				];
-				draw_parts(parts, x, y);
-			}
-			Tutorial::PlaceRomans | Tutorial::PlaceRomans2 =>
-			{
-				let parts = &[
-					Part::Word("Each day, we"),
-					Part::Newline,
-					Part::Word("must now also"),
-					Part::Newline,
-					Part::Word("place"),
-					Marker::Roman.into(),
-					Part::Word("on"),
-					Icon::Town.into(),
-					Part::Newline,
-					Part::Word("according to"),
-					Part::Newline,
-					Part::Word("their decrees."),
-					Part::Newline,
-					Part::Newline,
-					Part::Word("If we do not"),
-					Part::Newline,
-					Part::Word("obey, the"),
-					Part::Newline,
-					Part::Word("Roman Emperor"),
-					Part::Newline,
-					Part::Word("will send more"),
-					Part::Newline,
-					Marker::Roman.into(),
-					Part::Period,
-				];
-				draw_parts(parts, x, y);
-			}
-			Tutorial::Village => (),
-		}
+		self.is_tutorial == is_tutorial
+			&& threat_level >= self.min_threat
+			&& threat_level <= self.max_threat
+			&& year >= self.min_year
 	}
 }
 
-fn draw_parts(parts: &[Part], x: i32, y: i32) -> i32
+include!(concat!(env!("OUT_DIR"), "/decrees.rs"));
+
+pub fn draw_parts(parts: &[Part], x: i32, y: i32) -> i32
 {
 	let mut dx = 0;
 	let mut dy = 0;
@@ -346,8 +174,8 @@ fn draw_parts(parts: &[Part], x: i32, y: i32) -> i32
 	dx
 }
 
-#[derive(Debug, Clone, Copy)]
-enum Icon
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Icon
 {
 	Score,
 	Grain,
@@ -357,13 +185,14 @@ enum Icon
 	Town,
 }
 
-#[derive(Debug)]
-enum Part
+#[derive(Debug, PartialEq)]
+pub enum Part
 {
 	Word(&'static str),
 	Marker(Marker),
 	TerrainType(TerrainType),
 	Icon(Icon),
+	Quantity(Quantifier, u8),
 	Period,
 	Newline,
 }
@@ -380,14 +209,27 @@ impl From<AllOrNone> for Part
 	}
 }
 
-impl From<InOrNear> for Part
+impl From<Relation> for Part
+{
+	fn from(x: Relation) -> Part
+	{
+		match x
+		{
+			Relation::In => Part::Word("in"),
+			Relation::Near => Part::Word("near"),
+			Relation::As => Part::Word("as"),
+		}
+	}
+}
+
+impl From<Target> for Part
 {
-	fn from(x: InOrNear) -> Part
+	fn from(x: Target) -> Part
 	{
 		match x
 		{
-			InOrNear::In => Part::Word("in"),
-			InOrNear::Near => Part::Word("near"),
+			Target::Terrain(terrain_type) => terrain_type.into(),
+			Target::Marker(marker) => marker.into(),
 		}
 	}
 }
@@ -432,13 +274,40 @@ impl Part
 		{
 			Part::Word(word) =>
 			{
-				unsafe { *DRAW_COLORS = 0x03 };
+				set_draw_colors(0x03);
 				text(word, x, y);
 				(word.len() as i32) * 8 + 4
 			}
+			Part::Quantity(quantifier, count) =>
+			{
+				let mut buffer = *b">=  ";
+				let mut len = 2;
+				match quantifier
+				{
+					Quantifier::AtLeast => (),
+					Quantifier::AtMost => buffer[0] = b'<',
+					Quantifier::Exactly =>
+					{
+						buffer[0] = b'=';
+						len = 1;
+					}
+				}
+				if *count >= 10
+				{
+					buffer[len] = b'0' + count / 10;
+					len += 1;
+				}
+				buffer[len] = b'0' + count % 10;
+				len += 1;
+				let word =
+					unsafe { std::str::from_utf8_unchecked(&buffer[..len]) };
+				set_draw_colors(0x03);
+				text(word, x, y);
+				(len as i32) * 8 + 4
+			}
 			Part::Period =>
 			{
-				unsafe { *DRAW_COLORS = 0x03 };
+				set_draw_colors(0x03);
 				text(".", x - 4, y);
 				4
 			}
@@ -454,13 +323,13 @@ impl Part
 					Marker::Occupied => 0,
 					Marker::FogOfWar => 3,
 				};
-				unsafe { *DRAW_COLORS = 0x3210 };
+				set_draw_colors(0x3210);
 				sprites::draw_flag(x + 2, y + 8, flag);
 				10
 			}
 			Part::TerrainType(TerrainType::Village) =>
 			{
-				unsafe { *DRAW_COLORS = 0x1320 };
+				set_draw_colors(0x1320);
 				sprites::draw_house(x, y + 5, 0);
 				sprites::draw_house(x + 4, y + 6, 0);
 				sprites::draw_house(x + 8, y + 5, 0);
@@ -468,7 +337,7 @@ impl Part
 			}
 			Part::TerrainType(TerrainType::Grass) =>
 			{
-				unsafe { *DRAW_COLORS = 0x1320 };
+				set_draw_colors(0x1320);
 				sprites::draw_grass(x, y + 1, 0);
 				sprites::draw_grass(x + 4, y + 6, 4);
 				sprites::draw_grass(x + 8, y + 1, 5);
@@ -476,7 +345,7 @@ impl Part
 			}
 			Part::TerrainType(TerrainType::Forest) =>
 			{
-				unsafe { *DRAW_COLORS = 0x1320 };
+				set_draw_colors(0x1320);
 				sprites::draw_tree(x, y + 6, 0);
 				sprites::draw_tree(x + 4, y + 7, 0);
 				sprites::draw_tree(x + 8, y + 6, 0);
@@ -484,7 +353,7 @@ impl Part
 			}
 			Part::TerrainType(TerrainType::Hill) =>
 			{
-				unsafe { *DRAW_COLORS = 0x1320 };
+				set_draw_colors(0x1320);
 				sprites::draw_hill(x, y + 4, 1);
 				sprites::draw_hill(x + 3, y + 8, 0);
 				sprites::draw_hill(x + 8, y + 3, 2);
@@ -492,13 +361,13 @@ impl Part
 			}
 			Part::TerrainType(TerrainType::Mountain) =>
 			{
-				unsafe { *DRAW_COLORS = 0x1320 };
+				set_draw_colors(0x1320);
 				sprites::draw_mountain(x + 2, y + 8, 0);
 				12
 			}
 			Part::TerrainType(TerrainType::Water) =>
 			{
-				unsafe { *DRAW_COLORS = 0x20 };
+				set_draw_colors(0x20);
 				sprites::draw_surface(x + 4, y, 0);
 				sprites::draw_surface(x + 2, y + 2, 0);
 				sprites::draw_surface(x + 6, y + 2, 0);
@@ -506,46 +375,160 @@ impl Part
 				sprites::draw_surface(x + 2, y + 6, 0);
 				sprites::draw_surface(x + 6, y + 6, 0);
 				sprites::draw_surface(x + 4, y + 8, 0);
-				unsafe { *DRAW_COLORS = 0x1320 };
+				set_draw_colors(0x1320);
 				sprites::draw_boat(x + 4, y + 4, 0);
 				16
 			}
 			Part::Icon(Icon::Score) =>
 			{
-				unsafe { *DRAW_COLORS = 0x3210 };
+				set_draw_colors(0x3210);
 				sprites::draw_score_icon(x - 4, y - 1);
 				8
 			}
 			Part::Icon(Icon::Grain) =>
 			{
-				unsafe { *DRAW_COLORS = 0x3210 };
+				set_draw_colors(0x3210);
 				sprites::draw_grain_icon(x - 2, y - 1);
 				8
 			}
 			Part::Icon(Icon::Wood) =>
 			{
-				unsafe { *DRAW_COLORS = 0x3210 };
+				set_draw_colors(0x3210);
 				sprites::draw_wood_icon(x - 2, y - 1);
 				8
 			}
 			Part::Icon(Icon::Wine) =>
 			{
-				unsafe { *DRAW_COLORS = 0x3210 };
+				set_draw_colors(0x3210);
 				sprites::draw_wine_icon(x - 2, y - 1);
 				8
 			}
 			Part::Icon(Icon::Gold) =>
 			{
-				unsafe { *DRAW_COLORS = 0x3210 };
+				set_draw_colors(0x3210);
 				sprites::draw_gold_icon(x - 2, y - 1);
 				8
 			}
 			Part::Icon(Icon::Town) =>
 			{
-				unsafe { *DRAW_COLORS = 0x2310 };
+				set_draw_colors(0x2310);
 				sprites::draw_hovered_town(x + 2, y + 2);
 				16
 			}
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::framebuffer::assert_snapshot;
+	use crate::mock::Mock;
+	use crate::platform::run_with;
+
+	#[test]
+	fn decrees()
+	{
+		let order = |all_or_none, marker, relation, target| Decree::Order {
+			all_or_none,
+			marker,
+			relation,
+			target,
+		};
+		let decrees = [
+			order(
+				AllOrNone::All,
+				Marker::Worker,
+				Relation::Near,
+				Target::Terrain(TerrainType::Water),
+			),
+			order(
+				AllOrNone::None,
+				Marker::Roman,
+				Relation::In,
+				Target::Terrain(TerrainType::Forest),
+			),
+			order(
+				AllOrNone::None,
+				Marker::Worker,
+				Relation::In,
+				Target::Terrain(TerrainType::Mountain),
+			),
+			order(
+				AllOrNone::All,
+				Marker::Roman,
+				Relation::Near,
+				Target::Marker(Marker::Roman),
+			),
+			order(
+				AllOrNone::None,
+				Marker::Worker,
+				Relation::Near,
+				Target::Marker(Marker::Worker),
+			),
+			order(
+				AllOrNone::None,
+				Marker::Roman,
+				Relation::As,
+				Target::Marker(Marker::DeadRoman),
+			),
+			Decree::Count {
+				quantifier: Quantifier::AtMost,
+				count: 2,
+				marker: Marker::Roman,
+				relation: Relation::Near,
+				terrain_type: TerrainType::Forest,
+			},
+			Decree::Count {
+				quantifier: Quantifier::Exactly,
+				count: 12,
+				marker: Marker::Worker,
+				relation: Relation::In,
+				terrain_type: TerrainType::Hill,
+			},
+		];
+		let mut mock = Mock::new();
+		run_with(&mut mock, || {
+			let mut y = 10;
+			for decree in decrees
+			{
+				set_draw_colors(3);
+				decree.draw(5, y);
+				y += 15;
+			}
+		});
+		assert_snapshot(&mock.screen, "decrees");
+	}
+	#[test]
+	fn decree_deck()
+	{
+		assert!(DECREE_DECK.iter().any(|&i| matches!(
+			DECREES[i as usize].issue,
+			Issue::Drawn { .. }
+		)));
+		for i in DECREE_DECK
+		{
+			let definition = &DECREES[i as usize];
+			assert!(!definition.is_tutorial);
+			assert_ne!(definition.issue, Issue::First);
+		}
+		let num_in_deck = DECREES
+			.iter()
+			.filter(|d| !d.is_tutorial && d.issue != Issue::First)
+			.count();
+		assert_eq!(num_in_deck, DECREE_DECK_SIZE);
+	}
+	#[test]
+	fn decrees_wait_for_their_year()
+	{
+		let definition = DecreeDefinition {
+			min_year: 3,
+			..DECREES[DECREE_DECK[0] as usize]
+		};
+		let threat_level = definition.min_threat;
+		assert!(!definition.is_in_play(false, threat_level, 2));
+		assert!(definition.is_in_play(false, threat_level, 3));
+		assert!(definition.is_in_play(false, threat_level, 4));
+	}
+}
@@ -0,0 +1,166 @@
+//
+// Part of the-romans-are-coming
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+//! The layout of the 1024 bytes of persistent storage. WASM-4 can only read
+//! and write the disk as a whole, so each part of the game that wants to
+//! store something gets its own fixed section and we patch just that part.
+
+use crate::platform::{diskr, diskw};
+
+pub const DISK_SIZE: usize = 1024;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Section
+{
+	pub offset: usize,
+	pub size: usize,
+}
+
+pub const SAVED_GAME: Section = Section {
+	offset: 0,
+	size: 192,
+};
+
+pub const SCORES: Section = Section {
+	offset: 192,
+	size: 160,
+};
+
+pub const DAILY: Section = Section {
+	offset: 352,
+	size: 80,
+};
+
+pub const SETTINGS: Section = Section {
+	offset: 432,
+	size: 16,
+};
+
+pub const CAMPAIGN: Section = Section {
+	offset: 448,
+	size: 16,
+};
+
+/// Copies the section into `dest`. Bytes that were never written are 0.
+pub fn read(section: Section, dest: &mut [u8])
+{
+	let mut disk = [0u8; DISK_SIZE];
+	diskr(&mut disk);
+	let source = disk.iter().skip(section.offset).take(section.size);
+	for (byte, value) in dest.iter_mut().zip(source)
+	{
+		*byte = *value;
+	}
+}
+
+/// Overwrites the section with `src`, padded with zeroes.
+pub fn write(section: Section, src: &[u8])
+{
+	let mut disk = [0u8; DISK_SIZE];
+	diskr(&mut disk);
+	let padded = src.iter().chain(std::iter::repeat(&0));
+	let target = disk.iter_mut().skip(section.offset).take(section.size);
+	for (byte, value) in target.zip(padded)
+	{
+		*byte = *value;
+	}
+	diskw(&disk);
+}
+
+pub fn erase(section: Section)
+{
+	write(section, &[]);
+}
+
+pub struct Writer<'a>
+{
+	buffer: &'a mut [u8],
+	offset: usize,
+}
+
+impl<'a> Writer<'a>
+{
+	pub fn new(buffer: &'a mut [u8]) -> Self
+	{
+		Self { buffer, offset: 0 }
+	}
+
+	pub fn write_u8(&mut self, value: u8)
+	{
+		self.buffer[self.offset] = value;
+		self.offset += 1;
+	}
+
+	pub fn write_u16(&mut self, value: u16)
+	{
+		self.write_le(value.into(), 2);
+	}
+
+	pub fn write_u32(&mut self, value: u32)
+	{
+		self.write_le(value.into(), 4);
+	}
+
+	pub fn write_u64(&mut self, value: u64)
+	{
+		self.write_le(value, 8);
+	}
+
+	fn write_le(&mut self, value: u64, num_bytes: usize)
+	{
+		for i in 0..num_bytes
+		{
+			self.write_u8((value >> (8 * i)) as u8);
+		}
+	}
+}
+
+/// The counterpart of Writer; reading past the end gives None.
+pub struct Reader<'a>
+{
+	buffer: &'a [u8],
+	offset: usize,
+}
+
+impl<'a> Reader<'a>
+{
+	pub fn new(buffer: &'a [u8]) -> Self
+	{
+		Self { buffer, offset: 0 }
+	}
+
+	pub fn read_u8(&mut self) -> Option<u8>
+	{
+		let value = *self.buffer.get(self.offset)?;
+		self.offset += 1;
+		Some(value)
+	}
+
+	pub fn read_u16(&mut self) -> Option<u16>
+	{
+		self.read_le(2).map(|value| value as u16)
+	}
+
+	pub fn read_u32(&mut self) -> Option<u32>
+	{
+		self.read_le(4).map(|value| value as u32)
+	}
+
+	pub fn read_u64(&mut self) -> Option<u64>
+	{
+		self.read_le(8)
+	}
+
+	fn read_le(&mut self, num_bytes: usize) -> Option<u64>
+	{
+		let mut value = 0;
+		for i in 0..num_bytes
+		{
+			value |= (self.read_u8()? as u64) << (8 * i);
+		}
+		Some(value)
+	}
+}
@@ -0,0 +1,544 @@
+//
+// Part of the-romans-are-coming
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+//! Exports generated maps as ASCII art, JSON and PNG images, so that many
+//! seeds can be browsed at once. Run it natively with the tool in tools/mapgen.
+
+use crate::framebuffer::Framebuffer;
+use crate::level::Cursor;
+use crate::map::{
+	DrawOptions, Map, Preset, BITMAP_SIZE, GRID_SIZE, MAP_SIZE, PRESETS,
+};
+use crate::map::{PROP_GRID_CELL_SIZE, PROP_GRID_SIZE};
+use crate::mock::Mock;
+use crate::palette;
+use crate::platform::run_with;
+use crate::rules::{TerrainType, MAX_NUM_REGIONS};
+
+use bitmaps::Bitmap;
+use std::fmt::Write;
+use std::path::PathBuf;
+
+const USAGE: &str = "\
+Usage: mapgen SEED[..END] [OPTIONS]
+
+Generates the map of each seed from SEED up to (but not including) END,
+writes it to the output directory as ASCII art (.txt), JSON (.json), a
+PNG image (.png) and a starting point for a map drawn by hand (.map), and
+prints a summary line per seed.
+
+Options:
+  --preset NAME   the kind of island, such as Archipelago (default: Classic)
+  --format LIST   the formats to write, such as txt,png (default: all)
+  --out DIR       the output directory (default: target/maps)";
+
+const FORMATS: [&str; 4] = ["txt", "json", "png", "map"];
+
+/// Each character of the ASCII bitmaps covers this many pixels.
+const ASCII_PIXEL_WIDTH: usize = 2;
+const ASCII_PIXEL_HEIGHT: usize = 4;
+
+pub struct ExportedMap
+{
+	pub seed: u64,
+	pub preset: Preset,
+	pub regions: Vec<RegionInfo>,
+	/// Anything the generator traced, such as "gobbling up headless".
+	pub warnings: Vec<String>,
+	map: Box<Map>,
+	screen: Framebuffer,
+}
+
+pub struct RegionInfo
+{
+	pub region_id: i8,
+	pub terrain_type: TerrainType,
+	pub centroid: (u8, u8),
+	pub neighbors: Vec<i8>,
+	pub is_on_border: bool,
+}
+
+/// Generates the map for this seed the same way a level does.
+pub fn generate(seed: u64, preset: Preset) -> ExportedMap
+{
+	let mut map = Box::new(Map::empty());
+	let mut adjacency = [Bitmap::<MAX_NUM_REGIONS>::new(); MAX_NUM_REGIONS];
+	let mut border_adjacency = Bitmap::new();
+	let mut mock = Mock::new();
+	run_with(&mut mock, || {
+		let mut rng = fastrand::Rng::with_seed(seed);
+		map.generate(preset.config(), &mut rng);
+		map.fill_adjacency(&mut adjacency, &mut border_adjacency);
+		palette::setup();
+		map.draw(&DrawOptions::default(), &Cursor::new());
+	});
+	let mut regions: Vec<RegionInfo> = map
+		.regions()
+		.map(|(region_id, terrain_type, _marker)| RegionInfo {
+			region_id,
+			terrain_type,
+			centroid: (0, 0),
+			neighbors: adjacency[region_id as usize]
+				.into_iter()
+				.map(|i| i as i8)
+				.collect(),
+			is_on_border: border_adjacency.get(region_id as usize),
+		})
+		.collect();
+	regions.sort_by_key(|region| region.region_id);
+	for (region_id, x, y) in map.region_centroids()
+	{
+		if let Some(region) = regions
+			.iter_mut()
+			.find(|region| region.region_id == region_id)
+		{
+			region.centroid = (x, y);
+		}
+	}
+	ExportedMap {
+		seed,
+		preset,
+		regions,
+		warnings: mock.traces,
+		map,
+		screen: mock.screen,
+	}
+}
+
+impl ExportedMap
+{
+	/// A one-line description, for browsing many seeds in a terminal.
+	pub fn summary(&self) -> String
+	{
+		let mut line = format!(
+			"{} {}: {} regions",
+			self.preset.name(),
+			self.seed,
+			self.regions.len()
+		);
+		for warning in &self.warnings
+		{
+			let _ = write!(line, "; warning: {}", warning);
+		}
+		line
+	}
+
+	pub fn to_ascii(&self) -> String
+	{
+		let mut out = String::new();
+		let _ = writeln!(out, "{}", self.summary());
+		out.push_str("\nTerrain:\n");
+		self.write_prop_grid(&mut out, |region_id, u, v| {
+			let c = match self.terrain_type(region_id)
+			{
+				Some(terrain_type) => terrain_symbol(terrain_type),
+				None if self.is_water_at_prop(u, v) => '~',
+				None => ' ',
+			};
+			format!("{}{}", c, c)
+		});
+		out.push_str("\nRegions:\n");
+		// Region ids are written as a single base 36 digit, so that the
+		// borders between regions stand out.
+		self.write_prop_grid(&mut out, |region_id, _u, _v| {
+			let c = u32::try_from(region_id)
+				.ok()
+				.and_then(|i| char::from_digit(i, 36))
+				.unwrap_or(' ');
+			format!("{}{}", c, c)
+		});
+		out.push_str("\n id terrain   centroid    neighbors\n");
+		for region in &self.regions
+		{
+			let _ = write!(
+				out,
+				"{:3} {:9} ({:3}, {:3}) ",
+				region.region_id,
+				format!("{:?}", region.terrain_type),
+				region.centroid.0,
+				region.centroid.1
+			);
+			for neighbor in &region.neighbors
+			{
+				let _ = write!(out, " {}", neighbor);
+			}
+			if region.is_on_border
+			{
+				out.push_str(" (border)");
+			}
+			out.push('\n');
+		}
+		for (name, bitmap) in self.map.bitmaps()
+		{
+			let _ = writeln!(out, "\nBitmap {}:", name);
+			write_ascii_bitmap(&mut out, bitmap);
+		}
+		out
+	}
+
+	pub fn to_json(&self) -> String
+	{
+		let mut out = String::new();
+		out.push_str("{\n");
+		let _ = writeln!(out, "\t\"seed\": {},", self.seed);
+		let _ =
+			writeln!(out, "\t\"preset\": {},", json_string(self.preset.name()));
+		let warnings: Vec<String> =
+			self.warnings.iter().map(|x| json_string(x)).collect();
+		let _ = writeln!(out, "\t\"warnings\": [{}],", warnings.join(", "));
+		out.push_str("\t\"regions\": [\n");
+		for (i, region) in self.regions.iter().enumerate()
+		{
+			let neighbors: Vec<String> =
+				region.neighbors.iter().map(|x| x.to_string()).collect();
+			let _ = write!(
+				out,
+				"\t\t{{\"id\": {}, \"terrain\": \"{:?}\", \
+				 \"centroid\": [{}, {}], \"neighbors\": [{}], \
+				 \"border\": {}}}",
+				region.region_id,
+				region.terrain_type,
+				region.centroid.0,
+				region.centroid.1,
+				neighbors.join(", "),
+				region.is_on_border
+			);
+			out.push_str(separator(i, self.regions.len()));
+		}
+		out.push_str("\t],\n");
+		out.push_str("\t\"region_grid\": [\n");
+		for v in 0..PROP_GRID_SIZE
+		{
+			let row: Vec<String> = (0..PROP_GRID_SIZE)
+				.map(|u| self.map.region_id_at_prop(u, v).to_string())
+				.collect();
+			let _ = write!(out, "\t\t[{}]", row.join(", "));
+			out.push_str(separator(v, PROP_GRID_SIZE));
+		}
+		out.push_str("\t],\n");
+		// Each row of a bitmap is written as hexadecimal bytes, with the
+		// leftmost pixel in the most significant bit.
+		out.push_str("\t\"bitmaps\": {\n");
+		let bitmaps = self.map.bitmaps();
+		for (i, (name, bitmap)) in bitmaps.iter().enumerate()
+		{
+			let _ = writeln!(out, "\t\t\"{}\": [", name);
+			for (y, row) in bitmap.chunks(MAP_SIZE / 8).enumerate()
+			{
+				out.push_str("\t\t\t\"");
+				for byte in row
+				{
+					let _ = write!(out, "{:02x}", byte);
+				}
+				out.push('"');
+				out.push_str(separator(y, MAP_SIZE));
+			}
+			out.push_str("\t\t]");
+			out.push_str(separator(i, bitmaps.len()));
+		}
+		out.push_str("\t}\n");
+		out.push_str("}\n");
+		out
+	}
+
+	/// The map in the format of assets/maps, to start drawing a map by hand.
+	/// The culled cells become open sea and the other cells get the terrain
+	/// of their region, which may then be divided differently.
+	pub fn to_authored(&self) -> String
+	{
+		let mut out = String::new();
+		let _ = writeln!(out, "# {}\n", self.summary());
+		for r in 0..GRID_SIZE
+		{
+			for c in 0..GRID_SIZE
+			{
+				out.push(match self.map.terrain_type_at_cell(r, c)
+				{
+					Some(TerrainType::Water) => 'w',
+					Some(terrain_type) => terrain_symbol(terrain_type),
+					None => '~',
+				});
+			}
+			out.push('\n');
+		}
+		out.push('\n');
+		for region in &self.regions
+		{
+			let (x, y) = region.centroid;
+			let _ = writeln!(out, "{:3} {:3}  # {}", x, y, region.region_id);
+		}
+		out
+	}
+
+	/// The map as it is drawn at the start of a level.
+	pub fn to_png(&self) -> Vec<u8>
+	{
+		self.screen.to_png()
+	}
+
+	fn terrain_type(&self, region_id: i8) -> Option<TerrainType>
+	{
+		self.regions
+			.iter()
+			.find(|region| region.region_id == region_id)
+			.map(|region| region.terrain_type)
+	}
+
+	fn is_water_at_prop(&self, u: usize, v: usize) -> bool
+	{
+		let (_name, water) = self.map.bitmaps()[0];
+		let x = u * PROP_GRID_CELL_SIZE + PROP_GRID_CELL_SIZE / 2;
+		let y = v * PROP_GRID_CELL_SIZE + PROP_GRID_CELL_SIZE / 2;
+		is_on_bitmap(water, x, y)
+	}
+
+	fn write_prop_grid(
+		&self,
+		out: &mut String,
+		cell: impl Fn(i8, usize, usize) -> String,
+	)
+	{
+		for v in 0..PROP_GRID_SIZE
+		{
+			for u in 0..PROP_GRID_SIZE
+			{
+				out.push_str(&cell(self.map.region_id_at_prop(u, v), u, v));
+			}
+			out.push('\n');
+		}
+	}
+}
+
+fn terrain_symbol(terrain_type: TerrainType) -> char
+{
+	match terrain_type
+	{
+		TerrainType::Village => 'o',
+		TerrainType::Grass => '.',
+		TerrainType::Forest => 'f',
+		TerrainType::Hill => 'n',
+		TerrainType::Mountain => 'A',
+		TerrainType::Water => '~',
+	}
+}
+
+fn is_on_bitmap(bitmap: &[u8; BITMAP_SIZE], x: usize, y: usize) -> bool
+{
+	let offset = y * MAP_SIZE + x;
+	bitmap[offset / 8] & (0b10000000 >> (offset % 8)) != 0
+}
+
+fn write_ascii_bitmap(out: &mut String, bitmap: &[u8; BITMAP_SIZE])
+{
+	let area = ASCII_PIXEL_WIDTH * ASCII_PIXEL_HEIGHT;
+	for y in (0..MAP_SIZE).step_by(ASCII_PIXEL_HEIGHT)
+	{
+		for x in (0..MAP_SIZE).step_by(ASCII_PIXEL_WIDTH)
+		{
+			let count = (0..area)
+				.filter(|i| {
+					let dx = i % ASCII_PIXEL_WIDTH;
+					let dy = i / ASCII_PIXEL_WIDTH;
+					is_on_bitmap(bitmap, x + dx, y + dy)
+				})
+				.count();
+			out.push(match count
+			{
+				0 => ' ',
+				n if 2 * n < area => ':',
+				_ => '#',
+			});
+		}
+		out.push('\n');
+	}
+}
+
+fn separator(i: usize, len: usize) -> &'static str
+{
+	if i + 1 < len
+	{
+		",\n"
+	}
+	else
+	{
+		"\n"
+	}
+}
+
+fn json_string(text: &str) -> String
+{
+	let mut out = String::from("\"");
+	for c in text.chars()
+	{
+		match c
+		{
+			'"' => out.push_str("\\\""),
+			'\\' => out.push_str("\\\\"),
+			c if c.is_control() =>
+			{
+				let _ = write!(out, "\\u{:04x}", c as u32);
+			}
+			c => out.push(c),
+		}
+	}
+	out.push('"');
+	out
+}
+
+/// Parses "12" as a single seed and "100..200" or "100..=199" as a range.
+fn parse_seeds(text: &str) -> Option<std::ops::Range<u64>>
+{
+	match text.split_once("..")
+	{
+		None =>
+		{
+			let seed: u64 = text.parse().ok()?;
+			Some(seed..(seed + 1))
+		}
+		Some((start, end)) =>
+		{
+			let start = start.parse().ok()?;
+			let end = match end.strip_prefix('=')
+			{
+				Some(last) => last.parse::<u64>().ok()? + 1,
+				None => end.parse().ok()?,
+			};
+			Some(start..end)
+		}
+	}
+}
+
+/// Accepts "Forest Realm" as well as "forestrealm" or "forest-realm".
+fn parse_preset(text: &str) -> Option<Preset>
+{
+	let name = simplify(text);
+	PRESETS
+		.iter()
+		.copied()
+		.find(|preset| simplify(preset.name()) == name)
+}
+
+/// Turns "Forest Realm" into "forestrealm".
+fn simplify(name: &str) -> String
+{
+	name.chars()
+		.filter(|c| c.is_ascii_alphanumeric())
+		.map(|c| c.to_ascii_lowercase())
+		.collect()
+}
+
+/// Runs the map export tool with the given command line arguments.
+pub fn run(args: &[String]) -> Result<(), String>
+{
+	let mut seeds = None;
+	let mut preset = Preset::Classic;
+	let mut formats = FORMATS.to_vec();
+	let mut out = PathBuf::from("target/maps");
+	let mut args = args.iter();
+	while let Some(arg) = args.next()
+	{
+		let mut value = || {
+			args.next().ok_or_else(|| {
+				format!("Missing value for {}\n\n{}", arg, USAGE)
+			})
+		};
+		match arg.as_str()
+		{
+			"-h" | "--help" =>
+			{
+				println!("{}", USAGE);
+				return Ok(());
+			}
+			"--preset" =>
+			{
+				let name = value()?;
+				preset = parse_preset(name)
+					.ok_or_else(|| format!("Unknown preset '{}'", name))?;
+			}
+			"--format" =>
+			{
+				formats = value()?.split(',').collect();
+				if let Some(x) = formats.iter().find(|x| !FORMATS.contains(x))
+				{
+					return Err(format!("Unknown format '{}'", x));
+				}
+			}
+			"--out" => out = PathBuf::from(value()?),
+			_ if seeds.is_none() =>
+			{
+				seeds = Some(
+					parse_seeds(arg)
+						.ok_or_else(|| format!("Invalid seed '{}'", arg))?,
+				);
+			}
+			_ => return Err(format!("Unexpected '{}'\n\n{}", arg, USAGE)),
+		}
+	}
+	let seeds = seeds.ok_or_else(|| USAGE.to_string())?;
+
+	std::fs::create_dir_all(&out).map_err(|e| e.to_string())?;
+	let prefix = simplify(preset.name());
+	for seed in seeds
+	{
+		let exported = generate(seed, preset);
+		println!("{}", exported.summary());
+		for format in &formats
+		{
+			let contents = match *format
+			{
+				"txt" => exported.to_ascii().into_bytes(),
+				"json" => exported.to_json().into_bytes(),
+				"map" => exported.to_authored().into_bytes(),
+				_ => exported.to_png(),
+			};
+			let path = out.join(format!("{}_{}.{}", prefix, seed, format));
+			std::fs::write(&path, contents)
+				.map_err(|e| format!("{}: {}", path.display(), e))?;
+		}
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	#[test]
+	fn export_describes_every_region()
+	{
+		let exported = generate(12, Preset::Classic);
+		assert!(exported.regions.len() > 20);
+		for region in &exported.regions
+		{
+			for neighbor in &region.neighbors
+			{
+				let other = &exported.regions[*neighbor as usize];
+				assert_eq!(other.region_id, *neighbor);
+				assert!(other.neighbors.contains(&region.region_id));
+			}
+		}
+		let ascii = exported.to_ascii();
+		assert!(ascii.starts_with("Classic 12: "));
+		assert!(ascii.contains("Bitmap occupation:"));
+		let json = exported.to_json();
+		assert!(json.contains("\"seed\": 12,"));
+		assert_eq!(json.matches("\"terrain\"").count(), exported.regions.len());
+		assert!(exported.to_png().starts_with(b"\x89PNG"));
+	}
+
+	#[test]
+	fn parse_arguments()
+	{
+		assert_eq!(parse_seeds("12"), Some(12..13));
+		assert_eq!(parse_seeds("100..200"), Some(100..200));
+		assert_eq!(parse_seeds("100..=199"), Some(100..200));
+		assert_eq!(parse_seeds("a..b"), None);
+		assert_eq!(parse_preset("forest-realm"), Some(Preset::ForestRealm));
+		assert_eq!(parse_preset("Coastal Plain"), Some(Preset::CoastalPlain));
+		assert_eq!(parse_preset("desert"), None);
+	}
+}
@@ -0,0 +1,589 @@
+//
+// Part of the-romans-are-coming
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+//! A software version of the WASM-4 framebuffer: 160x160 pixels with 2 bits
+//! per pixel, drawn to with the same DRAW_COLORS semantics as the console,
+//! so that screens can be rendered natively and saved as PNG files.
+
+#![allow(unused)]
+
+use crate::platform::SCREEN_SIZE;
+use crate::platform::{BLIT_2BPP, BLIT_FLIP_X, BLIT_FLIP_Y, BLIT_ROTATE};
+
+const WIDTH: i32 = SCREEN_SIZE as i32;
+const HEIGHT: i32 = SCREEN_SIZE as i32;
+const NUM_BYTES: usize = (SCREEN_SIZE * SCREEN_SIZE / 4) as usize;
+
+pub const DEFAULT_PALETTE: [u32; 4] = [0xe0f8cf, 0x86c06c, 0x306850, 0x071821];
+pub const DEFAULT_DRAW_COLORS: u16 = 0x1203;
+
+pub struct Framebuffer
+{
+	pub palette: [u32; 4],
+	pub draw_colors: u16,
+	bytes: [u8; NUM_BYTES],
+}
+
+impl Framebuffer
+{
+	pub fn new() -> Self
+	{
+		Self {
+			palette: DEFAULT_PALETTE,
+			draw_colors: DEFAULT_DRAW_COLORS,
+			bytes: [0; NUM_BYTES],
+		}
+	}
+
+	/// Fills the screen with the first palette color, like the console does
+	/// at the start of each frame.
+	pub fn clear(&mut self)
+	{
+		self.bytes = [0; NUM_BYTES];
+	}
+
+	/// Returns the palette index (0 through 3) of the pixel at (x, y).
+	pub fn pixel(&self, x: i32, y: i32) -> u8
+	{
+		let offset = (y * WIDTH + x) as usize;
+		let shift = (offset & 0x3) << 1;
+		(self.bytes[offset >> 2] >> shift) & 0x3
+	}
+
+	fn draw_point(&mut self, color: u8, x: i32, y: i32)
+	{
+		if !(0..WIDTH).contains(&x) || !(0..HEIGHT).contains(&y)
+		{
+			return;
+		}
+		let offset = (y * WIDTH + x) as usize;
+		let shift = (offset & 0x3) << 1;
+		let mask = 0x3 << shift;
+		let byte = &mut self.bytes[offset >> 2];
+		*byte = (color << shift) | (*byte & !mask);
+	}
+
+	/// Draws the pixel with the color that the n-th nibble of DRAW_COLORS
+	/// refers to, or nothing if that nibble is 0 (transparent).
+	fn draw_point_with_nibble(&mut self, n: u8, x: i32, y: i32)
+	{
+		let dc = ((self.draw_colors >> (n << 2)) & 0xf) as u8;
+		if dc != 0
+		{
+			self.draw_point((dc - 1) & 0x3, x, y);
+		}
+	}
+
+	fn draw_hline_with_nibble(&mut self, n: u8, x: i32, y: i32, end_x: i32)
+	{
+		for xx in x..end_x
+		{
+			self.draw_point_with_nibble(n, xx, y);
+		}
+	}
+
+	#[allow(clippy::too_many_arguments)]
+	pub fn blit_sub(
+		&mut self,
+		sprite: &[u8],
+		x: i32,
+		y: i32,
+		width: u32,
+		height: u32,
+		src_x: u32,
+		src_y: u32,
+		stride: u32,
+		flags: u32,
+	)
+	{
+		let width = width as i32;
+		let height = height as i32;
+		let is_2bpp = flags & BLIT_2BPP != 0;
+		let flip_y = flags & BLIT_FLIP_Y != 0;
+		let rotate = flags & BLIT_ROTATE != 0;
+		let flip_x = (flags & BLIT_FLIP_X != 0) != rotate;
+
+		let (clip_x_min, clip_y_min, clip_x_max, clip_y_max) = if rotate
+		{
+			(
+				std::cmp::max(0, y) - y,
+				std::cmp::max(0, x) - x,
+				std::cmp::min(width, HEIGHT - y),
+				std::cmp::min(height, WIDTH - x),
+			)
+		}
+		else
+		{
+			(
+				std::cmp::max(0, x) - x,
+				std::cmp::max(0, y) - y,
+				std::cmp::min(width, WIDTH - x),
+				std::cmp::min(height, HEIGHT - y),
+			)
+		};
+
+		for v in clip_y_min..clip_y_max
+		{
+			for u in clip_x_min..clip_x_max
+			{
+				let tx = x + if rotate { v } else { u };
+				let ty = y + if rotate { u } else { v };
+				let sx = src_x as i32 + if flip_x { width - u - 1 } else { u };
+				let sy = src_y as i32 + if flip_y { height - v - 1 } else { v };
+				let bit_index = (sy * stride as i32 + sx) as usize;
+				let color_index = if is_2bpp
+				{
+					let byte = sprite[bit_index >> 2];
+					let shift = 6 - ((bit_index & 0x3) << 1);
+					(byte >> shift) & 0x3
+				}
+				else
+				{
+					let byte = sprite[bit_index >> 3];
+					let shift = 7 - (bit_index & 0x7);
+					(byte >> shift) & 0x1
+				};
+				self.draw_point_with_nibble(color_index, tx, ty);
+			}
+		}
+	}
+
+	pub fn line(&mut self, x1: i32, y1: i32, x2: i32, y2: i32)
+	{
+		let (mut x1, mut y1, x2, y2) = if y1 > y2
+		{
+			(x2, y2, x1, y1)
+		}
+		else
+		{
+			(x1, y1, x2, y2)
+		};
+		let dx = (x2 - x1).abs();
+		let sx = if x1 < x2 { 1 } else { -1 };
+		let dy = y2 - y1;
+		let mut err = if dx > dy { dx } else { -dy } / 2;
+		loop
+		{
+			self.draw_point_with_nibble(0, x1, y1);
+			if x1 == x2 && y1 == y2
+			{
+				break;
+			}
+			let e2 = err;
+			if e2 > -dx
+			{
+				err -= dy;
+				x1 += sx;
+			}
+			if e2 < dy
+			{
+				err += dx;
+				y1 += 1;
+			}
+		}
+	}
+
+	pub fn oval(&mut self, x: i32, y: i32, width: u32, height: u32)
+	{
+		let width = width as i32;
+		let height = height as i32;
+		if width <= 0 || height <= 0
+		{
+			return;
+		}
+		// Work in doubled coordinates so that the center is a whole number.
+		let cx = 2 * x + width;
+		let cy = 2 * y + height;
+		let rx = width as i64;
+		let ry = height as i64;
+		let is_inside = |px: i32, py: i32| -> bool {
+			if px < x || px >= x + width || py < y || py >= y + height
+			{
+				return false;
+			}
+			let dx = (2 * px + 1 - cx) as i64;
+			let dy = (2 * py + 1 - cy) as i64;
+			dx * dx * ry * ry + dy * dy * rx * rx <= rx * rx * ry * ry
+		};
+		for py in y..(y + height)
+		{
+			for px in x..(x + width)
+			{
+				if !is_inside(px, py)
+				{
+					continue;
+				}
+				let is_edge = !is_inside(px - 1, py)
+					|| !is_inside(px + 1, py)
+					|| !is_inside(px, py - 1)
+					|| !is_inside(px, py + 1);
+				let nibble = if is_edge { 1 } else { 0 };
+				self.draw_point_with_nibble(nibble, px, py);
+			}
+		}
+	}
+
+	pub fn rect(&mut self, x: i32, y: i32, width: u32, height: u32)
+	{
+		let start_x = std::cmp::max(0, x);
+		let start_y = std::cmp::max(0, y);
+		let end_x_unclamped = x + width as i32;
+		let end_y_unclamped = y + height as i32;
+		let end_x = std::cmp::min(end_x_unclamped, WIDTH);
+		let end_y = std::cmp::min(end_y_unclamped, HEIGHT);
+
+		for yy in start_y..end_y
+		{
+			self.draw_hline_with_nibble(0, start_x, yy, end_x);
+		}
+
+		if (self.draw_colors >> 4) & 0xf != 0
+		{
+			if (0..WIDTH).contains(&x)
+			{
+				for yy in start_y..end_y
+				{
+					self.draw_point_with_nibble(1, x, yy);
+				}
+			}
+			if (1..=WIDTH).contains(&end_x_unclamped)
+			{
+				for yy in start_y..end_y
+				{
+					self.draw_point_with_nibble(1, end_x_unclamped - 1, yy);
+				}
+			}
+			if (0..HEIGHT).contains(&y)
+			{
+				self.draw_hline_with_nibble(1, start_x, y, end_x);
+			}
+			if (1..=HEIGHT).contains(&end_y_unclamped)
+			{
+				let yy = end_y_unclamped - 1;
+				self.draw_hline_with_nibble(1, start_x, yy, end_x);
+			}
+		}
+	}
+
+	pub fn text(&mut self, text: &str, x: i32, y: i32)
+	{
+		let mut current_x = x;
+		let mut y = y;
+		for c in text.bytes()
+		{
+			match c
+			{
+				b'\0' => break,
+				b'\n' =>
+				{
+					y += 8;
+					current_x = x;
+				}
+				_ =>
+				{
+					self.draw_glyph(c, current_x, y);
+					current_x += 8;
+				}
+			}
+		}
+	}
+
+	fn draw_glyph(&mut self, c: u8, x: i32, y: i32)
+	{
+		let glyph = match c
+		{
+			32..=126 => &FONT[(c - 32) as usize],
+			_ => &FONT[0],
+		};
+		for (v, row) in glyph.iter().enumerate()
+		{
+			for u in 0..8
+			{
+				// Like the console, ink uses the first DRAW_COLORS nibble
+				// and the background uses the second.
+				let nibble = if (row >> u) & 0x1 != 0 { 0 } else { 1 };
+				self.draw_point_with_nibble(nibble, x + u, y + v as i32);
+			}
+		}
+	}
+
+	pub fn hline(&mut self, x: i32, y: i32, len: u32)
+	{
+		if !(0..HEIGHT).contains(&y)
+		{
+			return;
+		}
+		let start_x = std::cmp::max(0, x);
+		let end_x = std::cmp::min(WIDTH, x + len as i32);
+		self.draw_hline_with_nibble(0, start_x, y, end_x);
+	}
+
+	pub fn vline(&mut self, x: i32, y: i32, len: u32)
+	{
+		if !(0..WIDTH).contains(&x)
+		{
+			return;
+		}
+		let start_y = std::cmp::max(0, y);
+		let end_y = std::cmp::min(HEIGHT, y + len as i32);
+		for yy in start_y..end_y
+		{
+			self.draw_point_with_nibble(0, x, yy);
+		}
+	}
+
+	/// Encodes the screen as an indexed-color PNG image with the current
+	/// palette, without compression.
+	pub fn to_png(&self) -> Vec<u8>
+	{
+		let mut scanlines = Vec::with_capacity(NUM_BYTES + HEIGHT as usize);
+		for y in 0..HEIGHT
+		{
+			// Filter type None.
+			scanlines.push(0);
+			for x in (0..WIDTH).step_by(4)
+			{
+				// PNG puts the leftmost pixel in the most significant bits.
+				let byte = (self.pixel(x, y) << 6)
+					| (self.pixel(x + 1, y) << 4)
+					| (self.pixel(x + 2, y) << 2)
+					| self.pixel(x + 3, y);
+				scanlines.push(byte);
+			}
+		}
+
+		let mut png = Vec::new();
+		png.extend_from_slice(&[
+			0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A,
+		]);
+
+		let mut ihdr = Vec::new();
+		ihdr.extend_from_slice(&(WIDTH as u32).to_be_bytes());
+		ihdr.extend_from_slice(&(HEIGHT as u32).to_be_bytes());
+		// Bit depth 2, color type 3 (indexed), default compression,
+		// filtering and no interlacing.
+		ihdr.extend_from_slice(&[2, 3, 0, 0, 0]);
+		write_chunk(&mut png, b"IHDR", &ihdr);
+
+		let mut plte = Vec::new();
+		for rgb in self.palette
+		{
+			plte.extend_from_slice(&rgb.to_be_bytes()[1..]);
+		}
+		write_chunk(&mut png, b"PLTE", &plte);
+
+		write_chunk(&mut png, b"IDAT", &zlib_stored(&scanlines));
+		write_chunk(&mut png, b"IEND", &[]);
+		png
+	}
+
+	/// Saves the screen as a PNG file.
+	pub fn save_png(&self, path: &std::path::Path) -> std::io::Result<()>
+	{
+		if let Some(dir) = path.parent()
+		{
+			std::fs::create_dir_all(dir)?;
+		}
+		std::fs::write(path, self.to_png())
+	}
+}
+
+impl Default for Framebuffer
+{
+	fn default() -> Self
+	{
+		Self::new()
+	}
+}
+
+/// Compares the screen with the golden image `tests/snapshots/{name}.png`.
+/// The actual screenshot is always saved to `target/snapshots/{name}.png`.
+/// If the environment variable UPDATE_SNAPSHOTS is set, the golden image is
+/// (over)written instead; a missing golden image is a failure otherwise.
+#[cfg(test)]
+pub fn assert_snapshot(framebuffer: &Framebuffer, name: &str)
+{
+	let root = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
+	let filename = format!("{}.png", name);
+	let actual_path = root.join("target").join("snapshots").join(&filename);
+	let golden_path = root.join("tests").join("snapshots").join(&filename);
+	framebuffer.save_png(&actual_path).unwrap();
+
+	if std::env::var_os("UPDATE_SNAPSHOTS").is_some()
+	{
+		framebuffer.save_png(&golden_path).unwrap();
+		return;
+	}
+	let golden = std::fs::read(&golden_path).unwrap_or_else(|_| {
+		panic!(
+			"golden image {} is missing; run with UPDATE_SNAPSHOTS=1 to \
+			 accept {}",
+			golden_path.display(),
+			actual_path.display()
+		)
+	});
+	assert!(
+		framebuffer.to_png() == golden,
+		"screenshot {} differs from {}",
+		actual_path.display(),
+		golden_path.display()
+	);
+}
+
+fn write_chunk(png: &mut Vec<u8>, chunk_type: &[u8; 4], data: &[u8])
+{
+	png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+	let start = png.len();
+	png.extend_from_slice(chunk_type);
+	png.extend_from_slice(data);
+	let crc = crc32(&png[start..]);
+	png.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn crc32(data: &[u8]) -> u32
+{
+	let mut crc = 0xFFFFFFFFu32;
+	for &byte in data
+	{
+		crc ^= byte as u32;
+		for _ in 0..8
+		{
+			let mask = (crc & 1).wrapping_neg();
+			crc = (crc >> 1) ^ (0xEDB88320 & mask);
+		}
+	}
+	!crc
+}
+
+/// Wraps data in a zlib stream of uncompressed ("stored") deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8>
+{
+	let mut stream = vec![0x78, 0x01];
+	let mut chunks = data.chunks(0xFFFF).peekable();
+	if chunks.peek().is_none()
+	{
+		stream.extend_from_slice(&[0x01, 0x00, 0x00, 0xFF, 0xFF]);
+	}
+	while let Some(chunk) = chunks.next()
+	{
+		let is_final = chunks.peek().is_none();
+		stream.push(if is_final { 0x01 } else { 0x00 });
+		let len = chunk.len() as u16;
+		stream.extend_from_slice(&len.to_le_bytes());
+		stream.extend_from_slice(&(!len).to_le_bytes());
+		stream.extend_from_slice(chunk);
+	}
+	let mut a = 1u32;
+	let mut b = 0u32;
+	for &byte in data
+	{
+		a = (a + byte as u32) % 65521;
+		b = (b + a) % 65521;
+	}
+	stream.extend_from_slice(&((b << 16) | a).to_be_bytes());
+	stream
+}
+
+/// An 8x8 font for the printable ASCII characters, one byte per row with
+/// the leftmost pixel in the least significant bit. It has the same metrics
+/// as the font built into WASM-4, which is all the game relies on.
+const FONT: [[u8; 8]; 95] = [
+	[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+	[0x18, 0x3C, 0x3C, 0x18, 0x18, 0x00, 0x18, 0x00], // '!'
+	[0x36, 0x36, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '"'
+	[0x36, 0x36, 0x7F, 0x36, 0x7F, 0x36, 0x36, 0x00], // '#'
+	[0x0C, 0x3E, 0x03, 0x1E, 0x30, 0x1F, 0x0C, 0x00], // '$'
+	[0x00, 0x63, 0x33, 0x18, 0x0C, 0x66, 0x63, 0x00], // '%'
+	[0x1C, 0x36, 0x1C, 0x6E, 0x3B, 0x33, 0x6E, 0x00], // '&'
+	[0x06, 0x06, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00], // '''
+	[0x18, 0x0C, 0x06, 0x06, 0x06, 0x0C, 0x18, 0x00], // '('
+	[0x06, 0x0C, 0x18, 0x18, 0x18, 0x0C, 0x06, 0x00], // ')'
+	[0x00, 0x66, 0x3C, 0xFF, 0x3C, 0x66, 0x00, 0x00], // '*'
+	[0x00, 0x0C, 0x0C, 0x3F, 0x0C, 0x0C, 0x00, 0x00], // '+'
+	[0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C, 0x06], // ','
+	[0x00, 0x00, 0x00, 0x3F, 0x00, 0x00, 0x00, 0x00], // '-'
+	[0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C, 0x00], // '.'
+	[0x60, 0x30, 0x18, 0x0C, 0x06, 0x03, 0x01, 0x00], // '/'
+	[0x3E, 0x63, 0x73, 0x7B, 0x6F, 0x67, 0x3E, 0x00], // '0'
+	[0x0C, 0x0E, 0x0C, 0x0C, 0x0C, 0x0C, 0x3F, 0x00], // '1'
+	[0x1E, 0x33, 0x30, 0x1C, 0x06, 0x33, 0x3F, 0x00], // '2'
+	[0x1E, 0x33, 0x30, 0x1C, 0x30, 0x33, 0x1E, 0x00], // '3'
+	[0x38, 0x3C, 0x36, 0x33, 0x7F, 0x30, 0x78, 0x00], // '4'
+	[0x3F, 0x03, 0x1F, 0x30, 0x30, 0x33, 0x1E, 0x00], // '5'
+	[0x1C, 0x06, 0x03, 0x1F, 0x33, 0x33, 0x1E, 0x00], // '6'
+	[0x3F, 0x33, 0x30, 0x18, 0x0C, 0x0C, 0x0C, 0x00], // '7'
+	[0x1E, 0x33, 0x33, 0x1E, 0x33, 0x33, 0x1E, 0x00], // '8'
+	[0x1E, 0x33, 0x33, 0x3E, 0x30, 0x18, 0x0E, 0x00], // '9'
+	[0x00, 0x0C, 0x0C, 0x00, 0x00, 0x0C, 0x0C, 0x00], // ':'
+	[0x00, 0x0C, 0x0C, 0x00, 0x00, 0x0C, 0x0C, 0x06], // ';'
+	[0x18, 0x0C, 0x06, 0x03, 0x06, 0x0C, 0x18, 0x00], // '<'
+	[0x00, 0x00, 0x3F, 0x00, 0x00, 0x3F, 0x00, 0x00], // '='
+	[0x06, 0x0C, 0x18, 0x30, 0x18, 0x0C, 0x06, 0x00], // '>'
+	[0x1E, 0x33, 0x30, 0x18, 0x0C, 0x00, 0x0C, 0x00], // '?'
+	[0x3E, 0x63, 0x7B, 0x7B, 0x7B, 0x03, 0x1E, 0x00], // '@'
+	[0x0C, 0x1E, 0x33, 0x33, 0x3F, 0x33, 0x33, 0x00], // 'A'
+	[0x3F, 0x66, 0x66, 0x3E, 0x66, 0x66, 0x3F, 0x00], // 'B'
+	[0x3C, 0x66, 0x03, 0x03, 0x03, 0x66, 0x3C, 0x00], // 'C'
+	[0x1F, 0x36, 0x66, 0x66, 0x66, 0x36, 0x1F, 0x00], // 'D'
+	[0x7F, 0x46, 0x16, 0x1E, 0x16, 0x46, 0x7F, 0x00], // 'E'
+	[0x7F, 0x46, 0x16, 0x1E, 0x16, 0x06, 0x0F, 0x00], // 'F'
+	[0x3C, 0x66, 0x03, 0x03, 0x73, 0x66, 0x7C, 0x00], // 'G'
+	[0x33, 0x33, 0x33, 0x3F, 0x33, 0x33, 0x33, 0x00], // 'H'
+	[0x1E, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // 'I'
+	[0x78, 0x30, 0x30, 0x30, 0x33, 0x33, 0x1E, 0x00], // 'J'
+	[0x67, 0x66, 0x36, 0x1E, 0x36, 0x66, 0x67, 0x00], // 'K'
+	[0x0F, 0x06, 0x06, 0x06, 0x46, 0x66, 0x7F, 0x00], // 'L'
+	[0x63, 0x77, 0x7F, 0x7F, 0x6B, 0x63, 0x63, 0x00], // 'M'
+	[0x63, 0x67, 0x6F, 0x7B, 0x73, 0x63, 0x63, 0x00], // 'N'
+	[0x1C, 0x36, 0x63, 0x63, 0x63, 0x36, 0x1C, 0x00], // 'O'
+	[0x3F, 0x66, 0x66, 0x3E, 0x06, 0x06, 0x0F, 0x00], // 'P'
+	[0x1E, 0x33, 0x33, 0x33, 0x3B, 0x1E, 0x38, 0x00], // 'Q'
+	[0x3F, 0x66, 0x66, 0x3E, 0x36, 0x66, 0x67, 0x00], // 'R'
+	[0x1E, 0x33, 0x07, 0x0E, 0x38, 0x33, 0x1E, 0x00], // 'S'
+	[0x3F, 0x2D, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // 'T'
+	[0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x3F, 0x00], // 'U'
+	[0x33, 0x33, 0x33, 0x33, 0x33, 0x1E, 0x0C, 0x00], // 'V'
+	[0x63, 0x63, 0x63, 0x6B, 0x7F, 0x77, 0x63, 0x00], // 'W'
+	[0x63, 0x63, 0x36, 0x1C, 0x1C, 0x36, 0x63, 0x00], // 'X'
+	[0x33, 0x33, 0x33, 0x1E, 0x0C, 0x0C, 0x1E, 0x00], // 'Y'
+	[0x7F, 0x63, 0x31, 0x18, 0x4C, 0x66, 0x7F, 0x00], // 'Z'
+	[0x1E, 0x06, 0x06, 0x06, 0x06, 0x06, 0x1E, 0x00], // '['
+	[0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x40, 0x00], // '\'
+	[0x1E, 0x18, 0x18, 0x18, 0x18, 0x18, 0x1E, 0x00], // ']'
+	[0x08, 0x1C, 0x36, 0x63, 0x00, 0x00, 0x00, 0x00], // '^'
+	[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF], // '_'
+	[0x0C, 0x0C, 0x18, 0x00, 0x00, 0x00, 0x00, 0x00], // '`'
+	[0x00, 0x00, 0x1E, 0x30, 0x3E, 0x33, 0x6E, 0x00], // 'a'
+	[0x07, 0x06, 0x06, 0x3E, 0x66, 0x66, 0x3B, 0x00], // 'b'
+	[0x00, 0x00, 0x1E, 0x33, 0x03, 0x33, 0x1E, 0x00], // 'c'
+	[0x38, 0x30, 0x30, 0x3E, 0x33, 0x33, 0x6E, 0x00], // 'd'
+	[0x00, 0x00, 0x1E, 0x33, 0x3F, 0x03, 0x1E, 0x00], // 'e'
+	[0x1C, 0x36, 0x06, 0x0F, 0x06, 0x06, 0x0F, 0x00], // 'f'
+	[0x00, 0x00, 0x6E, 0x33, 0x33, 0x3E, 0x30, 0x1F], // 'g'
+	[0x07, 0x06, 0x36, 0x6E, 0x66, 0x66, 0x67, 0x00], // 'h'
+	[0x0C, 0x00, 0x0E, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // 'i'
+	[0x30, 0x00, 0x30, 0x30, 0x30, 0x33, 0x33, 0x1E], // 'j'
+	[0x07, 0x06, 0x66, 0x36, 0x1E, 0x36, 0x67, 0x00], // 'k'
+	[0x0E, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // 'l'
+	[0x00, 0x00, 0x33, 0x7F, 0x7F, 0x6B, 0x63, 0x00], // 'm'
+	[0x00, 0x00, 0x1F, 0x33, 0x33, 0x33, 0x33, 0x00], // 'n'
+	[0x00, 0x00, 0x1E, 0x33, 0x33, 0x33, 0x1E, 0x00], // 'o'
+	[0x00, 0x00, 0x3B, 0x66, 0x66, 0x3E, 0x06, 0x0F], // 'p'
+	[0x00, 0x00, 0x6E, 0x33, 0x33, 0x3E, 0x30, 0x78], // 'q'
+	[0x00, 0x00, 0x3B, 0x6E, 0x66, 0x06, 0x0F, 0x00], // 'r'
+	[0x00, 0x00, 0x3E, 0x03, 0x1E, 0x30, 0x1F, 0x00], // 's'
+	[0x08, 0x0C, 0x3E, 0x0C, 0x0C, 0x2C, 0x18, 0x00], // 't'
+	[0x00, 0x00, 0x33, 0x33, 0x33, 0x33, 0x6E, 0x00], // 'u'
+	[0x00, 0x00, 0x33, 0x33, 0x33, 0x1E, 0x0C, 0x00], // 'v'
+	[0x00, 0x00, 0x63, 0x6B, 0x7F, 0x7F, 0x36, 0x00], // 'w'
+	[0x00, 0x00, 0x63, 0x36, 0x1C, 0x36, 0x63, 0x00], // 'x'
+	[0x00, 0x00, 0x33, 0x33, 0x33, 0x3E, 0x30, 0x1F], // 'y'
+	[0x00, 0x00, 0x3F, 0x19, 0x0C, 0x26, 0x3F, 0x00], // 'z'
+	[0x38, 0x0C, 0x0C, 0x07, 0x0C, 0x0C, 0x38, 0x00], // '{'
+	[0x18, 0x18, 0x18, 0x00, 0x18, 0x18, 0x18, 0x00], // '|'
+	[0x07, 0x0C, 0x0C, 0x38, 0x0C, 0x0C, 0x07, 0x00], // '}'
+	[0x6E, 0x3B, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '~'
+];
@@ -20,8 +20,10 @@ impl<T> Wrapper<T>
 		}
 	}
 
+	/// The address is hidden from the optimizer, because otherwise it gets
+	/// folded into every single field access, which bloats the cart.
 	pub fn get_mut(&self) -> &mut T
 	{
-		unsafe { &mut *self.inner.get() }
+		unsafe { std::hint::black_box(&mut *self.inner.get()) }
 	}
 }
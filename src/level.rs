@@ -4,95 +4,25 @@
 // License: MIT
 //
 
-use crate::wasm4::*;
+use crate::platform::*;
 
-use crate::decree::Decree;
-use crate::decree::Tutorial;
-use crate::decree::{AllOrNone, InOrNear};
+use crate::campaign::{Chapter, Progress, CHAPTERS};
+use crate::disk;
+use crate::disk::{Reader, Writer};
 use crate::global_state::Wrapper;
-use crate::map::Map;
 use crate::map::GRID_SIZE;
+use crate::map::{DrawOptions, Map, Preset};
+use crate::music::{Cue, Theme};
 use crate::palette;
+use crate::rules::{Action, Card, Event, Marker, Rules, State, TerrainType};
+use crate::rules::{MAX_NUM_REGIONS, MAX_SAVED_SIZE, MAX_THREAT_LEVEL};
+use crate::scenario::{Scenario, GENERATED, VILLAGE_TUTORIAL};
+use crate::scores::{Daily, Leaderboard, Mode, Score};
+use crate::settings::{Setting, Settings};
+use crate::sound::Sound;
 use crate::sprites;
 
 use bitmaps::Bitmap;
-use fastrand;
-
-pub const MAX_NUM_REGIONS: usize = 35;
-pub const MAX_NUM_CARDS: usize = 20;
-pub const TOTAL_NUM_DECREES: usize = 23;
-
-const MAX_THREAT_LEVEL: u8 = 10;
-const MAX_TRIBUTE: u8 = 8;
-
-const VILLAGE_WOOD_COST: u8 = 10;
-const VILLAGE_GOLD_COST: u8 = 5;
-const MAX_STORED_GRAIN: u8 = 20;
-const MAX_STORED_WOOD: u8 = 20;
-const MAX_STORED_WINE: u8 = 50;
-const MAX_STORED_GOLD: u8 = 20;
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum TerrainType
-{
-	Village,
-	Grass,
-	Forest,
-	Hill,
-	Mountain,
-	Water,
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum Marker
-{
-	Worker,
-	DeadWorker,
-	Roman,
-	DeadRoman,
-	Occupied,
-	FogOfWar,
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Card
-{
-	Worker,
-	Roman,
-}
-
-#[derive(Debug, Clone, Copy)]
-struct Region
-{
-	terrain_type: TerrainType,
-	marker: Option<Marker>,
-}
-
-const EMPTY_REGION: Region = Region {
-	terrain_type: TerrainType::Water,
-	marker: None,
-};
-
-#[derive(Debug, Clone, Copy)]
-enum State
-{
-	Setup,
-	NewObjectives,
-	NewDecrees,
-	Placement,
-	Shuffling,
-	Resolution,
-	Occupation,
-	Cleanup,
-	DecreeViolated
-	{
-		decree_offset: u8,
-	},
-	TributePaid,
-	TributeFailed,
-	TributeSkipped,
-	GameOver,
-}
 
 #[derive(Debug, Clone, Copy)]
 enum Preview
@@ -115,6 +45,12 @@ enum Preview
 	CannotPlaceRoman,
 }
 
+const SAVE_FORMAT_VERSION: u8 = 9;
+/// The version, seed, mode, preset and scenario, followed by the rules.
+const SAVE_HEADER_SIZE: usize = 1 + 8 + 1 + 1 + 1;
+const _: () =
+	assert!(SAVE_HEADER_SIZE + MAX_SAVED_SIZE <= disk::SAVED_GAME.size);
+
 const UI_X_GRAIN: i32 = 35;
 const UI_X_WOOD: i32 = 61;
 const UI_X_WINE: i32 = 113;
@@ -122,326 +58,172 @@ const UI_X_GOLD: i32 = 87;
 
 pub struct Level
 {
-	region_data: [Region; MAX_NUM_REGIONS],
-	adjacency: [Bitmap<MAX_NUM_REGIONS>; MAX_NUM_REGIONS],
-	border_adjacency: Bitmap<MAX_NUM_REGIONS>,
+	scenario: &'static Scenario,
+	seed: u64,
+	mode: Mode,
+	preset: Preset,
+	rules: Rules,
 	kill_preview: Bitmap<MAX_NUM_REGIONS>,
 	attack_preview: Bitmap<MAX_NUM_REGIONS>,
 	support_preview: Bitmap<MAX_NUM_REGIONS>,
 	gather_preview: Bitmap<MAX_NUM_REGIONS>,
-	card_deck: [Card; MAX_NUM_CARDS],
-	decree_data: [Decree; TOTAL_NUM_DECREES],
-	num_regions: u8,
-	num_cards: u8,
-	num_decrees: u8,
-	card_offset: u8,
-	threat_level: u8,
-	tribute: u8,
-	grain: u8,
-	wood: u8,
-	wine: u8,
-	gold: u8,
-	score: u16,
 	ticks_in_4sec: u8,
 	previous_gamepad: u8,
 	previous_mousebuttons: u8,
-	state: State,
-	tutorial: Option<Tutorial>,
+	is_undo_tap: bool,
+	settings: Settings,
 	hover_preview: Option<Preview>,
+	violation_preview: Option<u8>,
 	cursor: Cursor,
-	rng: fastrand::Rng,
 }
 
 static MAP: Wrapper<Map> = Wrapper::new(Map::empty());
 
+/// All levels share the same map, so tests that create levels take turns.
+#[cfg(test)]
+pub static MAP_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
 impl Level
 {
-	pub fn new(seed: u64) -> Level
+	pub fn new(
+		scenario: &'static Scenario,
+		seed: u64,
+		mode: Mode,
+		preset: Preset,
+	) -> Level
 	{
-		let decree_data = [
-			Decree::Dummy,
-			Decree::NoRomansInAmbush,
-			Decree::Regional {
-				all_or_none: AllOrNone::All,
-				marker: Marker::Roman,
-				in_or_near: InOrNear::Near,
-				terrain_type: TerrainType::Water,
-			},
-			Decree::Regional {
-				all_or_none: AllOrNone::All,
-				marker: Marker::Roman,
-				in_or_near: InOrNear::Near,
-				terrain_type: TerrainType::Grass,
-			},
-			Decree::Regional {
-				all_or_none: AllOrNone::All,
-				marker: Marker::Roman,
-				in_or_near: InOrNear::Near,
-				terrain_type: TerrainType::Forest,
-			},
-			Decree::Regional {
-				all_or_none: AllOrNone::All,
-				marker: Marker::Roman,
-				in_or_near: InOrNear::Near,
-				terrain_type: TerrainType::Hill,
-			},
-			Decree::Regional {
-				all_or_none: AllOrNone::All,
-				marker: Marker::Roman,
-				in_or_near: InOrNear::Near,
-				terrain_type: TerrainType::Mountain,
-			},
-			Decree::Regional {
-				all_or_none: AllOrNone::None,
-				marker: Marker::Roman,
-				in_or_near: InOrNear::Near,
-				terrain_type: TerrainType::Water,
-			},
-			Decree::Regional {
-				all_or_none: AllOrNone::None,
-				marker: Marker::Roman,
-				in_or_near: InOrNear::Near,
-				terrain_type: TerrainType::Forest,
-			},
-			Decree::Regional {
-				all_or_none: AllOrNone::None,
-				marker: Marker::Roman,
-				in_or_near: InOrNear::Near,
-				terrain_type: TerrainType::Hill,
-			},
-			Decree::Regional {
-				all_or_none: AllOrNone::None,
-				marker: Marker::Roman,
-				in_or_near: InOrNear::Near,
-				terrain_type: TerrainType::Mountain,
-			},
-			Decree::Regional {
-				all_or_none: AllOrNone::All,
-				marker: Marker::Worker,
-				in_or_near: InOrNear::Near,
-				terrain_type: TerrainType::Water,
-			},
-			Decree::Regional {
-				all_or_none: AllOrNone::All,
-				marker: Marker::Worker,
-				in_or_near: InOrNear::Near,
-				terrain_type: TerrainType::Grass,
-			},
-			Decree::Regional {
-				all_or_none: AllOrNone::All,
-				marker: Marker::Worker,
-				in_or_near: InOrNear::Near,
-				terrain_type: TerrainType::Forest,
-			},
-			Decree::Regional {
-				all_or_none: AllOrNone::All,
-				marker: Marker::Worker,
-				in_or_near: InOrNear::Near,
-				terrain_type: TerrainType::Hill,
-			},
-			Decree::Regional {
-				all_or_none: AllOrNone::All,
-				marker: Marker::Worker,
-				in_or_near: InOrNear::Near,
-				terrain_type: TerrainType::Mountain,
-			},
-			Decree::Regional {
-				all_or_none: AllOrNone::All,
-				marker: Marker::Roman,
-				in_or_near: InOrNear::In,
-				terrain_type: TerrainType::Grass,
-			},
-			Decree::Regional {
-				all_or_none: AllOrNone::All,
-				marker: Marker::Worker,
-				in_or_near: InOrNear::In,
-				terrain_type: TerrainType::Forest,
-			},
-			Decree::Regional {
-				all_or_none: AllOrNone::None,
-				marker: Marker::Worker,
-				in_or_near: InOrNear::In,
-				terrain_type: TerrainType::Hill,
-			},
-			Decree::Regional {
-				all_or_none: AllOrNone::None,
-				marker: Marker::Worker,
-				in_or_near: InOrNear::In,
-				terrain_type: TerrainType::Mountain,
-			},
-			Decree::Regional {
-				all_or_none: AllOrNone::None,
-				marker: Marker::Roman,
-				in_or_near: InOrNear::In,
-				terrain_type: TerrainType::Forest,
-			},
-			Decree::Regional {
-				all_or_none: AllOrNone::None,
-				marker: Marker::Roman,
-				in_or_near: InOrNear::In,
-				terrain_type: TerrainType::Hill,
-			},
-			Decree::Regional {
-				all_or_none: AllOrNone::None,
-				marker: Marker::Roman,
-				in_or_near: InOrNear::In,
-				terrain_type: TerrainType::Mountain,
-			},
-		];
 		//trace(format!("seed = {}", seed));
-		let mut num_regions = 0;
-		let mut region_data = [EMPTY_REGION; MAX_NUM_REGIONS];
-		let mut adjacency = [Bitmap::default(); MAX_NUM_REGIONS];
-		let mut border_adjacency = Bitmap::default();
-		let mut rng = fastrand::Rng::with_seed(seed);
+		let map = MAP.get_mut();
+		let mut rules = generate(scenario, seed, preset, map);
+		rules.set_strict(Settings::load().get(Setting::Strict));
+		Level::with_rules(scenario, seed, mode, preset, rules, map)
+	}
+
+	/// Continues the game that was last saved to disk, if any.
+	pub fn resume() -> Option<Level>
+	{
+		let mut buffer = [0u8; disk::SAVED_GAME.size];
+		disk::read(disk::SAVED_GAME, &mut buffer);
+		let mut reader = Reader::new(&buffer);
+		if reader.read_u8()? != SAVE_FORMAT_VERSION
 		{
-			let map = MAP.get_mut();
-			map.generate(&mut rng);
-			for (id, terrain_type) in map.regions()
-			{
-				region_data[id as usize] = Region {
-					terrain_type,
-					marker: None,
-				};
-				num_regions += 1;
-			}
-			map.fill_adjacency(&mut adjacency, &mut border_adjacency);
-			let roman_spawn = match seed
-			{
-				1 => None,
-				_ => (0..(num_regions as usize))
-					.rev()
-					.filter(|i| border_adjacency.get(*i as usize))
-					.find(|i| match region_data[*i].terrain_type
-					{
-						TerrainType::Water => false,
-						TerrainType::Mountain => false,
-						_ => true,
-					})
-					.map(|i| i as i8),
-			};
-			if let Some(region_id) = roman_spawn
+			return None;
+		}
+		let seed = reader.read_u64()?;
+		let mode = Mode::from_u8(reader.read_u8()?)?;
+		let preset = Preset::from_u8(reader.read_u8()?)?;
+		let scenario = Scenario::from_id(reader.read_u8()?)?;
+		let map = MAP.get_mut();
+		let mut rules = generate(scenario, seed, preset, map);
+		let mut villages: Bitmap<MAX_NUM_REGIONS> = Bitmap::new();
+		for i in 0..rules.num_regions()
+		{
+			let is_village =
+				rules.terrain_type(i as i8) == TerrainType::Village;
+			villages.set(i as usize, is_village);
+		}
+		rules.load(&mut reader)?;
+		for i in 0..rules.num_regions()
+		{
+			let region_id = i as i8;
+			if rules.terrain_type(region_id) == TerrainType::Village
+				&& !villages.get(i as usize)
 			{
-				map.occupy_region(region_id);
-				map.update_occupation_map(100);
-				let marker = Marker::Occupied;
-				region_data[region_id as usize].marker = Some(marker);
-				map.set_marker_in_region(region_id, Some(marker));
-			}
-			match seed
-			{
-				1 =>
-				{
-					for region_id in [
-						0, 1, 2, 3, 4, 6, 8, 9, 10, 12, 13, 14, 15, 19, 20, 22,
-						23, 24, 25, 26, 27, 29, 30, 31,
-					]
-					{
-						let marker = Marker::FogOfWar;
-						region_data[region_id as usize].marker = Some(marker);
-						map.set_marker_in_region(region_id, Some(marker));
-					}
-				}
-				_ => (),
+				map.place_village(region_id);
 			}
 		}
-		let tutorial = match seed
-		{
-			1 => Some(Tutorial::PlaceBanners),
-			202 => Some(Tutorial::Village),
-			_ => None,
-		};
-		let threat_level = 0;
-		let tribute = match seed
+		Some(Level::with_rules(scenario, seed, mode, preset, rules, map))
+	}
+
+	#[cfg(test)]
+	pub fn rules(&self) -> &Rules
+	{
+		&self.rules
+	}
+
+	pub fn music_cue(&self) -> Option<Cue>
+	{
+		if !self.settings.get(Setting::Music)
 		{
-			1 => 0,
-			202 => 1,
-			_ => 2,
-		};
-		let starting_grain = match seed
+			return None;
+		}
+		let theme = match self.rules.state()
 		{
-			_ => 24,
+			State::Occupation => Theme::Occupation,
+			State::GameOver => Theme::GameOver,
+			_ => Theme::Level,
 		};
-		let starting_wood = match seed
+		Some(Cue {
+			theme,
+			intensity: self.rules.threat_level(),
+		})
+	}
+
+	pub fn has_saved_game() -> bool
+	{
+		let mut version = [0u8];
+		disk::read(disk::SAVED_GAME, &mut version);
+		version[0] == SAVE_FORMAT_VERSION
+	}
+
+	fn with_rules(
+		scenario: &'static Scenario,
+		seed: u64,
+		mode: Mode,
+		preset: Preset,
+		rules: Rules,
+		map: &mut Map,
+	) -> Level
+	{
+		let mut any_occupied = false;
+		for i in 0..rules.num_regions()
 		{
-			1 => 0,
-			202 => 15,
-			_ => 10,
-		};
-		let starting_gold = match seed
+			let region_id = i as i8;
+			let marker = rules.marker(region_id);
+			if marker == Some(Marker::Occupied)
+				|| rules.threat_level() >= MAX_THREAT_LEVEL
+			{
+				map.occupy_region(region_id);
+				any_occupied = true;
+			}
+			map.set_marker_in_region(region_id, marker);
+		}
+		if any_occupied
 		{
-			1 => 0,
-			_ => 10,
-		};
+			map.update_occupation_map(100);
+		}
 		Level {
-			num_regions,
-			region_data,
-			adjacency,
-			border_adjacency,
+			scenario,
+			seed,
+			mode,
+			preset,
+			rules,
 			kill_preview: Bitmap::default(),
 			attack_preview: Bitmap::default(),
 			support_preview: Bitmap::default(),
 			gather_preview: Bitmap::default(),
-			num_cards: 0,
-			card_offset: 0,
-			card_deck: [Card::Worker; MAX_NUM_CARDS],
-			decree_data,
-			num_decrees: 1,
-			threat_level,
-			tribute,
-			grain: starting_grain,
-			wood: starting_wood,
-			wine: 0,
-			gold: starting_gold,
-			score: 0,
 			ticks_in_4sec: 0,
 			previous_gamepad: 0,
 			previous_mousebuttons: 0,
+			is_undo_tap: false,
+			settings: Settings::load(),
 			hover_preview: None,
-			state: State::Setup,
-			tutorial,
-			cursor: Cursor {
-				mouse_x: -1,
-				mouse_y: -1,
-				row: 8,
-				col: 8,
-				is_mouse_active: false,
-				is_resource_bar_active: false,
-				resource_bar_offset: 0,
-			},
-			rng,
+			violation_preview: None,
+			cursor: Cursor::new(),
 		}
 	}
 
 	pub fn update(&mut self) -> Option<Transition>
 	{
-		let gamepad = unsafe { *GAMEPAD1 };
-		let mousebuttons = unsafe { *MOUSE_BUTTONS };
+		let gamepad = gamepad();
+		let mousebuttons = mouse_buttons();
 
 		let map = MAP.get_mut();
 
-		let active_card = if self.card_offset < self.num_cards
-		{
-			Some(self.card_deck[self.card_offset as usize])
-		}
-		else
-		{
-			None
-		};
-
-		if (gamepad & BUTTON_1 != 0)
-			&& (gamepad & BUTTON_2 != 0)
-			&& (gamepad & BUTTON_LEFT != 0)
-			&& (gamepad & BUTTON_RIGHT != 0)
-			&& (gamepad & BUTTON_UP != 0)
-			&& false
-		{
-			self.threat_level = MAX_THREAT_LEVEL;
-			self.num_decrees = 0;
-			self.num_cards = 0;
-		}
+		let active_card = self.rules.active_card();
 
 		self.hover_preview = None;
+		self.violation_preview = None;
 		self.kill_preview = Bitmap::new();
 		self.attack_preview = Bitmap::new();
 		self.support_preview = Bitmap::new();
@@ -449,313 +231,43 @@ impl Level
 		self.cursor.update(gamepad, self.previous_gamepad);
 		let hovered_region_id =
 			map.determine_hovered_region_id(&mut self.cursor);
-		if self.num_decrees == 0
+		if active_card.is_none()
 		{
-			self.num_cards = 0;
-			self.pick_decrees();
-
-			if self.decree_data[0] == Decree::Dummy
+			let state = self.rules.state();
+			let delay = proceed_delay(state);
+			if state == State::Occupation
+				&& self.ticks_in_4sec >= 30
+				&& self.ticks_in_4sec <= 50
+				&& self.ticks_in_4sec % 2 == 0
 			{
-				self.state = State::Shuffling;
+				let percentage = 5 * (self.ticks_in_4sec - 30);
+				map.update_occupation_map(percentage);
 			}
-			else if self.threat_level < MAX_THREAT_LEVEL
+			match delay
 			{
-				self.state = State::NewDecrees;
-			}
-			else
-			{
-				for i in 0..self.num_regions
-				{
-					map.occupy_region(i as i8);
-				}
-				self.state = State::Occupation;
-			}
-			self.ticks_in_4sec = 0;
-		}
-		else if active_card.is_none()
-		{
-			match self.state
-			{
-				State::Setup =>
+				Some(delay) if self.ticks_in_4sec >= delay =>
 				{
-					self.pick_decrees();
-					if self.tutorial.is_some()
+					if state == State::Occupation
 					{
-						if self.tutorial == Some(Tutorial::Village)
-						{
-							self.tutorial = None;
-						}
-						self.state = State::NewObjectives;
-					}
-					else
-					{
-						self.state = State::Shuffling;
+						map.update_occupation_map(100);
 					}
-					self.ticks_in_4sec = 0;
+					self.apply(Action::Proceed, map);
 				}
+				_ => (),
+			}
+			match self.rules.state()
+			{
 				State::NewObjectives =>
 				{
 					// Wait for user to finish reading.
 					self.hover_preview = Some(Preview::HoverObjectives);
 				}
-				State::Placement =>
-				{
-					if self.count_remaining_spaces() == 0
-					{
-						if self.tutorial == Some(Tutorial::FeedWorkers)
-						{
-							self.tutorial = Some(Tutorial::Harvest);
-							self.state = State::NewObjectives;
-						}
-						else if self.tutorial
-							== Some(Tutorial::RomansHaveCome)
-						{
-							self.tribute = 15;
-							self.tutorial = Some(Tutorial::Tribute);
-							self.state = State::NewObjectives;
-						}
-						else
-						{
-							self.state = State::Resolution;
-						}
-					}
-					else if self.tutorial == Some(Tutorial::PlaceBanners)
-					{
-						self.tutorial = Some(Tutorial::FeedWorkers);
-						self.state = State::NewObjectives;
-					}
-					else
-					{
-						self.state = State::Shuffling;
-					}
-					self.ticks_in_4sec = 0;
-				}
-				State::Shuffling =>
-				{
-					if self.ticks_in_4sec == 5
-					{
-						self.shuffle();
-						self.state = State::Placement;
-					}
-				}
-				State::Resolution =>
-				{
-					if self.ticks_in_4sec == 20
-					{
-						let survivor =
-							(0..(self.num_regions as usize)).find(|i| {
-								self.region_data[*i].marker
-									== Some(Marker::Worker)
-							});
-						if let Some(i) = survivor
-						{
-							match self.region_data[i].terrain_type
-							{
-								TerrainType::Village => self.grain += 1,
-								TerrainType::Grass => self.grain += 1,
-								TerrainType::Forest => self.wood += 1,
-								TerrainType::Hill => self.wine += 1,
-								TerrainType::Mountain => self.gold += 1,
-								TerrainType::Water => (),
-							}
-							self.region_data[i].marker = None;
-							map.set_marker_in_region(i as i8, None);
-						}
-						else
-						{
-							self.state = State::Occupation;
-						}
-						self.ticks_in_4sec = 0;
-					}
-				}
-				State::Occupation =>
-				{
-					if self.ticks_in_4sec == 1
-					{
-						let mut any = false;
-						for i in 0..(self.num_regions as usize)
-						{
-							if self.region_data[i].marker == Some(Marker::Roman)
-							{
-								let region_id = i as i8;
-								map.occupy_region(region_id);
-								let marker = Marker::Occupied;
-								self.region_data[i].marker = Some(marker);
-								map.set_marker_in_region(
-									region_id,
-									Some(marker),
-								);
-								any = true;
-							}
-						}
-						if !any
-						{
-							// Find a battlefield (empty squares are either
-							// water or had workers on them) on the border.
-							let roman_spawn = (0..(self.num_regions as usize))
-								.rev()
-								.filter(|i| self.border_adjacency.get(*i))
-								.find(|i| match self.region_data[*i].marker
-								{
-									Some(Marker::DeadRoman) => true,
-									Some(Marker::DeadWorker) => true,
-									_ => false,
-								});
-							if let Some(i) = roman_spawn
-							{
-								let region_id = i as i8;
-								map.occupy_region(region_id);
-								let marker = Marker::Occupied;
-								self.region_data[i].marker = Some(marker);
-								map.set_marker_in_region(
-									region_id,
-									Some(marker),
-								);
-							}
-						}
-					}
-					else if self.ticks_in_4sec >= 30
-						&& self.ticks_in_4sec <= 50
-						&& self.ticks_in_4sec % 2 == 0
-					{
-						let percentage = 5 * (self.ticks_in_4sec - 30);
-						map.update_occupation_map(percentage);
-					}
-					else if self.ticks_in_4sec >= 80
-					{
-						map.update_occupation_map(100);
-						if self.threat_level < MAX_THREAT_LEVEL
-						{
-							self.state = State::Cleanup;
-						}
-						else
-						{
-							self.state = State::GameOver;
-						}
-						self.ticks_in_4sec = 0;
-					}
-				}
-				State::Cleanup =>
-				{
-					if self.ticks_in_4sec == 5
-					{
-						let trash = (0..(self.num_regions as usize)).find(
-							|i| match self.region_data[*i].marker
-							{
-								Some(Marker::Occupied) => false,
-								Some(Marker::FogOfWar) => false,
-								Some(Marker::Roman) => true,
-								Some(_) => true,
-								None => false,
-							},
-						);
-						if let Some(i) = trash
-						{
-							self.region_data[i].marker = None;
-							map.set_marker_in_region(i as i8, None);
-						}
-						else
-						{
-							if self.tutorial == Some(Tutorial::FirstKill)
-							{
-								self.tutorial = Some(Tutorial::RomansHaveCome);
-								let marker = Marker::Roman;
-								self.region_data[27].marker = Some(marker);
-								map.set_marker_in_region(27, Some(marker));
-								self.state = State::Occupation;
-							}
-							else if self.tribute == 0
-							{
-								self.state = State::TributeSkipped;
-							}
-							else if self.wine >= self.tribute
-							{
-								self.wine -= self.tribute;
-								self.tribute += 2;
-								if self.tribute > MAX_TRIBUTE
-								{
-									self.tribute = MAX_TRIBUTE;
-								}
-								self.state = State::TributePaid;
-							}
-							else
-							{
-								self.tribute += 1;
-								if self.tutorial == Some(Tutorial::Tribute)
-								{
-									self.tribute = 1;
-								}
-								if self.tribute > MAX_TRIBUTE
-								{
-									self.tribute = MAX_TRIBUTE;
-								}
-								if self.threat_level < MAX_THREAT_LEVEL
-								{
-									self.threat_level += 1;
-								}
-								else
-								{
-									self.num_decrees = 0;
-								}
-								self.state = State::TributeFailed;
-							}
-							if self.grain > MAX_STORED_GRAIN
-							{
-								self.grain = MAX_STORED_GRAIN;
-							}
-							if self.wood > MAX_STORED_WOOD
-							{
-								self.wood = MAX_STORED_WOOD;
-							}
-							if self.wine > MAX_STORED_WINE
-							{
-								self.wine = MAX_STORED_WINE;
-							}
-							if self.gold > MAX_STORED_GOLD
-							{
-								self.gold = MAX_STORED_GOLD;
-							}
-						}
-						self.ticks_in_4sec = 0;
-					}
-				}
 				State::NewDecrees =>
 				{
 					// Wait for user to finish reading.
 					self.hover_preview = Some(Preview::HoverDecrees);
 				}
-				State::DecreeViolated { .. } =>
-				{
-					// Wait for user to finish reading.
-				}
-				State::TributePaid | State::TributeSkipped =>
-				{
-					if self.ticks_in_4sec >= 90
-					{
-						if self.tutorial == Some(Tutorial::Harvest)
-						{
-							let marker = Marker::Roman;
-							self.region_data[22].marker = Some(marker);
-							map.set_marker_in_region(22, Some(marker));
-						}
-
-						self.state = State::Shuffling;
-						self.ticks_in_4sec = 0;
-
-						if self.tutorial == Some(Tutorial::RomansHaveCome)
-						{
-							self.state = State::NewObjectives;
-						}
-					}
-				}
-				State::TributeFailed =>
-				{
-					// Wait for user to finish reading.
-				}
-				State::GameOver =>
-				{
-					// Wait for user to finish reading.
-				}
+				_ => (),
 			}
 		}
 		else if self.cursor.is_resource_bar_active
@@ -781,46 +293,26 @@ impl Level
 		}
 		else if let Some(region_id) = hovered_region_id
 		{
-			let region = self.region_data[region_id as usize];
-			if region.marker.is_none()
+			if self.rules.state() == State::Placement
+				&& self.rules.can_place(region_id)
 			{
-				let can_place = match region.terrain_type
-				{
-					TerrainType::Village => true,
-					TerrainType::Grass => true,
-					TerrainType::Forest => true,
-					TerrainType::Hill => true,
-					TerrainType::Mountain => true,
-					_ => false,
-				};
-				let preview = if active_card == Some(Card::Roman)
-				{
-					if can_place
-					{
-						self.figure_out_combat(region_id, Card::Roman);
-						Some(Preview::PlaceRoman { region_id })
-					}
-					else
-					{
-						None
-					}
-				}
-				else
-				{
-					if can_place
-					{
-						self.figure_out_combat(region_id, Card::Worker);
-						Some(Preview::PlaceWorker {
-							region_id,
-							terrain_type: region.terrain_type,
-						})
-					}
-					else
-					{
-						None
-					}
+				let card = active_card.unwrap_or(Card::Worker);
+				let combat = self.rules.figure_out_combat(region_id, card);
+				self.kill_preview = combat.kill;
+				self.attack_preview = combat.attack;
+				self.support_preview = combat.support;
+				self.gather_preview = combat.gather;
+				self.violation_preview =
+					self.rules.preview_violation(region_id);
+				let preview = match card
+				{
+					Card::Roman => Preview::PlaceRoman { region_id },
+					Card::Worker => Preview::PlaceWorker {
+						region_id,
+						terrain_type: self.rules.terrain_type(region_id),
+					},
 				};
-				self.hover_preview = preview;
+				self.hover_preview = Some(preview);
 			}
 		}
 		else if self.cursor.is_mouse_active
@@ -863,88 +355,96 @@ impl Level
 			|| ((gamepad & BUTTON_1 != 0)
 				&& (self.previous_gamepad & BUTTON_1 == 0))
 		{
-			match self.state
+			match self.rules.state()
 			{
-				State::NewObjectives =>
+				State::Placement => match self.hover_preview
 				{
-					if self.tutorial == Some(Tutorial::PlaceRomans)
+					Some(Preview::PlaceWorker { region_id, .. })
+					| Some(Preview::PlaceRoman { region_id }) =>
 					{
-						self.tutorial = Some(Tutorial::PlaceRomans2);
-						self.state = State::Placement;
+						self.apply(Action::Place { region_id }, map);
 					}
-					else
+					_ => (),
+				},
+				State::GameOver | State::Victory =>
+				{
+					if self.rules.tutorial().is_some()
 					{
-						self.num_decrees = 0;
-						self.state = State::Shuffling;
-						self.ticks_in_4sec = 0;
+						return Some(Transition {
+							scenario: &VILLAGE_TUTORIAL,
+							rng_seed: VILLAGE_TUTORIAL.rng_seed,
+							mode: self.mode,
+							preset: VILLAGE_TUTORIAL.preset,
+						});
 					}
-				}
-				State::Placement =>
-				{
-					self.place_marker(map);
-
-					if self.tutorial == Some(Tutorial::Harvest)
+					else if let Some(chapter) = Chapter::of(self.scenario)
 					{
-						if self.region_data[0..(self.num_regions as usize)]
-							.iter()
-							.any(|region| {
-								region.marker == Some(Marker::DeadRoman)
-							})
+						// Move on to the next chapter once this one is done.
+						let chapter = if Progress::load().is_completed(chapter)
 						{
-							self.tutorial = Some(Tutorial::FirstKill);
-							self.state = State::NewObjectives;
-							self.num_cards = 0;
+							CHAPTERS
+								.get(chapter + 1)
+								.unwrap_or(&CHAPTERS[chapter])
 						}
+						else
+						{
+							&CHAPTERS[chapter]
+						};
+						return Some(Transition {
+							scenario: chapter.scenario,
+							rng_seed: chapter.rng_seed,
+							mode: self.mode,
+							preset: chapter.preset,
+						});
 					}
-				}
-				State::NewDecrees =>
-				{
-					if self.tutorial == Some(Tutorial::Tribute)
-					{
-						self.tutorial = Some(Tutorial::PlaceRomans);
-						self.state = State::NewObjectives;
-						self.ticks_in_4sec = 0;
-					}
-					else
-					{
-						self.state = State::Shuffling;
-						self.ticks_in_4sec = 0;
-					}
-				}
-				State::TributePaid | State::TributeSkipped =>
-				{
-					self.ticks_in_4sec = 100;
-				}
-				State::DecreeViolated { .. } =>
-				{
-					self.num_decrees = 0;
-				}
-				State::TributeFailed =>
-				{
-					if self.tutorial == Some(Tutorial::Tribute)
-					{
-						self.num_decrees = 0;
-					}
-					self.state = State::Shuffling;
-					self.ticks_in_4sec = 0;
-				}
-				State::GameOver =>
-				{
-					if self.tutorial.is_some()
+					else if self.mode == Mode::Daily
 					{
-						return Some(Transition { rng_seed: 202 });
+						return Some(Transition {
+							scenario: self.scenario,
+							rng_seed: self.seed,
+							mode: self.mode,
+							preset: self.preset,
+						});
 					}
 					else
 					{
 						return Some(Transition {
+							scenario: &GENERATED,
 							rng_seed: self.ticks_in_4sec as u64,
+							mode: self.mode,
+							preset: self.preset,
 						});
 					}
 				}
-				_ => (),
+				_ =>
+				{
+					self.apply(Action::Acknowledge, map);
+				}
 			}
 		}
 
+		// Tapping the second button undoes, holding it shows hints instead.
+		let arrow_mask = BUTTON_UP | BUTTON_DOWN | BUTTON_LEFT | BUTTON_RIGHT;
+		if gamepad & BUTTON_2 != 0 && self.previous_gamepad & BUTTON_2 == 0
+		{
+			self.is_undo_tap = true;
+		}
+		else if gamepad & arrow_mask != 0
+		{
+			self.is_undo_tap = false;
+		}
+		let is_undo_tapped = self.is_undo_tap
+			&& gamepad & BUTTON_2 == 0
+			&& self.previous_gamepad & BUTTON_2 != 0;
+		let is_right_clicked = mousebuttons & MOUSE_RIGHT != 0
+			&& self.previous_mousebuttons & MOUSE_RIGHT == 0;
+		if (is_undo_tapped || is_right_clicked)
+			&& self.settings.get(Setting::Undo)
+			&& self.rules.can_undo()
+		{
+			self.apply(Action::Undo, map);
+		}
+
 		self.ticks_in_4sec += 1;
 		if self.ticks_in_4sec == 240
 		{
@@ -955,430 +455,135 @@ impl Level
 		None
 	}
 
-	fn figure_out_combat(&mut self, region_id: i8, card: Card)
+	fn apply(&mut self, action: Action, map: &mut Map)
 	{
-		let mut supporters = Bitmap::<MAX_NUM_REGIONS>::default();
-		let mut enemies = Bitmap::<MAX_NUM_REGIONS>::default();
-		let mut occupants = Bitmap::<MAX_NUM_REGIONS>::default();
-		let mut num_supporters = 0;
-		let mut num_enemies = 0;
-		let mut num_occupants = 0;
-		for i in (0..(self.num_regions as usize))
-			.filter(|i| *i as i8 != region_id)
-			.filter(|i| self.adjacency[region_id as usize].get(*i))
+		let previous_state = self.rules.state();
+		let was_over = self.rules.is_over();
+		let events = self.rules.apply(action);
+		let mut sound = None;
+		for event in events.iter()
 		{
-			match (self.region_data[i].marker, card)
+			match *event
 			{
-				(Some(Marker::Worker), Card::Worker) =>
+				Event::MarkerChanged { region_id, marker } =>
 				{
-					if self.region_data[i].terrain_type != TerrainType::Village
+					map.set_marker_in_region(region_id, marker);
+					if let Action::Place { .. } = action
 					{
-						supporters.set(i, true);
-						num_supporters += 1;
+						match marker
+						{
+							Some(Marker::DeadWorker | Marker::DeadRoman) =>
+							{
+								sound = Some(Sound::Kill);
+							}
+							Some(Marker::Worker) if sound.is_none() =>
+							{
+								sound = Some(Sound::PlaceWorker);
+							}
+							_ => (),
+						}
 					}
 				}
-				(Some(Marker::Roman), Card::Worker) =>
-				{
-					enemies.set(i, true);
-					num_enemies += 1;
-				}
-				(Some(Marker::Occupied), Card::Worker) =>
+				Event::VillageBuilt { region_id } =>
 				{
-					occupants.set(i, true);
-					num_occupants += 1;
-				}
-				(Some(Marker::Roman), Card::Roman) =>
-				{
-					supporters.set(i, true);
-					num_supporters += 1;
-				}
-				(Some(Marker::Worker), Card::Roman) =>
-				{
-					enemies.set(i, true);
-					num_enemies += 1;
+					map.place_village(region_id);
+					sound = Some(Sound::VillageBuilt);
 				}
-				(Some(Marker::Occupied), Card::Roman) =>
+				Event::RegionOccupied { region_id } =>
 				{
-					supporters.set(i, true);
-					num_supporters += 1;
+					map.occupy_region(region_id);
+					sound = Some(Sound::Occupation);
 				}
-				(Some(Marker::DeadRoman), _) => (),
-				(Some(Marker::DeadWorker), _) => (),
-				(Some(Marker::FogOfWar), _) => (),
-				(None, _) => (),
-			}
-		}
-		if num_enemies == 1 && num_occupants == 0
-		{
-			self.kill_preview = enemies;
-			self.attack_preview = Bitmap::new();
-			if num_supporters == 0
-			{
-				self.kill_preview.set(region_id as usize, true);
+				// The date is drawn straight from the rules.
+				Event::DayStarted { .. } => (),
 			}
-			else
-			{
-				self.attack_preview.set(region_id as usize, true);
-			}
-			self.support_preview = supporters;
 		}
-		else if num_enemies + num_occupants > 0
+		if self.rules.state() != previous_state
 		{
-			if num_supporters < num_enemies + num_occupants
+			match self.rules.state()
 			{
-				self.kill_preview = Bitmap::new();
-				self.kill_preview.set(region_id as usize, true);
-				self.attack_preview = enemies | occupants;
-			}
-			else
-			{
-				self.kill_preview = enemies;
-				self.attack_preview = Bitmap::new();
-				if num_occupants > 0
-				{
-					self.kill_preview.set(region_id as usize, true);
-				}
-				else if num_enemies > 0
+				State::DecreeViolated { .. } =>
 				{
-					self.attack_preview.set(region_id as usize, true);
+					sound = Some(Sound::DecreeViolated);
 				}
+				State::TributePaid => sound = Some(Sound::TributePaid),
+				State::TributeFailed => sound = Some(Sound::TributeFailed),
+				_ => (),
 			}
-			self.support_preview = supporters;
-		}
-		else
-		{
-			self.gather_preview = supporters;
-		}
-	}
-
-	fn count_remaining_spaces(&self) -> usize
-	{
-		self.region_data[0..(self.num_regions as usize)]
-			.iter()
-			.filter(|region| region.marker.is_none())
-			.filter(|region| region.terrain_type != TerrainType::Water)
-			.count()
-	}
-
-	fn shuffle(&mut self)
-	{
-		let num_villages = self.region_data[0..(self.num_regions as usize)]
-			.iter()
-			.filter(|region| region.terrain_type == TerrainType::Village)
-			.filter(|region| region.marker == Some(Marker::Worker))
-			.count();
-		let num_workers = 4 + num_villages as u8;
-		if self.grain >= num_workers
-		{
-			self.grain -= num_workers;
-		}
-		else if self.grain + self.wine >= num_workers
-		{
-			self.wine += self.grain;
-			self.wine -= num_workers;
-			self.grain = 0;
-		}
-		else
-		{
-			self.grain = 0;
-			self.wine = 0;
-		}
-		let num_remaining_spaces = self.count_remaining_spaces();
-		self.num_cards = std::cmp::min(
-			num_workers + self.threat_level,
-			num_remaining_spaces as u8,
-		);
-		for i in 0..self.num_cards
-		{
-			let card = if 2 * (i / 3) >= num_workers
-			{
-				Card::Roman
-			}
-			else if i / 3 >= self.threat_level
-			{
-				Card::Worker
-			}
-			else if i % 3 == 2
-			{
-				Card::Roman
-			}
-			else
-			{
-				Card::Worker
-			};
-			self.card_deck[i as usize] = card;
 		}
-		self.card_offset = 0;
-	}
-
-	fn pick_decrees(&mut self)
-	{
-		self.num_decrees = 0;
-		if self.threat_level == 0
+		if let Some(sound) = sound
 		{
-			if self.tutorial.is_some()
+			if self.settings.get(Setting::Sound)
 			{
-				self.decree_data[self.num_decrees as usize] = Decree::Dummy;
-				self.num_decrees += 1;
+				sound.play();
 			}
-			else
-			{
-				let decree = Decree::NoWorkersAdjacent;
-				self.decree_data[self.num_decrees as usize] = decree;
-				self.num_decrees += 1;
-			}
-			return;
 		}
-		self.decree_data[self.num_decrees as usize] = Decree::AllRomansAdjacent;
-		self.num_decrees += 1;
-		if self.tutorial.is_some()
+		if action == Action::Proceed || self.rules.state() != previous_state
 		{
-			if self.threat_level >= 4
-			{
-				let decree = Decree::Regional {
-					all_or_none: AllOrNone::All,
-					marker: Marker::Roman,
-					in_or_near: InOrNear::In,
-					terrain_type: TerrainType::Grass,
-				};
-				self.decree_data[self.num_decrees as usize] = decree;
-				self.num_decrees += 1;
-			}
-			if self.threat_level >= 9
-			{
-				let decree = Decree::Regional {
-					all_or_none: AllOrNone::All,
-					marker: Marker::Roman,
-					in_or_near: InOrNear::Near,
-					terrain_type: TerrainType::Forest,
-				};
-				self.decree_data[self.num_decrees as usize] = decree;
-				self.num_decrees += 1;
-			}
-			self.decree_data[self.num_decrees as usize] =
-				Decree::NoRomansInAmbush;
-			self.num_decrees += 1;
+			self.ticks_in_4sec = 0;
 		}
-		else
+		if self.rules.is_over() && !was_over
 		{
-			let difficulty_level = match self.threat_level
-			{
-				0..=1 => 0,
-				2..=3 => 1,
-				4..=5 => 2,
-				6 => 3,
-				7.. => 4,
+			let score = Score {
+				score: self.rules.score(),
+				seed: self.seed,
+				years_survived: self.rules.years_survived(),
+				villages_built: self.rules.villages_built(),
 			};
-			self.rng.shuffle(
-				&mut self.decree_data
-					[(self.num_decrees as usize)..TOTAL_NUM_DECREES],
-			);
-			self.num_decrees += difficulty_level;
-			if let Some(offset) = self
-				.decree_data
-				.iter()
-				.position(|decree| *decree == Decree::NoRomansInAmbush)
-			{
-				if offset != self.num_decrees as usize
-				{
-					self.decree_data[offset] =
-						self.decree_data[self.num_decrees as usize];
-					self.decree_data[self.num_decrees as usize] =
-						Decree::NoRomansInAmbush;
-				}
-			}
-			self.num_decrees += 1;
-		}
-	}
-
-	fn place_marker(&mut self, map: &mut Map)
-	{
-		let (region_id, marker) = match self.hover_preview
-		{
-			Some(Preview::PlaceWorker {
-				region_id,
-				terrain_type,
-			}) =>
+			match self.mode
 			{
-				self.score += 1;
-				if self.kill_preview.get(region_id as usize)
+				Mode::Daily =>
 				{
-					(region_id, Marker::DeadWorker)
+					Daily::load().submit(score.score);
 				}
-				else if terrain_type == TerrainType::Grass
-					&& self.gather_preview.len() >= 2
-					&& self.wood >= VILLAGE_WOOD_COST
-					&& self.gold >= VILLAGE_GOLD_COST
+				Mode::Campaign =>
 				{
-					self.score += 9;
-					self.grain += 1;
-					self.wood -= VILLAGE_WOOD_COST;
-					self.gold -= VILLAGE_GOLD_COST;
-					for j in self.gather_preview.into_iter()
+					// There is no leaderboard for the campaign, only the
+					// progress through its chapters.
+					if let Some(chapter) = Chapter::of(self.scenario)
 					{
-						if self.region_data[j].marker != Some(Marker::Worker)
+						if self.rules.state() == State::Victory
 						{
-							continue;
-						}
-						match self.region_data[j].terrain_type
-						{
-							TerrainType::Village => (),
-							TerrainType::Grass => self.grain += 1,
-							TerrainType::Forest => self.wood += 1,
-							TerrainType::Hill => self.wine += 1,
-							TerrainType::Mountain => self.gold += 1,
-							TerrainType::Water => (),
-						}
-					}
-					map.place_village(region_id);
-					self.region_data[region_id as usize].terrain_type =
-						TerrainType::Village;
-					(region_id, Marker::Worker)
-				}
-				else
-				{
-					match terrain_type
-					{
-						TerrainType::Village =>
-						{
-							self.grain += 1;
-							for j in self.gather_preview.into_iter()
-							{
-								if self.region_data[j].marker
-									!= Some(Marker::Worker)
-								{
-									continue;
-								}
-								match self.region_data[j].terrain_type
-								{
-									TerrainType::Village => (),
-									TerrainType::Grass => self.grain += 1,
-									TerrainType::Forest => self.wood += 1,
-									TerrainType::Hill => self.wine += 1,
-									TerrainType::Mountain => self.gold += 1,
-									TerrainType::Water => (),
-								}
-							}
-						}
-						TerrainType::Grass => self.grain += 1,
-						TerrainType::Forest => self.wood += 1,
-						TerrainType::Hill => self.wine += 1,
-						TerrainType::Mountain => self.gold += 1,
-						TerrainType::Water => (),
-					}
-					(region_id, Marker::Worker)
-				}
-			}
-			Some(Preview::PlaceRoman { region_id }) =>
-			{
-				if self.kill_preview.get(region_id as usize)
-				{
-					(region_id, Marker::DeadRoman)
-				}
-				else
-				{
-					(region_id, Marker::Roman)
-				}
-			}
-			_ => return,
-		};
-		self.region_data[region_id as usize].marker = Some(marker);
-		map.set_marker_in_region(region_id, Some(marker));
-		for i in self.kill_preview.into_iter()
-		{
-			if i != region_id as usize
-			{
-				let killed = match self.region_data[i].marker
-				{
-					Some(Marker::Roman) => Some(Marker::DeadRoman),
-					Some(Marker::Worker) => Some(Marker::DeadWorker),
-					_ => None,
-				};
-				self.region_data[i].marker = killed;
-				map.set_marker_in_region(i as i8, killed);
-			}
-		}
-		let alive_marker = match marker
-		{
-			Marker::DeadRoman => Marker::Roman,
-			Marker::DeadWorker => Marker::Worker,
-			x => x,
-		};
-		let violated_decree_offset =
-			(0..(self.num_decrees as usize)).find(|offset| {
-				match self.decree_data[*offset]
-				{
-					Decree::Regional { .. }
-						if self.region_data[region_id as usize]
-							.terrain_type == TerrainType::Village =>
-					{
-						false
-					}
-					Decree::Regional { marker: m, .. } if m != alive_marker =>
-					{
-						false
-					}
-					Decree::Regional {
-						all_or_none,
-						marker: _,
-						in_or_near,
-						terrain_type,
-					} =>
-					{
-						let matches = match in_or_near
-						{
-							InOrNear::In =>
-							{
-								self.region_data[region_id as usize]
-									.terrain_type == terrain_type
-							}
-							InOrNear::Near => self.adjacency
-								[region_id as usize]
-								.into_iter()
-								.any(|adj_id| {
-									self.region_data[adj_id].terrain_type
-										== terrain_type
-								}),
-						};
-						match all_or_none
-						{
-							AllOrNone::All => !matches,
-							AllOrNone::None => matches,
-						}
-					}
-					Decree::NoWorkersAdjacent =>
-					{
-						alive_marker == Marker::Worker
-							&& (!self.support_preview.is_empty()
-								|| !self.gather_preview.is_empty())
-					}
-					Decree::AllRomansAdjacent =>
-					{
-						alive_marker == Marker::Roman
-							&& self.support_preview.is_empty()
-							&& self.gather_preview.is_empty()
+							Progress::load().complete(chapter);
+						}
 					}
-					Decree::NoRomansInAmbush => marker == Marker::DeadRoman,
-					Decree::Dummy => false,
 				}
-			});
-		if let Some(offset) = violated_decree_offset
-		{
-			self.num_cards = 0;
-			if self.threat_level < MAX_THREAT_LEVEL
-			{
-				self.threat_level += 1;
+				mode =>
+				{
+					Leaderboard::load().submit(mode, score);
+				}
 			}
-			self.state = State::DecreeViolated {
-				decree_offset: offset as u8,
-			};
 		}
-		else
+		// Automatic steps follow each other quickly, so only save once the
+		// game waits for the player again.
+		let state = self.rules.state();
+		if action != Action::Proceed
+			|| (state != previous_state
+				&& (state == State::Placement
+					|| proceed_delay(state).is_none()))
 		{
-			self.card_offset += 1;
+			self.save();
 		}
 	}
 
+	fn save(&self)
+	{
+		if self.rules.is_over()
+		{
+			disk::erase(disk::SAVED_GAME);
+			return;
+		}
+		let mut buffer = [0u8; disk::SAVED_GAME.size];
+		let mut writer = Writer::new(&mut buffer);
+		writer.write_u8(SAVE_FORMAT_VERSION);
+		writer.write_u64(self.seed);
+		writer.write_u8(self.mode as u8);
+		writer.write_u8(self.preset as u8);
+		writer.write_u8(self.scenario.id);
+		self.rules.save(&mut writer);
+		disk::write(disk::SAVED_GAME, &buffer);
+	}
+
 	pub fn draw(&mut self)
 	{
 		{
@@ -1422,15 +627,17 @@ impl Level
 				Some(Preview::CannotPlaceRoman) => palette::ROMAN,
 				Some(Preview::HoverDecrees) => palette::ROMAN,
 				Some(Preview::HoverObjectives) => palette::WATER,
-				None => match self.state
+				None => match self.rules.state()
 				{
 					State::DecreeViolated { .. } => palette::ROMAN,
+					State::NoLegalPlacement => palette::ROMAN,
 					State::TributeFailed => palette::ROMAN,
 					State::GameOver => palette::ROMAN,
+					State::Victory => palette::GOLD,
 					_ => palette::DEFAULT,
 				},
 			};
-			unsafe { *PALETTE = palette };
+			set_palette(palette);
 		}
 
 		{
@@ -1452,22 +659,24 @@ impl Level
 			};
 
 			let map = MAP.get_mut();
-			map.draw(
-				region_id,
-				highlighted_terrain,
-				self.kill_preview,
-				self.attack_preview,
-				self.support_preview,
-				self.gather_preview,
-				&self.cursor,
-			);
+			let options = DrawOptions {
+				hovered_region_id: region_id,
+				highlighted_terrain_type: highlighted_terrain,
+				highlighted_regions: self.rules.tutorial_targets(),
+				kill_preview: self.kill_preview,
+				attack_preview: self.attack_preview,
+				support_preview: self.support_preview,
+				gather_preview: self.gather_preview,
+				is_violation_preview: self.violation_preview.is_some(),
+			};
+			map.draw(&options, &self.cursor);
 		}
 
-		if self.card_offset < self.num_cards
+		if self.rules.active_card().is_some()
 		{
-			unsafe { *DRAW_COLORS = 0x31 };
-			rect(-1, 9, 9, 3 + 7 * (self.num_cards as u32));
-			unsafe { *DRAW_COLORS = 3 };
+			set_draw_colors(0x31);
+			rect(-1, 9, 9, 3 + 7 * (self.rules.num_cards() as u32));
+			set_draw_colors(3);
 			hline(0, 17, 9);
 
 			match self.hover_preview
@@ -1475,20 +684,17 @@ impl Level
 				Some(Preview::PlaceRoman { .. })
 				| Some(Preview::CannotPlaceRoman) =>
 				{
-					unsafe { *DRAW_COLORS = 0x44 };
+					set_draw_colors(0x44);
 				}
 				_ =>
 				{
-					unsafe { *DRAW_COLORS = 0x22 };
+					set_draw_colors(0x22);
 				}
 			}
 			rect(0, 10, 7, 7);
 
-			unsafe { *DRAW_COLORS = 0x4320 };
-			let remaining_card_offsets =
-				(self.card_offset as usize)..(self.num_cards as usize);
-			for (i, card) in
-				self.card_deck[remaining_card_offsets].iter().enumerate()
+			set_draw_colors(0x4320);
+			for (i, card) in self.rules.remaining_cards().iter().enumerate()
 			{
 				let y = 11 + 1 * ((i > 0) as i32) + 7 * (i as i32);
 				let alt = match card
@@ -1500,12 +706,12 @@ impl Level
 			}
 		}
 
-		unsafe { *DRAW_COLORS = 0x11 };
+		set_draw_colors(0x11);
 		rect(0, 0, 160, 10);
-		unsafe { *DRAW_COLORS = 3 };
+		set_draw_colors(3);
 		hline(0, 9, 160);
 
-		unsafe { *DRAW_COLORS = 0x40 };
+		set_draw_colors(0x40);
 		match self.hover_preview
 		{
 			Some(Preview::HoverResource { terrain_type }) =>
@@ -1553,167 +759,194 @@ impl Level
 			_ => (),
 		}
 
-		unsafe { *DRAW_COLORS = 0x3210 };
+		set_draw_colors(0x3210);
 		sprites::draw_score_icon(-3, 0);
-		unsafe { *DRAW_COLORS = 3 };
-		draw_score(self.score, 6, 1);
+		set_draw_colors(3);
+		draw_score(self.rules.score(), 6, 1);
 
-		unsafe { *DRAW_COLORS = 0x3210 };
+		set_draw_colors(0x3210);
 		sprites::draw_grain_icon(UI_X_GRAIN, 0);
-		unsafe { *DRAW_COLORS = 3 };
-		draw_resource_value(self.grain, UI_X_GRAIN + 8, 1);
-		unsafe { *DRAW_COLORS = 0x3210 };
+		set_draw_colors(3);
+		draw_resource_value(self.rules.grain(), UI_X_GRAIN + 8, 1);
+		set_draw_colors(0x3210);
 		sprites::draw_wood_icon(UI_X_WOOD, 0);
-		unsafe { *DRAW_COLORS = 3 };
-		draw_resource_value(self.wood, UI_X_WOOD + 8, 1);
-		unsafe { *DRAW_COLORS = 0x3210 };
+		set_draw_colors(3);
+		draw_resource_value(self.rules.wood(), UI_X_WOOD + 8, 1);
+		set_draw_colors(0x3210);
 		sprites::draw_wine_icon(UI_X_WINE, 0);
-		unsafe { *DRAW_COLORS = 3 };
-		draw_resource_value(self.wine, UI_X_WINE + 8, 1);
-		unsafe { *DRAW_COLORS = 0x3210 };
+		set_draw_colors(3);
+		draw_resource_value(self.rules.wine(), UI_X_WINE + 8, 1);
+		set_draw_colors(0x3210);
 		sprites::draw_gold_icon(UI_X_GOLD, 0);
-		unsafe { *DRAW_COLORS = 3 };
-		draw_resource_value(self.gold, UI_X_GOLD + 8, 1);
+		set_draw_colors(3);
+		draw_resource_value(self.rules.gold(), UI_X_GOLD + 8, 1);
 
-		unsafe { *DRAW_COLORS = 0x3210 };
+		set_draw_colors(0x3210);
 		sprites::draw_wreath_icon((SCREEN_SIZE as i32) - 17, 0);
-		unsafe { *DRAW_COLORS = 3 };
-		if self.threat_level < MAX_THREAT_LEVEL
+		set_draw_colors(3);
+		let threat_level = self.rules.threat_level();
+		if threat_level < MAX_THREAT_LEVEL
 		{
-			draw_threat_value(self.threat_level, (SCREEN_SIZE as i32) - 8, 1);
+			draw_threat_value(threat_level, (SCREEN_SIZE as i32) - 8, 1);
 		}
 		else
 		{
 			text("X", (SCREEN_SIZE as i32) - 8, 1);
 		}
 
+		// The date hangs below the right end of the bar.
+		let (year, day) = (self.rules.year(), self.rules.day());
+		let width = roman_numeral(year, None) + 4 + day_width(day);
+		let x = (SCREEN_SIZE as i32) - width - 2;
+		set_draw_colors(0x31);
+		rect(x - 3, 9, (width + 5) as u32, 10);
+		set_draw_colors(1);
+		hline(x - 2, 9, (width + 3) as u32);
+		set_draw_colors(3);
+		let offset = roman_numeral(year, Some((x, 10)));
+		draw_number(day.into(), x + offset + 4, 10);
+
 		match self.hover_preview
 		{
-			Some(Preview::HoverObjectives) if self.tutorial.is_some() =>
+			Some(Preview::HoverObjectives)
+				if self.rules.tutorial().is_some() =>
 			{
-				unsafe { *DRAW_COLORS = 0x31 };
+				set_draw_colors(0x31);
 				rect(20, 20, 120, 130);
 
-				if let Some(tutorial) = self.tutorial
+				if let Some(tutorial) = self.rules.tutorial()
 				{
 					tutorial.draw(25, 25);
 				}
 			}
 			Some(Preview::HoverObjectives) =>
 			{
-				unsafe { *DRAW_COLORS = 0x31 };
-				rect(20, 20, 120, 120);
+				set_draw_colors(0x31);
+				let height = if self.scenario.goal.is_some()
+				{
+					135
+				}
+				else
+				{
+					120
+				};
+				rect(20, 20, 120, height);
 
-				unsafe { *DRAW_COLORS = 0x03 };
+				set_draw_colors(0x03);
 				let x = 25;
 				let mut y = 25;
 				text("Banner", x, y);
-				unsafe { *DRAW_COLORS = 0x3210 };
+				set_draw_colors(0x3210);
 				sprites::draw_score_icon(x + 102, y - 1);
-				unsafe { *DRAW_COLORS = 0x03 };
+				set_draw_colors(0x03);
 				text("1", x + 94, y);
 				y += 10;
 				text("Cost:  1", x, y);
-				unsafe { *DRAW_COLORS = 0x3210 };
+				set_draw_colors(0x3210);
 				sprites::draw_grain_icon(x + 66, y - 1);
-				unsafe { *DRAW_COLORS = 0x03 };
+				set_draw_colors(0x03);
 				text("/ 1", x + 78, y);
-				unsafe { *DRAW_COLORS = 0x3210 };
+				set_draw_colors(0x3210);
 				sprites::draw_wine_icon(x + 104, y - 1);
-				unsafe { *DRAW_COLORS = 0x03 };
+				set_draw_colors(0x03);
 				y += 22;
 				text("Village", x, y);
-				unsafe { *DRAW_COLORS = 0x3210 };
+				set_draw_colors(0x3210);
 				sprites::draw_score_icon(x + 102, y - 1);
-				unsafe { *DRAW_COLORS = 0x03 };
+				set_draw_colors(0x03);
 				text("10", x + 86, y);
 				y += 10;
 				text("Cost: 10", x, y);
-				unsafe { *DRAW_COLORS = 0x3210 };
+				set_draw_colors(0x3210);
 				sprites::draw_wood_icon(x + 66, y - 1);
-				unsafe { *DRAW_COLORS = 0x03 };
+				set_draw_colors(0x03);
 				text("+ 5", x + 78, y);
-				unsafe { *DRAW_COLORS = 0x3210 };
+				set_draw_colors(0x3210);
 				sprites::draw_gold_icon(x + 104, y - 1);
-				unsafe { *DRAW_COLORS = 0x03 };
+				set_draw_colors(0x03);
 				y += 10;
 				text("Place", x, y);
-				unsafe { *DRAW_COLORS = 0x3210 };
+				set_draw_colors(0x3210);
 				sprites::draw_flag(x + 46 + 2, y + 9, 2);
-				unsafe { *DRAW_COLORS = 0x03 };
+				set_draw_colors(0x03);
 				text("in", x + 56, y);
-				unsafe { *DRAW_COLORS = 0x1320 };
+				set_draw_colors(0x1320);
 				sprites::draw_grass(x + 76, y + 1, 0);
 				sprites::draw_grass(x + 76 + 4, y + 6, 4);
 				sprites::draw_grass(x + 76 + 8, y + 1, 5);
-				unsafe { *DRAW_COLORS = 0x03 };
+				set_draw_colors(0x03);
 				y += 10;
 				text("with 2", x, y);
-				unsafe { *DRAW_COLORS = 0x2310 };
+				set_draw_colors(0x2310);
 				sprites::draw_gathering_town(x + 54 + 2, y + 1);
-				unsafe { *DRAW_COLORS = 0x3210 };
+				set_draw_colors(0x3210);
 				sprites::draw_flag(x + 64 + 2, y + 9, 2);
-				unsafe { *DRAW_COLORS = 0x03 };
+				set_draw_colors(0x03);
 				text(".", x + 74, y);
 				y += 10;
 				text("Once built,", x, y);
 				y += 10;
 				text("gain ", x, y);
-				unsafe { *DRAW_COLORS = 0x3210 };
+				set_draw_colors(0x3210);
 				sprites::draw_grain_icon(x + 36, y - 1);
 				sprites::draw_wood_icon(x + 44, y - 1);
 				sprites::draw_gold_icon(x + 52, y - 1);
 				sprites::draw_wine_icon(x + 60, y - 1);
-				unsafe { *DRAW_COLORS = 0x03 };
+				set_draw_colors(0x03);
 				text("of", x + 72, y);
-				unsafe { *DRAW_COLORS = 0x2310 };
+				set_draw_colors(0x2310);
 				sprites::draw_gathering_town(x + 92 + 2, y + 1);
-				unsafe { *DRAW_COLORS = 0x3210 };
+				set_draw_colors(0x3210);
 				sprites::draw_flag(x + 102 + 2, y + 9, 2);
-				unsafe { *DRAW_COLORS = 0x03 };
+				set_draw_colors(0x03);
 				y += 10;
 				text("and +1", x, y);
-				unsafe { *DRAW_COLORS = 0x3210 };
+				set_draw_colors(0x3210);
 				sprites::draw_flag(x + 52 + 2, y + 9, 2);
-				unsafe { *DRAW_COLORS = 0x03 };
+				set_draw_colors(0x03);
 				text(".", x + 62, y);
 				y += 10;
 				text("Ignore decree.", x, y);
+				if let Some(goal) = self.scenario.goal
+				{
+					y += 15;
+					goal.draw(x, y);
+				}
 			}
 			Some(Preview::HoverDecrees) =>
 			{
-				unsafe { *DRAW_COLORS = 0x31 };
+				set_draw_colors(0x31);
 				rect(60, 20, 90, 130);
 
-				unsafe { *DRAW_COLORS = 4 };
+				set_draw_colors(4);
 				let x = 63;
 				let mut y = 25;
 				text("IMPERIAL", x + 10, y);
 				y += 8;
 				text("DECREE", x + 10 + 8, y);
-				unsafe { *DRAW_COLORS = 3 };
-				for decree in &self.decree_data[0..(self.num_decrees as usize)]
+				set_draw_colors(3);
+				for decree in self.rules.decrees()
 				{
 					y += 15;
 					decree.draw(x, y);
 				}
-				if self.tribute > 0
+				if self.rules.tribute() > 0
 				{
 					y = 139;
-					unsafe { *DRAW_COLORS = 0x03 };
+					set_draw_colors(0x03);
 					text("Tribute:", x, y);
-					draw_threat_value(self.tribute, x + 67, y);
-					unsafe { *DRAW_COLORS = 0x3210 };
+					draw_threat_value(self.rules.tribute(), x + 67, y);
+					set_draw_colors(0x3210);
 					sprites::draw_wine_icon(x + 76, y - 1);
 				}
 			}
-			_ => match self.state
+			_ => match self.rules.state()
 			{
 				State::DecreeViolated { decree_offset } =>
 				{
-					unsafe { *DRAW_COLORS = 0x31 };
+					set_draw_colors(0x31);
 					rect(10, 60, 140, 58);
-					unsafe { *DRAW_COLORS = 0x03 };
+					set_draw_colors(0x03);
 					let x = 15;
 					let mut y = 60 + 6;
 					text("Imperial decrees", x, y);
@@ -1721,24 +954,37 @@ impl Level
 					text("are absolute!", x, y);
 					y += 15;
 					text("(", x, y);
-					let decree = &self.decree_data[decree_offset as usize];
+					let decree = self.rules.decree(decree_offset);
 					let w = decree.draw(x + 8, y);
-					unsafe { *DRAW_COLORS = 0x03 };
+					set_draw_colors(0x03);
 					text(")", x + 4 + w, y);
 					y += 15;
-					unsafe { *DRAW_COLORS = 0x40 };
+					set_draw_colors(0x40);
 					sprites::draw_backfill(x + 49, y - 1, 0);
-					unsafe { *DRAW_COLORS = 0x03 };
+					set_draw_colors(0x03);
 					text("+1", x + 52, y);
-					unsafe { *DRAW_COLORS = 0x3210 };
+					set_draw_colors(0x3210);
 					sprites::draw_wreath_icon(x + 70, y - 1);
-					unsafe { *DRAW_COLORS = 0x03 };
+					set_draw_colors(0x03);
+				}
+				State::NoLegalPlacement =>
+				{
+					set_draw_colors(0x31);
+					rect(10, 60, 140, 43);
+					set_draw_colors(0x03);
+					let x = 15;
+					let mut y = 60 + 6;
+					text("This Roman has", x, y);
+					y += 8;
+					text("nowhere lawful", x, y);
+					y += 8;
+					text("to go and leaves.", x, y);
 				}
 				State::TributeSkipped =>
 				{
-					unsafe { *DRAW_COLORS = 0x31 };
+					set_draw_colors(0x31);
 					rect(10, 60, 140, 35);
-					unsafe { *DRAW_COLORS = 0x03 };
+					set_draw_colors(0x03);
 					let x = 15;
 					let mut y = 60 + 6;
 					text("A new year", x, y);
@@ -1747,62 +993,179 @@ impl Level
 				}
 				State::TributePaid =>
 				{
-					unsafe { *DRAW_COLORS = 0x31 };
+					set_draw_colors(0x31);
 					rect(10, 60, 140, 35);
-					unsafe { *DRAW_COLORS = 0x03 };
+					set_draw_colors(0x03);
 					let x = 15;
 					let mut y = 60 + 6;
 					text("Tribute paid.", x, y);
 					y += 15;
 					text("New tribute:", x, y);
-					draw_threat_value(self.tribute, x + 114, y);
-					unsafe { *DRAW_COLORS = 0x3210 };
+					draw_threat_value(self.rules.tribute(), x + 114, y);
+					set_draw_colors(0x3210);
 					sprites::draw_wine_icon(x + 123, y - 1);
 				}
 				State::TributeFailed =>
 				{
-					unsafe { *DRAW_COLORS = 0x31 };
+					set_draw_colors(0x31);
 					rect(10, 60, 140, 58);
-					unsafe { *DRAW_COLORS = 0x03 };
+					set_draw_colors(0x03);
 					let x = 15;
 					let mut y = 60 + 6;
 					text("You dare refuse", x, y);
 					y += 8;
 					text("to pay tribute?!", x, y);
 					y += 15;
-					unsafe { *DRAW_COLORS = 0x40 };
+					set_draw_colors(0x40);
 					sprites::draw_backfill(x + 49, y - 1, 0);
-					unsafe { *DRAW_COLORS = 0x03 };
+					set_draw_colors(0x03);
 					text("+1", x + 52, y);
-					unsafe { *DRAW_COLORS = 0x3210 };
+					set_draw_colors(0x3210);
 					sprites::draw_wreath_icon(x + 70, y - 1);
-					unsafe { *DRAW_COLORS = 0x03 };
+					set_draw_colors(0x03);
 					y += 15;
 					text("New tribute:", x, y);
-					draw_threat_value(self.tribute, x + 114, y);
-					unsafe { *DRAW_COLORS = 0x3210 };
+					draw_threat_value(self.rules.tribute(), x + 114, y);
+					set_draw_colors(0x3210);
 					sprites::draw_wine_icon(x + 123, y - 1);
 				}
 				State::GameOver =>
 				{
-					unsafe { *DRAW_COLORS = 0x31 };
-					rect(10, 60, 140, 35);
-					unsafe { *DRAW_COLORS = 0x03 };
+					let (year, day) = (self.rules.year(), self.rules.day());
+					let overflow = date_overflow(year, day);
+					set_draw_colors(0x31);
+					rect(10, 60, 140, 65 + overflow as u32);
+					set_draw_colors(0x03);
 					let x = 15;
 					let mut y = 60 + 6;
 					text("You have been", x, y);
 					y += 8;
 					text("eradicated.", x, y);
+					y += 15;
+					draw_date(year, day, x, y);
+					y += overflow;
+					if let Some(goal) = self.scenario.goal
+					{
+						y += 15;
+						text("Goal:", x, y);
+						y += 10;
+						goal.draw(x, y);
+					}
+					else if self.rules.tutorial().is_none()
+					{
+						y += 15;
+						text("Seed:", x, y);
+						draw_number(self.seed, x + 48, y);
+						if self.preset != Preset::Classic
+						{
+							y += 10;
+							text(self.preset.name(), x, y);
+						}
+					}
+				}
+				State::Victory =>
+				{
+					let (year, day) = (self.rules.year(), self.rules.day());
+					let overflow = date_overflow(year, day);
+					set_draw_colors(0x31);
+					rect(10, 45, 140, 74 + overflow as u32);
+					set_draw_colors(0x03);
+					let x = 15;
+					let mut y = 45 + 6;
+					text("Victory!", x, y);
+					if let Some(goal) = self.scenario.goal
+					{
+						y += 10;
+						goal.draw(x, y);
+					}
+					y += 15;
+					draw_date(year, day, x, y);
+					y += overflow;
+					let summary = [
+						("Score:", self.rules.score()),
+						("Villages:", self.rules.villages_built() as u16),
+						("Romans:", self.rules.romans_defeated() as u16),
+					];
+					for (label, value) in summary
+					{
+						y += 10;
+						text(label, x, y);
+						draw_number(value as u64, x + 96, y);
+					}
 				}
 				_ => (),
 			},
 		}
+
+		if let Some(decree_offset) = self.violation_preview
+		{
+			let is_cursor_low = if self.cursor.is_mouse_active
+			{
+				self.cursor.mouse_y >= 80
+			}
+			else
+			{
+				self.cursor.row >= 8
+			};
+			let y = if is_cursor_low { 11 } else { 147 };
+			set_draw_colors(0x31);
+			rect(-1, y, 162, 13);
+			set_draw_colors(0x04);
+			text("!", 3, y + 3);
+			set_draw_colors(0x03);
+			self.rules.decree(decree_offset).draw(12, y + 3);
+		}
+	}
+}
+
+fn generate(
+	scenario: &Scenario,
+	seed: u64,
+	preset: Preset,
+	map: &mut Map,
+) -> Rules
+{
+	let mut adjacency = [Bitmap::default(); MAX_NUM_REGIONS];
+	let mut border_adjacency = Bitmap::default();
+	let mut rng = fastrand::Rng::with_seed(seed);
+	match scenario.map
+	{
+		Some(authored) => map.load(authored, &mut rng),
+		None => map.generate(preset.config(), &mut rng),
+	}
+	map.fill_adjacency(&mut adjacency, &mut border_adjacency);
+	Rules::new(scenario, map.regions(), adjacency, border_adjacency, rng)
+}
+
+/// How many ticks the level waits before it proceeds on its own from this
+/// state, or `None` if it waits for the player to acknowledge it.
+fn proceed_delay(state: State) -> Option<u8>
+{
+	match state
+	{
+		State::Setup => Some(0),
+		State::Placement => Some(0),
+		State::Shuffling => Some(5),
+		State::Resolution => Some(20),
+		State::Occupation => Some(80),
+		State::Cleanup => Some(5),
+		State::TributePaid | State::TributeSkipped => Some(90),
+		State::NewObjectives
+		| State::NewDecrees
+		| State::DecreeViolated { .. }
+		| State::NoLegalPlacement
+		| State::TributeFailed
+		| State::GameOver
+		| State::Victory => None,
 	}
 }
 
 pub struct Transition
 {
+	pub scenario: &'static Scenario,
 	pub rng_seed: u64,
+	pub mode: Mode,
+	pub preset: Preset,
 }
 
 fn draw_score(value: u16, x: i32, y: i32)
@@ -1820,6 +1183,100 @@ fn draw_threat_value(value: u8, x: i32, y: i32)
 	draw_decimal_value::<1>(value.into(), x, y);
 }
 
+const ROMAN_NUMERALS: [(&str, u8); 9] = [
+	("C", 100),
+	("XC", 90),
+	("L", 50),
+	("XL", 40),
+	("X", 10),
+	("IX", 9),
+	("V", 5),
+	("IV", 4),
+	("I", 1),
+];
+
+/// Returns the width of a number in Roman numerals, and draws it if a
+/// position is given.
+fn roman_numeral(value: u8, position: Option<(i32, i32)>) -> i32
+{
+	let mut width = 0;
+	let mut value = value;
+	for (numeral, amount) in ROMAN_NUMERALS
+	{
+		while value >= amount
+		{
+			if let Some((x, y)) = position
+			{
+				text(numeral, x + width, y);
+			}
+			width += 8 * (numeral.len() as i32);
+			value -= amount;
+		}
+	}
+	width
+}
+
+/// The width of the day when it is drawn without leading zeroes.
+fn day_width(day: u8) -> i32
+{
+	8 * (1 + (day >= 10) as i32 + (day >= 100) as i32)
+}
+
+/// The width available to the date inside a popup.
+const DATE_MAX_WIDTH: i32 = 130;
+
+/// The extra height the date needs when the day does not fit on the same
+/// line as the year.
+fn date_overflow(year: u8, day: u8) -> i32
+{
+	let width = 40 + roman_numeral(year, None) + 8 + 32 + day_width(day);
+	if width > DATE_MAX_WIDTH
+	{
+		8
+	}
+	else
+	{
+		0
+	}
+}
+
+fn draw_date(year: u8, day: u8, x: i32, y: i32)
+{
+	text("Year", x, y);
+	let width = roman_numeral(year, Some((x + 40, y)));
+	let (x, y) = if date_overflow(year, day) > 0
+	{
+		(x, y + 8)
+	}
+	else
+	{
+		(x + 40 + width + 8, y)
+	};
+	text("Day", x, y);
+	draw_number(day.into(), x + 32, y);
+}
+
+/// Draws a number without leading zeroes and returns its width.
+pub fn draw_number(value: u64, x: i32, y: i32) -> i32
+{
+	let mut buffer = [0u8; 20];
+	let mut start = buffer.len();
+	let mut value = value;
+	loop
+	{
+		start -= 1;
+		buffer[start] = b'0' + (value % 10) as u8;
+		value /= 10;
+		if value == 0
+		{
+			break;
+		}
+	}
+	let txt = unsafe { std::str::from_utf8_unchecked(&buffer[start..]) };
+	text(txt, x, y);
+	8 * (txt.len() as i32)
+}
+
 fn draw_decimal_value<const N: usize>(value: u16, x: i32, y: i32)
 {
 	let mut buffer = [0u8; N];
@@ -1847,9 +1304,22 @@ pub struct Cursor
 
 impl Cursor
 {
+	pub fn new() -> Cursor
+	{
+		Cursor {
+			mouse_x: -1,
+			mouse_y: -1,
+			row: 8,
+			col: 8,
+			is_mouse_active: false,
+			is_resource_bar_active: false,
+			resource_bar_offset: 0,
+		}
+	}
+
 	fn update(&mut self, gamepad: u8, previous_gamepad: u8)
 	{
-		let (mouse_x, mouse_y): (i16, i16) = unsafe { (*MOUSE_X, *MOUSE_Y) };
+		let (mouse_x, mouse_y) = mouse_xy();
 		if (mouse_x != self.mouse_x || mouse_y != self.mouse_y)
 			&& mouse_x >= 0
 			&& mouse_y >= 0
@@ -1919,3 +1389,512 @@ impl Cursor
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::framebuffer::assert_snapshot;
+	use crate::mock::Mock;
+	use crate::platform::run_with;
+	use crate::scenario::TUTORIAL;
+
+	/// Plays the first seed starting from `seed` that reaches a state for
+	/// which `is_target` is true.
+	fn play_until(seed: u64, is_target: impl Fn(State) -> bool) -> Level
+	{
+		play_until_with(seed, false, is_target)
+	}
+
+	fn play_until_with(
+		seed: u64,
+		is_strict: bool,
+		is_target: impl Fn(State) -> bool,
+	) -> Level
+	{
+		for seed in seed..(seed + 100)
+		{
+			let mut level =
+				Level::new(&GENERATED, seed, Mode::Freeplay, Preset::Classic);
+			level.rules.set_strict(is_strict);
+			for step in 0..10000
+			{
+				let state = level.rules.state();
+				if is_target(state)
+				{
+					return level;
+				}
+				else if state == State::GameOver
+				{
+					break;
+				}
+				play_step(&mut level, step);
+			}
+		}
+		panic!("no seed reached the desired state");
+	}
+
+	/// Always places cards in the first available region, counting from a
+	/// different region each step.
+	fn play_step(level: &mut Level, step: usize)
+	{
+		let map = MAP.get_mut();
+		if level.rules.state() == State::Placement
+			&& level.rules.active_card().is_some()
+		{
+			let num_regions = level.rules.num_regions() as usize;
+			let region_id = (0..num_regions)
+				.map(|i| ((i + step * 7) % num_regions) as i8)
+				.find(|&region_id| level.rules.is_legal(region_id))
+				.unwrap();
+			level.apply(Action::Place { region_id }, map);
+			return;
+		}
+		match level.rules.state()
+		{
+			State::NewObjectives
+			| State::NewDecrees
+			| State::DecreeViolated { .. }
+			| State::NoLegalPlacement
+			| State::TributeFailed => level.apply(Action::Acknowledge, map),
+			State::Occupation =>
+			{
+				map.update_occupation_map(100);
+				level.apply(Action::Proceed, map);
+			}
+			_ => level.apply(Action::Proceed, map),
+		}
+	}
+
+	fn snapshot_level(level: &mut Level, name: &str)
+	{
+		let mut mock = Mock::new();
+		run_with(&mut mock, || level.draw());
+		assert_snapshot(&mock.screen, name);
+	}
+
+	#[test]
+	fn level_runs_headless()
+	{
+		let _lock = MAP_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+		let mut mock = Mock::new();
+		run_with(&mut mock, || {
+			let mut level =
+				Level::new(&TUTORIAL, 1, Mode::Start, Preset::Classic);
+			for _ in 0..600
+			{
+				level.update();
+				level.draw();
+			}
+		});
+		assert!(mock.num_draw_calls > 0);
+		assert!(mock.has_text("your highness!"));
+	}
+
+	#[test]
+	fn tutorial_script_runs_to_the_end()
+	{
+		let _lock = MAP_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+		let mut level = Level::new(&TUTORIAL, 1, Mode::Start, Preset::Classic);
+		let mut is_any_highlighted = false;
+		for step in 0..10000
+		{
+			is_any_highlighted |= !level.rules.tutorial_targets().is_empty();
+			match level.rules.tutorial()
+			{
+				Some(tutorial_step) if tutorial_step.until.is_none() =>
+				{
+					assert!(is_any_highlighted);
+					return;
+				}
+				Some(_) => (),
+				None => panic!("the tutorial ended early"),
+			}
+			assert_ne!(level.rules.state(), State::GameOver);
+			play_step(&mut level, step);
+		}
+		panic!("the tutorial got stuck at {:?}", level.rules.state());
+	}
+
+	#[test]
+	fn presets_generate_enough_regions()
+	{
+		let _lock = MAP_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+		for preset in crate::map::PRESETS
+		{
+			let config = preset.config();
+			for seed in 0..10
+			{
+				let level =
+					Level::new(&GENERATED, seed, Mode::Freeplay, preset);
+				let num_regions = level.rules.num_regions();
+				assert!(num_regions >= config.min_num_regions, "{:?}", preset);
+			}
+		}
+	}
+
+	#[test]
+	fn decree_violated_popup()
+	{
+		let _lock = MAP_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+		let mut level = play_until(2, |state| {
+			matches!(state, State::DecreeViolated { .. })
+		});
+		snapshot_level(&mut level, "level_decree_violated");
+	}
+
+	#[test]
+	fn tribute_failed_popup()
+	{
+		let _lock = MAP_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+		let mut level = play_until(2, |state| state == State::TributeFailed);
+		snapshot_level(&mut level, "level_tribute_failed");
+	}
+
+	#[test]
+	fn undo_placements()
+	{
+		let _lock = MAP_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+		let mut mock = Mock::new();
+		run_with(&mut mock, || {
+			let mut level = play_until(7, |state| {
+				matches!(state, State::DecreeViolated { .. })
+			});
+			let threat_level = level.rules.threat_level();
+			assert!(level.rules.can_undo());
+			level.apply(Action::Undo, MAP.get_mut());
+			assert_eq!(level.rules.state(), State::Placement);
+			assert_eq!(level.rules.threat_level(), threat_level - 1);
+			let progress = |rules: &Rules| {
+				let markers: Vec<Option<Marker>> = (0..rules.num_regions())
+					.map(|i| rules.marker(i as i8))
+					.collect();
+				let resources =
+					[rules.grain(), rules.wood(), rules.wine(), rules.gold()];
+				let num_cards = rules.remaining_cards().len();
+				(rules.score(), resources, num_cards, markers)
+			};
+			let before = progress(&level.rules);
+			play_step(&mut level, 0);
+			assert_ne!(progress(&level.rules), before);
+			level.apply(Action::Undo, MAP.get_mut());
+			assert_eq!(level.rules.state(), State::Placement);
+			assert_eq!(progress(&level.rules), before);
+		});
+	}
+
+	#[test]
+	fn violation_preview()
+	{
+		let _lock = MAP_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+		let mut level = play_until(7, |state| {
+			matches!(state, State::DecreeViolated { .. })
+		});
+		level.apply(Action::Undo, MAP.get_mut());
+		let mut num_violations = 0;
+		for region_id in 0..(level.rules.num_regions() as i8)
+		{
+			if !level.rules.can_place(region_id)
+			{
+				continue;
+			}
+			let preview = level.rules.preview_violation(region_id);
+			let mut rules = level.rules.clone();
+			rules.apply(Action::Place { region_id });
+			if rules.active_card().is_none()
+			{
+				rules.apply(Action::Proceed);
+			}
+			let outcome = match rules.state()
+			{
+				State::DecreeViolated { decree_offset } => Some(decree_offset),
+				_ => None,
+			};
+			assert_eq!(preview, outcome, "region {}", region_id);
+			num_violations += preview.is_some() as usize;
+		}
+		assert!(num_violations > 0);
+	}
+
+	#[test]
+	fn strict_mode()
+	{
+		let _lock = MAP_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+		let mut level =
+			Level::new(&GENERATED, 2, Mode::Freeplay, Preset::Classic);
+		let mut step = 0;
+		let region_id = loop
+		{
+			let violation = (0..(level.rules.num_regions() as i8)).find(|&i| {
+				level.rules.active_card() == Some(Card::Roman)
+					&& level.rules.can_place(i)
+					&& level.rules.preview_violation(i).is_some()
+			});
+			if let Some(region_id) = violation
+			{
+				break region_id;
+			}
+			play_step(&mut level, step);
+			step += 1;
+		};
+		let threat_level = level.rules.threat_level();
+		level.rules.set_strict(true);
+		assert!(!level.rules.is_legal(region_id));
+		level.apply(Action::Place { region_id }, MAP.get_mut());
+		assert_eq!(level.rules.state(), State::Placement);
+		assert_eq!(level.rules.marker(region_id), None);
+		assert_eq!(level.rules.threat_level(), threat_level);
+
+		let mut level =
+			play_until_with(2, true, |state| state == State::NoLegalPlacement);
+		let num_cards = level.rules.remaining_cards().len();
+		assert_eq!(level.rules.active_card(), Some(Card::Roman));
+		snapshot_level(&mut level, "level_no_legal_placement");
+		level.apply(Action::Acknowledge, MAP.get_mut());
+		assert_eq!(level.rules.remaining_cards().len(), num_cards - 1);
+	}
+
+	#[test]
+	fn satisfiable_decrees()
+	{
+		use crate::decree::{AllOrNone, Decree, Quantifier, Relation, Target};
+
+		let _lock = MAP_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+		let mut num_days = 0;
+		for seed in 2..12
+		{
+			let mut level =
+				Level::new(&GENERATED, seed, Mode::Freeplay, Preset::Classic);
+			for step in 0..2000
+			{
+				let state = level.rules.state();
+				if state == State::GameOver
+				{
+					break;
+				}
+				else if state == State::NewDecrees
+				{
+					num_days += 1;
+					assert!(level.rules.is_satisfiable(), "seed {}", seed);
+					let decrees = level.rules.decrees();
+					for a in decrees
+					{
+						let Decree::Order {
+							all_or_none: AllOrNone::None,
+							marker,
+							relation: Relation::In,
+							target: Target::Terrain(terrain_type),
+						} = *a
+						else
+						{
+							continue;
+						};
+						assert!(!decrees.contains(&Decree::Count {
+							quantifier: Quantifier::AtLeast,
+							count: 1,
+							marker,
+							relation: Relation::In,
+							terrain_type,
+						}));
+					}
+				}
+				play_step(&mut level, step);
+			}
+		}
+		assert!(num_days > 10);
+	}
+
+	#[test]
+	fn sound_effects()
+	{
+		let _lock = MAP_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+		let mut mock = Mock::new();
+		run_with(&mut mock, || {
+			play_until(7, |state| matches!(state, State::DecreeViolated { .. }))
+		});
+		let last = mock.tones.last().unwrap();
+		assert_eq!(last.flags, TONE_TRIANGLE);
+		assert_eq!(last.frequency, 110 | (55 << 16));
+
+		let mut mock = Mock::new();
+		run_with(&mut mock, || {
+			Settings::load().toggle(Setting::Sound);
+			play_until(7, |state| matches!(state, State::DecreeViolated { .. }))
+		});
+		assert!(mock.tones.is_empty());
+	}
+
+	#[test]
+	fn calendar_counts_days_and_years()
+	{
+		let _lock = MAP_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+		let mut level =
+			Level::new(&GENERATED, 5, Mode::Freeplay, Preset::Classic);
+		let (mut year, mut day) = (1, 0);
+		for step in 0..10000
+		{
+			if level.rules.state() == State::GameOver
+			{
+				break;
+			}
+			let was_shuffling = level.rules.state() == State::Shuffling;
+			play_step(&mut level, step);
+			if level.rules.year() != year
+			{
+				assert_eq!(level.rules.year(), year + 1);
+				assert_eq!(level.rules.day(), 0);
+				(year, day) = (level.rules.year(), 0);
+			}
+			else if was_shuffling && level.rules.state() != State::NewDecrees
+			{
+				day += 1;
+			}
+			assert_eq!(level.rules.day(), day);
+		}
+		assert_eq!(level.rules.state(), State::GameOver);
+		assert!(year > 1);
+		assert_eq!(year, level.rules.years_survived() + 1);
+	}
+
+	#[test]
+	fn dates_fit_in_popups()
+	{
+		let dates = [(1, 0), (18, 12), (27, 3), (28, 3), (38, 12), (188, 255)];
+		let mut mock = Mock::new();
+		run_with(&mut mock, || {
+			let mut y = 5;
+			for (year, day) in dates
+			{
+				set_draw_colors(0x31);
+				let overflow = date_overflow(year, day);
+				rect(10, y, 140, 18 + overflow as u32);
+				set_draw_colors(0x03);
+				draw_date(year, day, 15, y + 5);
+				y += 20 + overflow;
+			}
+		});
+		assert_snapshot(&mock.screen, "dates");
+	}
+
+	#[test]
+	fn game_over_popup()
+	{
+		let _lock = MAP_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+		let mut level = play_until(2, |state| state == State::GameOver);
+		snapshot_level(&mut level, "level_game_over");
+	}
+
+	fn assert_same_game(a: &Level, b: &Level)
+	{
+		assert_eq!(a.scenario.id, b.scenario.id);
+		assert_eq!(a.seed, b.seed);
+		let (a, b) = (&a.rules, &b.rules);
+		assert_eq!(a.state(), b.state());
+		assert_eq!(a.tutorial(), b.tutorial());
+		assert_eq!(a.num_regions(), b.num_regions());
+		for i in 0..a.num_regions()
+		{
+			let region_id = i as i8;
+			assert_eq!(a.terrain_type(region_id), b.terrain_type(region_id));
+			assert_eq!(a.marker(region_id), b.marker(region_id));
+		}
+		assert_eq!(a.num_cards(), b.num_cards());
+		assert_eq!(a.remaining_cards(), b.remaining_cards());
+		assert_eq!(a.decrees(), b.decrees());
+		assert_eq!(a.threat_level(), b.threat_level());
+		assert_eq!(a.tribute(), b.tribute());
+		assert_eq!(a.grain(), b.grain());
+		assert_eq!(a.wood(), b.wood());
+		assert_eq!(a.wine(), b.wine());
+		assert_eq!(a.gold(), b.gold());
+		assert_eq!(a.score(), b.score());
+		assert_eq!(a.year(), b.year());
+		assert_eq!(a.day(), b.day());
+	}
+
+	#[test]
+	fn save_and_resume()
+	{
+		let _lock = MAP_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+		let mut mock = Mock::new();
+		run_with(&mut mock, || {
+			assert!(!Level::has_saved_game());
+			let mut level =
+				play_until(3, |state| state == State::TributeFailed);
+			assert!(Level::has_saved_game());
+			let mut resumed = Level::resume().unwrap();
+			assert_same_game(&level, &resumed);
+			for step in 0..200
+			{
+				play_step(&mut level, step);
+				play_step(&mut resumed, step);
+				assert_same_game(&level, &resumed);
+				if level.rules.state() == State::GameOver
+				{
+					break;
+				}
+			}
+			assert_eq!(level.rules.state(), State::GameOver);
+			assert!(!Level::has_saved_game());
+			let leaderboard = Leaderboard::load();
+			assert!(leaderboard.scores(Mode::Freeplay).any(|entry| {
+				entry.seed == level.seed && entry.score == level.rules.score()
+			}));
+		});
+		assert!(mock.disk.len() <= crate::mock::DISK_SIZE);
+	}
+
+	#[test]
+	fn automatic_steps_are_not_saved()
+	{
+		let _lock = MAP_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+		let mut mock = Mock::new();
+		let mut level = run_with(&mut mock, || {
+			play_until(3, |state| state == State::Resolution)
+		});
+		mock.num_disk_writes = 0;
+		run_with(&mut mock, || {
+			let map = MAP.get_mut();
+			while level.rules.state() == State::Resolution
+			{
+				level.apply(Action::Proceed, map);
+			}
+		});
+		assert_eq!(mock.num_disk_writes, 0);
+	}
+
+	#[test]
+	fn campaign_goal_completes_chapter()
+	{
+		let _lock = MAP_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+		let mut mock = Mock::new();
+		let mut level = run_with(&mut mock, || {
+			let chapter = &CHAPTERS[1];
+			let mut level = Level::new(
+				chapter.scenario,
+				chapter.rng_seed,
+				Mode::Campaign,
+				chapter.preset,
+			);
+			for step in 0..10000
+			{
+				if level.rules.is_over()
+				{
+					break;
+				}
+				play_step(&mut level, step);
+			}
+			assert_eq!(level.rules.state(), State::Victory);
+			let goal = chapter.scenario.goal.unwrap();
+			assert!(goal.is_met(&level.rules));
+			assert!(Progress::load().is_completed(1));
+			assert!(!Level::has_saved_game());
+			assert!(Leaderboard::load()
+				.scores(Mode::Campaign)
+				.next()
+				.is_none());
+			level
+		});
+		snapshot_level(&mut level, "level_victory");
+	}
+}
@@ -6,26 +6,51 @@
 
 mod wasm4;
 
-#[cfg(feature = "buddy-alloc")]
+#[cfg(all(feature = "buddy-alloc", target_arch = "wasm32"))]
 mod alloc;
 
+mod campaign;
 mod decree;
+mod disk;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod export;
+#[cfg(not(target_arch = "wasm32"))]
+mod framebuffer;
 mod global_state;
 mod level;
 mod map;
 mod menu;
+#[cfg(not(target_arch = "wasm32"))]
+mod mock;
+mod music;
 mod palette;
+mod platform;
+mod replay;
+mod rules;
+mod scenario;
+mod scores;
+mod settings;
+mod sound;
 mod sprites;
+mod tutorial;
 mod wreath;
 
 use global_state::Wrapper;
 use level::Level;
 use menu::Menu;
+use music::Sequencer;
+use replay::Recorder;
 
 static GAME: Wrapper<Game> = Wrapper::new(Game::Loading);
+static RECORDER: Wrapper<Recorder> = Wrapper::new(Recorder::new());
+static MUSIC: Wrapper<Sequencer> = Wrapper::new(Sequencer::new());
 
 const QUICK_TEST: bool = false;
+const RECORD_INPUT: bool = false;
 
+// The explicit representation makes Loading all zeroes, which keeps GAME out
+// of the data section of the cart.
+#[repr(u8)]
 enum Game
 {
 	Loading,
@@ -37,6 +62,7 @@ enum Progress
 {
 	Menu,
 	Level(level::Transition),
+	Resume,
 }
 
 #[no_mangle]
@@ -50,7 +76,12 @@ fn update()
 			setup();
 			if QUICK_TEST
 			{
-				Some(Progress::Level(level::Transition { rng_seed: 0 }))
+				Some(Progress::Level(level::Transition {
+					scenario: &scenario::GENERATED,
+					rng_seed: 0,
+					mode: scores::Mode::Freeplay,
+					preset: map::Preset::Classic,
+				}))
 			}
 			else
 			{
@@ -62,17 +93,36 @@ fn update()
 			let transition = menu.update();
 			match transition
 			{
-				Some(menu::Transition::Start { rng_seed }) =>
+				Some(menu::Transition::Start {
+					scenario,
+					rng_seed,
+					mode,
+					preset,
+				}) =>
 				{
-					let data = level::Transition { rng_seed };
+					let data = level::Transition {
+						scenario,
+						rng_seed,
+						mode,
+						preset,
+					};
 					Some(Progress::Level(data))
 				}
+				Some(menu::Transition::Continue) => Some(Progress::Resume),
 				None => None,
 			}
 		}
 		Game::Level(level) =>
 		{
+			if RECORD_INPUT
+			{
+				RECORDER.get_mut().record();
+			}
 			let transition = level.update();
+			if RECORD_INPUT && transition.is_some()
+			{
+				RECORDER.get_mut().stop();
+			}
 			match transition
 			{
 				Some(data) => Some(Progress::Level(data)),
@@ -89,12 +139,46 @@ fn update()
 		}
 		Some(Progress::Level(data)) =>
 		{
-			let level = Level::new(data.rng_seed);
+			if RECORD_INPUT
+			{
+				RECORDER.get_mut().start(
+					data.scenario,
+					data.rng_seed,
+					data.mode,
+					data.preset,
+				);
+			}
+			let level = Level::new(
+				data.scenario,
+				data.rng_seed,
+				data.mode,
+				data.preset,
+			);
 			*game = Game::Level(level);
 		}
+		Some(Progress::Resume) => match Level::resume()
+		{
+			Some(level) =>
+			{
+				if RECORD_INPUT
+				{
+					RECORDER.get_mut().resume();
+				}
+				*game = Game::Level(level);
+			}
+			None => *game = Game::Menu(Menu::new()),
+		},
 		None => (),
 	}
 
+	let cue = match game
+	{
+		Game::Loading => None,
+		Game::Menu(menu) => menu.music_cue(),
+		Game::Level(level) => level.music_cue(),
+	};
+	MUSIC.get_mut().update(cue);
+
 	match game
 	{
 		Game::Loading => (),
@@ -4,12 +4,12 @@
 // License: MIT
 //
 
-use crate::wasm4::*;
+use crate::platform::*;
 
 use crate::level::Cursor;
-use crate::level::Marker;
-use crate::level::TerrainType;
-use crate::level::MAX_NUM_REGIONS;
+use crate::rules::Marker;
+use crate::rules::TerrainType;
+use crate::rules::MAX_NUM_REGIONS;
 use crate::sprites;
 
 use bitmaps::Bitmap;
@@ -24,18 +24,14 @@ pub const PROP_GRID_SIZE: usize = 40;
 pub const PROP_GRID_CELL_SIZE: usize = MAP_SIZE / PROP_GRID_SIZE;
 pub const PROPMAP_SIZE: usize = PROP_GRID_SIZE * PROP_GRID_SIZE / 2;
 
-const MIN_NUM_REGIONS: usize = 25;
 const MIN_NUM_SURFACES_PER_REGION: usize = 10;
-const MIN_DISTANCE_BETWEEN_REGIONS: i32 = 20;
 const MAX_DISTANCE_BETWEEN_REGIONS: i32 = 30;
 const DBR_BBOX_RADIUS: i32 = 4;
 const VILLAGE_RADIUS: i32 = 15;
 const MIN_OCCUPATION_RADIUS: f64 = 7.0;
 
 const NOISE_OCTAVES: i32 = 5;
-const NOISE_AMPLITUDE_ELEVATION: f64 = 50.0;
 const NOISE_AMPLITUDE_ELEVATION_MAGIC: f64 = 25.0;
-const NOISE_AMPLITUDE_FOREST: f64 = 50.0;
 const NOISE_AMPLITUDE_OCCUPATION: f64 = 0.25;
 const NOISE_FREQUENCY_ELEVATION: f64 = 1.0;
 const NOISE_FREQUENCY_FOREST: f64 = 1.0;
@@ -45,19 +41,189 @@ const NOISE_PERSISTENCE_FOREST: f64 = 2.0;
 const NOISE_PERSISTENCE_OCCUPATION: f64 = 2.0;
 const NOISE_LACUNARITY: f64 = 2.0;
 const NOISE_SCALE: (f64, f64) = (MAP_SIZE as f64, MAP_SIZE as f64);
-const NOISE_BIAS_ELEVATION: f64 = 35.0;
-const NOISE_BIAS_FOREST: f64 = 0.0;
 const NOISE_BIAS_OCCUPATION: f64 = 0.0;
 
-const ELEVATION_THRESHOLD_MOUNTAIN: f64 = 70.0;
-const ELEVATION_THRESHOLD_HILL: f64 = 50.0;
-const ELEVATION_THRESHOLD_WATER: f64 = 0.0;
-const FOREST_THRESHOLD_ON_HILL: f64 = 10.0;
-const FOREST_THRESHOLD_ON_GRASS: f64 = 200.0;
-
 const MAP_X: i32 = 2;
 const MAP_Y: i32 = 7;
 
+/// The tuning of the map generator, which determines what kind of island
+/// it generates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MapGenConfig
+{
+	pub noise_amplitude_elevation: i16,
+	pub noise_amplitude_forest: i16,
+	pub noise_bias_elevation: i16,
+	pub noise_bias_forest: i16,
+	pub elevation_threshold_mountain: i16,
+	pub elevation_threshold_hill: i16,
+	pub elevation_threshold_water: i16,
+	pub forest_threshold_on_hill: i16,
+	pub forest_threshold_on_grass: i16,
+	pub min_num_regions: u8,
+	pub min_distance_between_regions: u8,
+}
+
+impl MapGenConfig
+{
+	fn terrain_type(&self, e: f64, f: f64) -> TerrainType
+	{
+		if e > self.elevation_threshold_mountain as f64
+		{
+			TerrainType::Mountain
+		}
+		else if e > self.elevation_threshold_hill as f64
+		{
+			if f > self.forest_threshold_on_hill as f64
+			{
+				TerrainType::Forest
+			}
+			else
+			{
+				TerrainType::Hill
+			}
+		}
+		else if e > self.elevation_threshold_water as f64
+		{
+			if f > self.forest_threshold_on_grass as f64
+			{
+				TerrainType::Forest
+			}
+			else
+			{
+				TerrainType::Grass
+			}
+		}
+		else
+		{
+			TerrainType::Water
+		}
+	}
+}
+
+const CLASSIC: MapGenConfig = MapGenConfig {
+	noise_amplitude_elevation: 50,
+	noise_amplitude_forest: 50,
+	noise_bias_elevation: 35,
+	noise_bias_forest: 0,
+	elevation_threshold_mountain: 70,
+	elevation_threshold_hill: 50,
+	elevation_threshold_water: 0,
+	forest_threshold_on_hill: 10,
+	forest_threshold_on_grass: 200,
+	min_num_regions: 25,
+	min_distance_between_regions: 20,
+};
+
+const CONFIGS: [MapGenConfig; 5] = [
+	CLASSIC,
+	MapGenConfig {
+		noise_bias_elevation: 20,
+		elevation_threshold_water: 5,
+		min_distance_between_regions: 18,
+		..CLASSIC
+	},
+	MapGenConfig {
+		noise_bias_elevation: 45,
+		elevation_threshold_mountain: 75,
+		elevation_threshold_hill: 40,
+		..CLASSIC
+	},
+	MapGenConfig {
+		noise_bias_forest: 50,
+		forest_threshold_on_hill: 0,
+		forest_threshold_on_grass: 100,
+		..CLASSIC
+	},
+	MapGenConfig {
+		elevation_threshold_mountain: 85,
+		elevation_threshold_hill: 65,
+		elevation_threshold_water: 8,
+		min_num_regions: 28,
+		..CLASSIC
+	},
+];
+
+/// The kinds of island that can be picked for Freeplay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset
+{
+	Classic,
+	Archipelago,
+	Highlands,
+	ForestRealm,
+	CoastalPlain,
+}
+
+pub const PRESETS: [Preset; 5] = [
+	Preset::Classic,
+	Preset::Archipelago,
+	Preset::Highlands,
+	Preset::ForestRealm,
+	Preset::CoastalPlain,
+];
+
+impl Preset
+{
+	pub fn from_u8(x: u8) -> Option<Preset>
+	{
+		PRESETS.get(x as usize).copied()
+	}
+
+	pub fn name(&self) -> &'static str
+	{
+		match self
+		{
+			Preset::Classic => "Classic",
+			Preset::Archipelago => "Archipelago",
+			Preset::Highlands => "Highlands",
+			Preset::ForestRealm => "Forest Realm",
+			Preset::CoastalPlain => "Coastal Plain",
+		}
+	}
+
+	pub fn config(&self) -> MapGenConfig
+	{
+		CONFIGS[*self as usize]
+	}
+}
+
+// The maps in assets/maps, compiled by build.rs.
+include!(concat!(env!("OUT_DIR"), "/maps.rs"));
+
+/// A map that was drawn by hand, to be loaded instead of generated.
+#[derive(Debug)]
+pub struct AuthoredMap
+{
+	/// The region that each cell belongs to, or -1 for the open sea.
+	cells: [[i8; GRID_SIZE]; GRID_SIZE],
+	regions: &'static [AuthoredRegion],
+}
+
+#[derive(Debug)]
+struct AuthoredRegion
+{
+	x: u8,
+	y: u8,
+	terrain_type: TerrainType,
+	marker: Option<Marker>,
+}
+
+/// What to highlight on the map, besides the regions themselves.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DrawOptions
+{
+	pub hovered_region_id: Option<i8>,
+	pub highlighted_terrain_type: Option<TerrainType>,
+	pub highlighted_regions: Bitmap<MAX_NUM_REGIONS>,
+	pub kill_preview: Bitmap<MAX_NUM_REGIONS>,
+	pub attack_preview: Bitmap<MAX_NUM_REGIONS>,
+	pub support_preview: Bitmap<MAX_NUM_REGIONS>,
+	pub gather_preview: Bitmap<MAX_NUM_REGIONS>,
+	/// Whether the kill preview shows a decree being violated.
+	pub is_violation_preview: bool,
+}
+
 pub struct Map
 {
 	water_bitmap: [u8; BITMAP_SIZE],
@@ -88,32 +254,23 @@ impl Map
 		}
 	}
 
-	pub fn generate(&mut self, rng: &mut fastrand::Rng)
+	pub fn generate(&mut self, config: MapGenConfig, rng: &mut fastrand::Rng)
 	{
 		let seed = rng.u16(..) as i32;
-		let elevation = generate_elevation_noise(seed);
+		let elevation = generate_elevation_noise(&config, seed);
 		let seed = rng.u16(..) as i32;
 		let forest = PerlinNoise2D::new(
 			NOISE_OCTAVES,
-			NOISE_AMPLITUDE_FOREST,
+			config.noise_amplitude_forest as f64,
 			NOISE_FREQUENCY_FOREST,
 			NOISE_PERSISTENCE_FOREST,
 			NOISE_LACUNARITY,
 			NOISE_SCALE,
-			NOISE_BIAS_FOREST,
+			config.noise_bias_forest as f64,
 			seed,
 		);
 		let seed = rng.u16(..) as i32;
-		self.occupation_noise = Some(PerlinNoise2D::new(
-			NOISE_OCTAVES,
-			NOISE_AMPLITUDE_OCCUPATION,
-			NOISE_FREQUENCY_OCCUPATION,
-			NOISE_PERSISTENCE_OCCUPATION,
-			NOISE_LACUNARITY,
-			NOISE_SCALE,
-			NOISE_BIAS_OCCUPATION,
-			seed,
-		));
+		self.occupation_noise = Some(generate_occupation_noise(seed));
 		for r in 0..GRID_SIZE
 		{
 			for c in 0..GRID_SIZE
@@ -139,68 +296,8 @@ impl Map
 					self.closest_rc_to_xy(x as i32, y as i32);
 				let e = elevation.get_noise(x as f64 + 0.5, y as f64 + 0.5);
 				let f = forest.get_noise(x as f64 + 0.5, y as f64 + 0.5);
-				let terrain_type = if e > ELEVATION_THRESHOLD_MOUNTAIN
-				{
-					TerrainType::Mountain
-				}
-				else if e > ELEVATION_THRESHOLD_HILL
-				{
-					if f > FOREST_THRESHOLD_ON_HILL
-					{
-						TerrainType::Forest
-					}
-					else
-					{
-						TerrainType::Hill
-					}
-				}
-				else if e > ELEVATION_THRESHOLD_WATER
-				{
-					if f > FOREST_THRESHOLD_ON_GRASS
-					{
-						TerrainType::Forest
-					}
-					else
-					{
-						TerrainType::Grass
-					}
-				}
-				else
-				{
-					TerrainType::Water
-				};
-				if terrain_type == TerrainType::Water
-				{
-					draw_on_bitmap(&mut self.water_bitmap, x, y);
-				}
-				else
-				{
-					erase_on_bitmap(&mut self.water_bitmap, x, y);
-				}
-				let has_surface = match terrain_type
-				{
-					TerrainType::Forest =>
-					{
-						((x + y) % 2 == 0)
-							&& (x % 4 == 0) && ((x / 2 + y) % 4 == 0)
-					}
-					TerrainType::Hill =>
-					{
-						((x / 2 + y) % 2 == 0)
-							&& ((x / 2 + y) % 4 == 0) && (y % 2 == 0)
-					}
-					TerrainType::Mountain => ((x + y) % 2 == 0),
-					_ => false,
-				};
-				if has_surface
-				{
-					draw_on_bitmap(&mut self.surface_bitmap, x, y);
-				}
-				else
-				{
-					erase_on_bitmap(&mut self.surface_bitmap, x, y);
-				}
-				erase_on_bitmap(&mut self.ink_bitmap, x, y);
+				let terrain_type = config.terrain_type(e, f);
+				self.paint_pixel(x, y, terrain_type);
 				self.cells[r][c].add_tally(terrain_type);
 			}
 		}
@@ -221,55 +318,8 @@ impl Map
 				let y = PROP_GRID_CELL_SIZE * v;
 				let e = elevation.get_noise(x as f64 + 0.5, y as f64 + 0.5);
 				let f = forest.get_noise(x as f64 + 0.5, y as f64 + 0.5);
-				let terrain_type = if e > ELEVATION_THRESHOLD_MOUNTAIN
-				{
-					TerrainType::Mountain
-				}
-				else if e > ELEVATION_THRESHOLD_HILL
-				{
-					if f > FOREST_THRESHOLD_ON_HILL
-					{
-						TerrainType::Forest
-					}
-					else
-					{
-						TerrainType::Hill
-					}
-				}
-				else if e > ELEVATION_THRESHOLD_WATER
-				{
-					if f > FOREST_THRESHOLD_ON_GRASS
-					{
-						TerrainType::Forest
-					}
-					else
-					{
-						TerrainType::Grass
-					}
-				}
-				else
-				{
-					TerrainType::Water
-				};
-				let prop_type = match terrain_type
-				{
-					TerrainType::Mountain | TerrainType::Hill =>
-					{
-						if (u + v) % 2 == 0
-						{
-							Some(terrain_type)
-						}
-						else
-						{
-							None
-						}
-					}
-					TerrainType::Water => None,
-					_ => Some(terrain_type),
-				};
-				set_on_propmap(&mut self.propmap, u, v, prop_type);
-				let surface_type = Some(terrain_type);
-				set_on_propmap(&mut self.surface_propmap, u, v, surface_type);
+				let terrain_type = config.terrain_type(e, f);
+				self.paint_prop(u, v, terrain_type);
 				self.prop_region_vu_map[v][u] = -1;
 				let (r, c, _distance) =
 					self.closest_rc_to_xy(x as i32, y as i32);
@@ -328,7 +378,7 @@ impl Map
 			}
 		}
 		// Remove the cells with the highest badness until we have between
-		// 25 and 35 regions.
+		// the configured minimum and MAX_NUM_REGIONS regions.
 		let mut cell_badness = [0i8; GRID_SIZE * GRID_SIZE];
 		let mut num_candidates = 0;
 		for r in 0..GRID_SIZE
@@ -339,7 +389,8 @@ impl Map
 				{
 					Contents::Unmerged { .. } =>
 					{
-						let badness = self.calculate_cell_badness(r, c);
+						let badness =
+							self.calculate_cell_badness(r, c, &config);
 						cell_badness[r * GRID_SIZE + c] = badness;
 						num_candidates += 1;
 					}
@@ -347,7 +398,7 @@ impl Map
 				}
 			}
 		}
-		while num_candidates > MIN_NUM_REGIONS
+		while num_candidates > config.min_num_regions as usize
 		{
 			let worst = cell_badness
 				.iter()
@@ -391,7 +442,7 @@ impl Map
 							Contents::Unmerged { .. } => (),
 							_ => continue,
 						}
-						let b = self.calculate_cell_badness(rr, cc);
+						let b = self.calculate_cell_badness(rr, cc, &config);
 						cell_badness[rr * GRID_SIZE + cc] = b;
 					}
 				}
@@ -494,7 +545,7 @@ impl Map
 					TerrainType::Mountain => (1000.0, 0.0),
 					TerrainType::Hill =>
 					{
-						(ELEVATION_THRESHOLD_HILL + 10.0, -200.0)
+						(config.elevation_threshold_hill as f64 + 10.0, -200.0)
 					}
 					TerrainType::Forest => (20.0, 400.0),
 					TerrainType::Grass => (10.0, -200.0),
@@ -532,6 +583,196 @@ impl Map
 				}
 			}
 		}
+		self.draw_clearings();
+		// Merge abandoned props on the edges of regions.
+		for merge_depth in 0..10
+		{
+			let mut any = false;
+			for v in 0..PROP_GRID_SIZE
+			{
+				for u in 0..PROP_GRID_SIZE
+				{
+					if self.prop_region_vu_map[v][u] >= 0
+					{
+						continue;
+					}
+					let terrain_type =
+						get_from_propmap(&self.surface_propmap, u, v);
+					if terrain_type.is_none()
+					{
+						continue;
+					}
+					let mut adjacents = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+					rng.shuffle(&mut adjacents);
+					for (dv, du) in adjacents
+					{
+						if (v == 0 && dv < 0)
+							|| (u == 0 && du < 0) || (v + 1 == PROP_GRID_SIZE
+							&& dv > 0) || (u + 1 == PROP_GRID_SIZE && du > 0)
+						{
+							continue;
+						};
+						let uu = ((u as i32) + du) as usize;
+						let vv = ((v as i32) + dv) as usize;
+						if self.prop_region_vu_map[vv][uu] < 0
+						{
+							continue;
+						}
+						let t = get_from_propmap(&self.surface_propmap, uu, vv);
+						if t == terrain_type
+						{
+							self.prop_region_vu_map[v][u] =
+								self.prop_region_vu_map[vv][uu];
+							any = true;
+						}
+						else if merge_depth >= 3
+							&& (terrain_type == Some(TerrainType::Grass)
+								|| terrain_type == Some(TerrainType::Hill))
+						{
+							self.prop_region_vu_map[v][u] =
+								self.prop_region_vu_map[vv][uu];
+							set_on_propmap(&mut self.surface_propmap, u, v, t);
+							any = true;
+						}
+					}
+				}
+			}
+			if !any && merge_depth >= 3
+			{
+				break;
+			}
+		}
+	}
+
+	/// Loads a map that was drawn by hand instead of generating one. Only the
+	/// exact borders between the cells are left to chance.
+	pub fn load(&mut self, authored: &AuthoredMap, rng: &mut fastrand::Rng)
+	{
+		let seed = rng.u16(..) as i32;
+		self.occupation_noise = Some(generate_occupation_noise(seed));
+		for r in 0..GRID_SIZE
+		{
+			for c in 0..GRID_SIZE
+			{
+				let cell = &mut self.cells[r][c];
+				let (x, y) = pick_random_centroid_xy_at_rc(r, c, rng);
+				cell.centroid_x = x as u8;
+				cell.centroid_y = y as u8;
+				let region_id = authored.cells[r][c];
+				cell.contents = match authored.regions.get(region_id as usize)
+				{
+					Some(region)
+						if region.y as usize / GRID_CELL_SIZE == r
+							&& region.x as usize / GRID_CELL_SIZE == c =>
+					{
+						cell.centroid_x = region.x;
+						cell.centroid_y = region.y;
+						Contents::Region {
+							region_id,
+							terrain_type: region.terrain_type,
+							marker: region.marker,
+							occupation_percentage: 0,
+						}
+					}
+					Some(region) => Contents::Subregion {
+						parent_region_id: region_id,
+						parent_terrain_type: region.terrain_type,
+						occupation_percentage: 0,
+					},
+					None => Contents::Culled {
+						occupation_percentage: 0,
+					},
+				};
+			}
+		}
+		for y in 0..MAP_SIZE
+		{
+			for x in 0..MAP_SIZE
+			{
+				let (r, c, _distance) =
+					self.closest_rc_to_xy(x as i32, y as i32);
+				let (_, terrain_type) = self.cells[r][c].authored_region();
+				self.paint_pixel(x, y, terrain_type);
+			}
+		}
+		for v in 0..PROP_GRID_SIZE
+		{
+			for u in 0..PROP_GRID_SIZE
+			{
+				let x = PROP_GRID_CELL_SIZE * u;
+				let y = PROP_GRID_CELL_SIZE * v;
+				let (r, c, _distance) =
+					self.closest_rc_to_xy(x as i32, y as i32);
+				let (region_id, terrain_type) =
+					self.cells[r][c].authored_region();
+				self.paint_prop(u, v, terrain_type);
+				self.prop_region_vu_map[v][u] = region_id;
+			}
+		}
+		self.draw_clearings();
+	}
+
+	fn paint_pixel(&mut self, x: usize, y: usize, terrain_type: TerrainType)
+	{
+		if terrain_type == TerrainType::Water
+		{
+			draw_on_bitmap(&mut self.water_bitmap, x, y);
+		}
+		else
+		{
+			erase_on_bitmap(&mut self.water_bitmap, x, y);
+		}
+		let has_surface = match terrain_type
+		{
+			TerrainType::Forest =>
+			{
+				((x + y) % 2 == 0)
+					&& (x % 4 == 0) && ((x / 2 + y) % 4 == 0)
+			}
+			TerrainType::Hill =>
+			{
+				((x / 2 + y) % 2 == 0)
+					&& ((x / 2 + y) % 4 == 0) && (y % 2 == 0)
+			}
+			TerrainType::Mountain => ((x + y) % 2 == 0),
+			_ => false,
+		};
+		if has_surface
+		{
+			draw_on_bitmap(&mut self.surface_bitmap, x, y);
+		}
+		else
+		{
+			erase_on_bitmap(&mut self.surface_bitmap, x, y);
+		}
+		erase_on_bitmap(&mut self.ink_bitmap, x, y);
+	}
+
+	fn paint_prop(&mut self, u: usize, v: usize, terrain_type: TerrainType)
+	{
+		let prop_type = match terrain_type
+		{
+			TerrainType::Mountain | TerrainType::Hill =>
+			{
+				if (u + v).is_multiple_of(2)
+				{
+					Some(terrain_type)
+				}
+				else
+				{
+					None
+				}
+			}
+			TerrainType::Water => None,
+			_ => Some(terrain_type),
+		};
+		set_on_propmap(&mut self.propmap, u, v, prop_type);
+		let surface_type = Some(terrain_type);
+		set_on_propmap(&mut self.surface_propmap, u, v, surface_type);
+	}
+
+	fn draw_clearings(&mut self)
+	{
 		// Draw region clearings.
 		for r in 0..GRID_SIZE
 		{
@@ -550,15 +791,13 @@ impl Map
 						terrain_type: _, ..
 					} =>
 					{
-						draw_on_bitmap(&mut self.ink_bitmap, x, y);
-						draw_on_bitmap(&mut self.ink_bitmap, x + 1, y);
-						draw_on_bitmap(&mut self.ink_bitmap, x, y + 1);
-						draw_on_bitmap(&mut self.ink_bitmap, x + 1, y + 1);
-						draw_on_bitmap(&mut self.ink_bitmap, x - 1, y);
-						draw_on_bitmap(&mut self.ink_bitmap, x, y + 2);
-						draw_on_bitmap(&mut self.ink_bitmap, x - 1, y + 2);
-						draw_on_bitmap(&mut self.ink_bitmap, x + 1, y + 2);
-						draw_on_bitmap(&mut self.ink_bitmap, x - 1, y + 1);
+						for yy in y..(y + 3)
+						{
+							for xx in (x - 1)..(x + 2)
+							{
+								draw_on_bitmap(&mut self.ink_bitmap, xx, yy);
+							}
+						}
 					}
 					Contents::Subregion { .. } if false =>
 					{
@@ -590,10 +829,14 @@ impl Map
 				let y = cell.centroid_y as usize;
 				let u = x / PROP_GRID_CELL_SIZE;
 				let v = y / PROP_GRID_CELL_SIZE;
-				for (dv, dus) in [(0, -1..=1), (1, -1..=2), (2, -1..=2)]
+				for dv in 0..3
 				{
-					for du in dus
+					for du in -1..3
 					{
+						if dv == 0 && du == 2
+						{
+							continue;
+						}
 						let uu = (u as i32 + du) as usize;
 						let vv = (v as i32 + dv) as usize;
 						if uu < PROP_GRID_SIZE && vv < PROP_GRID_SIZE
@@ -604,64 +847,6 @@ impl Map
 				}
 			}
 		}
-		// Merge abandoned props on the edges of regions.
-		for merge_depth in 0..10
-		{
-			let mut any = false;
-			for v in 0..PROP_GRID_SIZE
-			{
-				for u in 0..PROP_GRID_SIZE
-				{
-					if self.prop_region_vu_map[v][u] >= 0
-					{
-						continue;
-					}
-					let terrain_type =
-						get_from_propmap(&self.surface_propmap, u, v);
-					if terrain_type.is_none()
-					{
-						continue;
-					}
-					let mut adjacents = [(1, 0), (-1, 0), (0, 1), (0, -1)];
-					rng.shuffle(&mut adjacents);
-					for (dv, du) in adjacents
-					{
-						if (v == 0 && dv < 0)
-							|| (u == 0 && du < 0) || (v + 1 == PROP_GRID_SIZE
-							&& dv > 0) || (u + 1 == PROP_GRID_SIZE && du > 0)
-						{
-							continue;
-						};
-						let uu = ((u as i32) + du) as usize;
-						let vv = ((v as i32) + dv) as usize;
-						if self.prop_region_vu_map[vv][uu] < 0
-						{
-							continue;
-						}
-						let t = get_from_propmap(&self.surface_propmap, uu, vv);
-						if t == terrain_type
-						{
-							self.prop_region_vu_map[v][u] =
-								self.prop_region_vu_map[vv][uu];
-							any = true;
-						}
-						else if merge_depth >= 3
-							&& (terrain_type == Some(TerrainType::Grass)
-								|| terrain_type == Some(TerrainType::Hill))
-						{
-							self.prop_region_vu_map[v][u] =
-								self.prop_region_vu_map[vv][uu];
-							set_on_propmap(&mut self.surface_propmap, u, v, t);
-							any = true;
-						}
-					}
-				}
-			}
-			if !any && merge_depth >= 3
-			{
-				break;
-			}
-		}
 	}
 
 	pub fn update_occupation_map(&mut self, percentage: u8)
@@ -744,7 +929,10 @@ impl Map
 		}
 	}
 
-	pub fn regions(&self) -> impl Iterator<Item = (i8, TerrainType)> + '_
+	/// Each region with its terrain and its marker, if any.
+	pub fn regions(
+		&self,
+	) -> impl Iterator<Item = (i8, TerrainType, Option<Marker>)> + '_
 	{
 		(0..(GRID_SIZE * GRID_SIZE))
 			.map(|i| (i / GRID_SIZE, i % GRID_SIZE))
@@ -753,9 +941,9 @@ impl Map
 				Contents::Region {
 					region_id,
 					terrain_type,
-					marker: _,
+					marker,
 					occupation_percentage: _,
-				} => Some((region_id, terrain_type)),
+				} => Some((region_id, terrain_type, marker)),
 				Contents::Subregion { .. } => None,
 				Contents::Culled { .. } => None,
 				_ => None,
@@ -803,6 +991,67 @@ impl Map
 				}
 			}
 		}
+		// Connect any part of the island that is cut off from region 0 to the
+		// closest region that is not, as if across the water, so that every
+		// region can be reached by the player and by the Romans.
+		self.connect_cut_off_regions(adjacency);
+	}
+
+	fn connect_cut_off_regions(
+		&self,
+		adjacency: &mut [Bitmap<MAX_NUM_REGIONS>; MAX_NUM_REGIONS],
+	)
+	{
+		let mut regions: u64 = 0;
+		let mut centroids = [(0i32, 0i32); MAX_NUM_REGIONS];
+		for cell in self.cells.iter().flatten()
+		{
+			if let Contents::Region { region_id, .. } = cell.contents
+			{
+				regions |= 1 << region_id;
+				let x = cell.centroid_x as i32;
+				let y = cell.centroid_y as i32;
+				centroids[region_id as usize] = (x, y);
+			}
+		}
+		let mut reached: u64 = 1;
+		loop
+		{
+			for _ in 0..MAX_NUM_REGIONS
+			{
+				for (i, neighbors) in adjacency.iter().enumerate()
+				{
+					if reached & (1 << i) != 0
+					{
+						reached |= neighbors.into_value();
+					}
+				}
+			}
+			let cut_off = regions & !reached;
+			if cut_off == 0
+			{
+				break;
+			}
+			let j = cut_off.trailing_zeros() as usize;
+			let (x, y) = centroids[j];
+			let mut closest = 0;
+			let mut closest_sqdis = i32::MAX;
+			let mut candidates = reached & regions;
+			while candidates != 0
+			{
+				let i = candidates.trailing_zeros() as usize;
+				candidates &= candidates - 1;
+				let (dx, dy) = (centroids[i].0 - x, centroids[i].1 - y);
+				let sqdis = dx * dx + dy * dy;
+				if sqdis < closest_sqdis
+				{
+					closest = i;
+					closest_sqdis = sqdis;
+				}
+			}
+			adjacency[closest].set(j, true);
+			adjacency[j].set(closest, true);
+		}
 	}
 
 	fn are_prelims_adjacent(&self, id0: i8, id1: i8) -> bool
@@ -968,17 +1217,18 @@ impl Map
 		}
 	}
 
-	pub fn draw(
-		&self,
-		hovered_region_id: Option<i8>,
-		highlighted_terrain_type: Option<TerrainType>,
-		kill_preview: Bitmap<MAX_NUM_REGIONS>,
-		attack_preview: Bitmap<MAX_NUM_REGIONS>,
-		support_preview: Bitmap<MAX_NUM_REGIONS>,
-		gather_preview: Bitmap<MAX_NUM_REGIONS>,
-		cursor: &Cursor,
-	)
+	pub fn draw(&self, options: &DrawOptions, cursor: &Cursor)
 	{
+		let DrawOptions {
+			hovered_region_id,
+			highlighted_terrain_type,
+			highlighted_regions,
+			kill_preview,
+			attack_preview,
+			support_preview,
+			gather_preview,
+			is_violation_preview,
+		} = *options;
 		let mut is_empty = [false; MAX_NUM_REGIONS];
 		for r in 0..GRID_SIZE
 		{
@@ -1001,7 +1251,7 @@ impl Map
 			}
 		}
 
-		unsafe { *DRAW_COLORS = 0x20 };
+		set_draw_colors(0x20);
 		blit(
 			&self.surface_bitmap,
 			MAP_X,
@@ -1011,8 +1261,10 @@ impl Map
 			BLIT_1BPP,
 		);
 
-		unsafe { *DRAW_COLORS = 0x40 };
-		if hovered_region_id.is_some() || highlighted_terrain_type.is_some()
+		set_draw_colors(0x40);
+		if hovered_region_id.is_some()
+			|| highlighted_terrain_type.is_some()
+			|| !highlighted_regions.is_empty()
 		{
 			for v in 0..PROP_GRID_SIZE
 			{
@@ -1022,7 +1274,8 @@ impl Map
 					if region_id < 0
 						|| !is_empty[region_id as usize]
 						|| (highlighted_terrain_type.is_none()
-							&& hovered_region_id != Some(region_id))
+							&& hovered_region_id != Some(region_id)
+							&& !highlighted_regions.get(region_id as usize))
 					{
 						continue;
 					}
@@ -1031,6 +1284,7 @@ impl Map
 					let alt = ((23 * u + 71 * v + 59 * (u + v)) % 97) as u8;
 					let tt = get_from_propmap(&self.surface_propmap, u, v);
 					if hovered_region_id == Some(region_id)
+						|| highlighted_regions.get(region_id as usize)
 						|| tt == highlighted_terrain_type
 					{
 						match tt
@@ -1052,7 +1306,7 @@ impl Map
 			}
 		}
 
-		unsafe { *DRAW_COLORS = 0x20 };
+		set_draw_colors(0x20);
 		blit(
 			&self.water_bitmap,
 			MAP_X,
@@ -1061,7 +1315,7 @@ impl Map
 			MAP_SIZE as u32,
 			BLIT_1BPP,
 		);
-		unsafe { *DRAW_COLORS = 0x10 };
+		set_draw_colors(0x10);
 		blit(
 			&self.occupation_bitmap,
 			MAP_X,
@@ -1070,7 +1324,7 @@ impl Map
 			MAP_SIZE as u32,
 			BLIT_1BPP,
 		);
-		unsafe { *DRAW_COLORS = 0x20 };
+		set_draw_colors(0x20);
 		blit(
 			&self.occupation_bitmap,
 			MAP_X,
@@ -1079,7 +1333,7 @@ impl Map
 			MAP_SIZE as u32,
 			BLIT_1BPP,
 		);
-		unsafe { *DRAW_COLORS = 0x30 };
+		set_draw_colors(0x30);
 		blit(
 			&self.ink_bitmap,
 			MAP_X,
@@ -1090,7 +1344,7 @@ impl Map
 		);
 		if false
 		{
-			unsafe { *DRAW_COLORS = 3 };
+			set_draw_colors(3);
 			for r in 0..GRID_SIZE
 			{
 				for c in 0..GRID_SIZE
@@ -1125,7 +1379,7 @@ impl Map
 						..
 					} =>
 					{
-						unsafe { *DRAW_COLORS = 0x1320 };
+						set_draw_colors(0x1320);
 						sprites::draw_boat(
 							MAP_X + (cell.centroid_x as i32),
 							MAP_Y + (cell.centroid_y as i32),
@@ -1145,13 +1399,14 @@ impl Map
 				if region_id >= 0
 					&& is_empty[region_id as usize]
 					&& (hovered_region_id == Some(region_id)
+						|| highlighted_regions.get(region_id as usize)
 						|| highlighted_terrain_type == terrain_type)
 				{
-					unsafe { *DRAW_COLORS = 0x4320 };
+					set_draw_colors(0x4320);
 				}
 				else
 				{
-					unsafe { *DRAW_COLORS = 0x1320 };
+					set_draw_colors(0x1320);
 				}
 				let x = MAP_X + (u * PROP_GRID_CELL_SIZE) as i32;
 				let y =
@@ -1177,9 +1432,10 @@ impl Map
 					}
 					Some(TerrainType::Grass) =>
 					{
-						let is_palette_bloody =
-							unsafe { *PALETTE == crate::palette::BLOOD }
-								|| unsafe { *PALETTE == crate::palette::ROMAN };
+						let palette = get_palette();
+						let is_palette_bloody = palette
+							== crate::palette::BLOOD
+							|| palette == crate::palette::ROMAN;
 						let is_placing_blood = hovered_region_id
 							== Some(region_id)
 							&& is_palette_bloody;
@@ -1221,15 +1477,15 @@ impl Map
 						let y = MAP_Y + cell.centroid_y as i32;
 						if flag >= 100
 						{
-							unsafe { *DRAW_COLORS = 0x11 };
+							set_draw_colors(0x11);
 							rect(x - 1, y, 3, 3);
 						}
 						else
 						{
-							unsafe { *DRAW_COLORS = 0x3210 };
+							set_draw_colors(0x3210);
 							sprites::draw_flag(x, y, flag);
 						}
-						unsafe { *DRAW_COLORS = 0x2310 };
+						set_draw_colors(0x2310);
 						if is_killed
 						{
 							sprites::draw_killed_town(x, y);
@@ -1257,19 +1513,26 @@ impl Map
 						let y = MAP_Y + cell.centroid_y as i32;
 						if kill_preview.get(region_id as usize)
 						{
-							unsafe { *DRAW_COLORS = 0x2310 };
+							set_draw_colors(0x2310);
 							sprites::draw_killed_town(x, y);
 						}
+						else if is_violation_preview
+						{
+							set_draw_colors(0x2310);
+							sprites::draw_hovered_town(x, y);
+							set_draw_colors(0x04);
+							text("!", x + 4, y - 10);
+						}
 						else if attack_preview.get(region_id as usize)
 						{
-							unsafe { *DRAW_COLORS = 0x4310 };
+							set_draw_colors(0x4310);
 							sprites::draw_hovered_town(x, y);
-							unsafe { *DRAW_COLORS = 0x2310 };
+							set_draw_colors(0x2310);
 							sprites::draw_attacking_town(x + 6, y);
 						}
 						else
 						{
-							unsafe { *DRAW_COLORS = 0x4310 };
+							set_draw_colors(0x4310);
 							sprites::draw_hovered_town(x, y);
 						}
 					}
@@ -1304,7 +1567,7 @@ impl Map
 					(x, y)
 				}
 			};
-			unsafe { *DRAW_COLORS = 0x2310 };
+			set_draw_colors(0x2310);
 			sprites::draw_cursor(MAP_X + x + 1, MAP_Y + y + 1);
 		};
 	}
@@ -1736,7 +1999,12 @@ impl Map
 		}
 	}
 
-	fn calculate_cell_badness(&self, r: usize, c: usize) -> i8
+	fn calculate_cell_badness(
+		&self,
+		r: usize,
+		c: usize,
+		config: &MapGenConfig,
+	) -> i8
 	{
 		let cell = &self.cells[r][c];
 		let (prelim_id, terrain_type) = match cell.contents
@@ -1789,8 +2057,8 @@ impl Map
 					continue;
 				}
 				if dx * dx + dy * dy
-					< MIN_DISTANCE_BETWEEN_REGIONS
-						* MIN_DISTANCE_BETWEEN_REGIONS
+					< (config.min_distance_between_regions as i32)
+						* (config.min_distance_between_regions as i32)
 				{
 					num_too_close += 1;
 				}
@@ -1829,6 +2097,63 @@ impl Map
 	}
 }
 
+/// Read access to a generated map for the map export tool.
+#[cfg(not(target_arch = "wasm32"))]
+impl Map
+{
+	/// The centroid of each region, in map coordinates.
+	pub fn region_centroids(&self) -> impl Iterator<Item = (i8, u8, u8)> + '_
+	{
+		self.cells
+			.iter()
+			.flatten()
+			.filter_map(|cell| match cell.contents
+			{
+				Contents::Region { region_id, .. } =>
+				{
+					Some((region_id, cell.centroid_x, cell.centroid_y))
+				}
+				_ => None,
+			})
+	}
+
+	/// The terrain of the region that a cell of the grid belongs to, if any.
+	pub fn terrain_type_at_cell(
+		&self,
+		r: usize,
+		c: usize,
+	) -> Option<TerrainType>
+	{
+		match self.cells[r][c].contents
+		{
+			Contents::Region { terrain_type, .. } => Some(terrain_type),
+			Contents::Subregion {
+				parent_terrain_type,
+				..
+			} => Some(parent_terrain_type),
+			_ => None,
+		}
+	}
+
+	/// The region that a cell of the prop grid belongs to, or -1 if none.
+	pub fn region_id_at_prop(&self, u: usize, v: usize) -> i8
+	{
+		self.prop_region_vu_map[v][u]
+	}
+
+	/// The bitmaps by name, row by row, with the leftmost pixel of each
+	/// byte in its most significant bit.
+	pub fn bitmaps(&self) -> [(&'static str, &[u8; BITMAP_SIZE]); 4]
+	{
+		[
+			("water", &self.water_bitmap),
+			("surface", &self.surface_bitmap),
+			("ink", &self.ink_bitmap),
+			("occupation", &self.occupation_bitmap),
+		]
+	}
+}
+
 #[derive(Debug, Clone, Copy)]
 enum Contents
 {
@@ -1938,6 +2263,25 @@ impl Cell
 		}
 	}
 
+	/// The region of a loaded cell and its terrain, or the open sea.
+	fn authored_region(&self) -> (i8, TerrainType)
+	{
+		match self.contents
+		{
+			Contents::Region {
+				region_id,
+				terrain_type,
+				..
+			} => (region_id, terrain_type),
+			Contents::Subregion {
+				parent_region_id,
+				parent_terrain_type,
+				..
+			} => (parent_region_id, parent_terrain_type),
+			_ => (-1, TerrainType::Water),
+		}
+	}
+
 	fn make_more_important(&mut self)
 	{
 		match &mut self.contents
@@ -2030,7 +2374,8 @@ impl Cell
 			{
 				let n_total = (n_water as u16)
 					+ (n_mountain as u16)
-					+ (n_hill as u16) + (n_forest as u16)
+					+ (n_hill as u16)
+					+ (n_forest as u16)
 					+ (n_grass as u16);
 				let n_total = std::cmp::min(n_total, 250) as u8;
 				if n_water > (3 * (n_total as u16) / 4) as u8
@@ -2183,16 +2528,30 @@ fn pick_random_centroid_xy_at_rc(
 	(x, y)
 }
 
-fn generate_elevation_noise(seed: i32) -> PerlinNoise2D
+fn generate_occupation_noise(seed: i32) -> PerlinNoise2D
+{
+	PerlinNoise2D::new(
+		NOISE_OCTAVES,
+		NOISE_AMPLITUDE_OCCUPATION,
+		NOISE_FREQUENCY_OCCUPATION,
+		NOISE_PERSISTENCE_OCCUPATION,
+		NOISE_LACUNARITY,
+		NOISE_SCALE,
+		NOISE_BIAS_OCCUPATION,
+		seed,
+	)
+}
+
+fn generate_elevation_noise(config: &MapGenConfig, seed: i32) -> PerlinNoise2D
 {
 	let mut elevation = PerlinNoise2D::new(
 		NOISE_OCTAVES,
-		NOISE_AMPLITUDE_ELEVATION,
+		config.noise_amplitude_elevation as f64,
 		NOISE_FREQUENCY_ELEVATION,
 		NOISE_PERSISTENCE_ELEVATION,
 		NOISE_LACUNARITY,
 		NOISE_SCALE,
-		NOISE_BIAS_ELEVATION,
+		config.noise_bias_elevation as f64,
 		seed,
 	);
 	let mut total = 0.0;
@@ -2224,7 +2583,8 @@ fn generate_elevation_noise(seed: i32) -> PerlinNoise2D
 	let standard_deviation = variance.sqrt();
 	// Adjust the amplitude to make sure the variance is "nice".
 	elevation.set_amplitude(
-		NOISE_AMPLITUDE_ELEVATION * NOISE_AMPLITUDE_ELEVATION_MAGIC
+		config.noise_amplitude_elevation as f64
+			* NOISE_AMPLITUDE_ELEVATION_MAGIC
 			/ standard_deviation,
 	);
 	// Recalculate the average because we changed the amplitude and the average
@@ -2241,6 +2601,130 @@ fn generate_elevation_noise(seed: i32) -> PerlinNoise2D
 		}
 	}
 	let average = total / n;
-	elevation.set_bias(2.0 * NOISE_BIAS_ELEVATION - average);
+	elevation.set_bias(2.0 * config.noise_bias_elevation as f64 - average);
 	elevation
 }
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	/// How many seeds of each preset are checked by default; set `MAP_SEEDS`
+	/// to check more, e.g. `MAP_SEEDS=10000 cargo test --release invariants`.
+	const NUM_SEEDS: u64 = 40;
+
+	/// Checks the invariants that `Level` and `Rules` rely on.
+	fn check_invariants(map: &Map, min_num_regions: usize)
+		-> Result<(), String>
+	{
+		let mut adjacency = [Bitmap::<MAX_NUM_REGIONS>::new(); MAX_NUM_REGIONS];
+		let mut border_adjacency = Bitmap::new();
+		map.fill_adjacency(&mut adjacency, &mut border_adjacency);
+		let mut regions: Vec<_> = map.regions().collect();
+		regions.sort_by_key(|(region_id, ..)| *region_id);
+		let num_regions = regions.len();
+		if num_regions < min_num_regions || num_regions > MAX_NUM_REGIONS
+		{
+			return Err(format!("{} regions", num_regions));
+		}
+		for (i, (region_id, ..)) in regions.iter().enumerate()
+		{
+			if *region_id as usize != i
+			{
+				return Err(format!("region id {} at {}", region_id, i));
+			}
+			if adjacency[i].is_empty()
+			{
+				return Err(format!("region {} has no neighbors", i));
+			}
+			if let Some(j) =
+				adjacency[i].into_iter().find(|j| *j >= num_regions)
+			{
+				return Err(format!("region {} borders unknown {}", i, j));
+			}
+			if let Some(j) =
+				adjacency[i].into_iter().find(|j| !adjacency[*j].get(i))
+			{
+				return Err(format!("region {} borders {} one way", i, j));
+			}
+		}
+		// The regions are the land, including lakes and bays, and the culled
+		// cells between them are open sea. Every region must be reachable.
+		let mut reached = Bitmap::<MAX_NUM_REGIONS>::new();
+		reached.set(0, true);
+		let mut todo = vec![0];
+		while let Some(i) = todo.pop()
+		{
+			for j in adjacency[i].into_iter()
+			{
+				if !reached.set(j, true)
+				{
+					todo.push(j);
+				}
+			}
+		}
+		if let Some(i) = (0..num_regions).find(|i| !reached.get(*i))
+		{
+			return Err(format!("region {} is cut off from region 0", i));
+		}
+		let can_spawn = |i: usize| match regions[i].1
+		{
+			TerrainType::Water => false,
+			TerrainType::Mountain => false,
+			_ => border_adjacency.get(i),
+		};
+		if !(0..num_regions).any(can_spawn)
+		{
+			return Err("no border region for the Romans".to_string());
+		}
+		Ok(())
+	}
+
+	#[test]
+	fn generated_maps_satisfy_invariants()
+	{
+		let num_seeds = std::env::var("MAP_SEEDS")
+			.map(|x| x.parse().unwrap())
+			.unwrap_or(NUM_SEEDS);
+		let mut map = Box::new(Map::empty());
+		let mut failures = Vec::new();
+		for preset in PRESETS
+		{
+			let config = preset.config();
+			for seed in 0..num_seeds
+			{
+				let mut rng = fastrand::Rng::with_seed(seed);
+				map.generate(config, &mut rng);
+				let min_num_regions = config.min_num_regions as usize;
+				if let Err(message) = check_invariants(&map, min_num_regions)
+				{
+					failures
+						.push(format!("{:?} {}: {}", preset, seed, message));
+				}
+			}
+		}
+		assert!(failures.is_empty(), "{}", failures.join("\n"));
+	}
+
+	#[test]
+	fn tutorial_map_is_loaded_as_drawn()
+	{
+		let mut map = Box::new(Map::empty());
+		let mut rng = fastrand::Rng::with_seed(1);
+		map.load(&TUTORIAL, &mut rng);
+		let num_regions = TUTORIAL.regions.len();
+		assert_eq!(check_invariants(&map, num_regions), Ok(()));
+		for (region_id, terrain_type, marker) in map.regions()
+		{
+			let region = &TUTORIAL.regions[region_id as usize];
+			assert_eq!(terrain_type, region.terrain_type);
+			assert_eq!(marker, region.marker);
+		}
+		for (region_id, x, y) in map.region_centroids()
+		{
+			let region = &TUTORIAL.regions[region_id as usize];
+			assert_eq!((x, y), (region.x, region.y));
+		}
+	}
+}
@@ -4,15 +4,27 @@
 // License: MIT
 //
 
-use crate::wasm4::*;
+use crate::platform::*;
 
+use crate::campaign::{Progress, CHAPTERS};
+use crate::level::{draw_number, Level};
+use crate::map::{Preset, PRESETS};
+use crate::music::{Cue, Theme};
 use crate::palette;
+use crate::scenario::{Scenario, GENERATED, TUTORIAL};
+use crate::scores::{Daily, Leaderboard, Mode, MAX_DAY};
+use crate::settings::{Setting, Settings, SETTINGS};
 use crate::sprites;
 use crate::wreath;
 
 pub struct Menu
 {
 	items: &'static [MenuItem],
+	screen: Screen,
+	leaderboard: Leaderboard,
+	daily: Daily,
+	settings: Settings,
+	progress: Progress,
 	selected_item: Option<MenuItem>,
 	ticks: u32,
 	previous_gamepad: u8,
@@ -22,17 +34,59 @@ pub struct Menu
 
 const NUM_INTRO_ANIMATION_TICKS: u32 = 90;
 
+const Y_OF_CENTER_OF_WREATH: i32 = 70;
 const X_OF_CENTER_OF_MENU_ITEM: i32 = 80;
-const Y_OF_TOP_OF_MENU_ITEM: i32 = 130;
+const Y_OF_BOTTOM_OF_MENU: i32 = 158;
 const MENU_ITEM_WIDTH: u32 = 80;
-const MENU_ITEM_HEIGHT: u32 = 12;
+const MENU_ITEM_HEIGHT: u32 = 9;
+/// The items of the main menu are closer together, so that all seven of them
+/// fit below the wreath when there is a saved game.
+const MAIN_MENU_ITEM_HEIGHT: u32 = 8;
+const ISLAND_ITEM_WIDTH: u32 = 120;
+const CHAPTER_ITEM_WIDTH: u32 = 136;
+
+const NUM_SEED_DIGITS: usize = 8;
+const X_OF_LEFT_OF_SEED: i32 = 40;
+const Y_OF_SEED: i32 = 70;
+const SEED_DIGIT_WIDTH: i32 = 10;
+const Y_OF_TOP_OF_PLAY_BUTTON: i32 = 110;
+const Y_OF_TOP_OF_BACK_BUTTON: i32 = 124;
+const X_OF_DAY_ARROWS: i32 = 24;
+const Y_OF_DAY: i32 = 66;
 
 impl Menu
 {
 	pub fn new() -> Self
 	{
 		Self {
-			items: &[MenuItem::Start, MenuItem::Freeplay],
+			items: if Level::has_saved_game()
+			{
+				&[
+					MenuItem::Continue,
+					MenuItem::Start,
+					MenuItem::Campaign,
+					MenuItem::Freeplay,
+					MenuItem::Daily,
+					MenuItem::Scores,
+					MenuItem::Settings,
+				]
+			}
+			else
+			{
+				&[
+					MenuItem::Start,
+					MenuItem::Campaign,
+					MenuItem::Freeplay,
+					MenuItem::Daily,
+					MenuItem::Scores,
+					MenuItem::Settings,
+				]
+			},
+			screen: Screen::Main,
+			leaderboard: Leaderboard::load(),
+			daily: Daily::load(),
+			settings: Settings::load(),
+			progress: Progress::load(),
 			selected_item: None,
 			ticks: 0,
 			previous_gamepad: 0,
@@ -48,25 +102,180 @@ impl Menu
 			return self.loading_transition;
 		}
 
-		let gamepad = unsafe { *GAMEPAD1 };
-		let mousebuttons = unsafe { *MOUSE_BUTTONS };
+		let gamepad = gamepad();
+		let mousebuttons = mouse_buttons();
 
 		self.ticks += 1;
 
-		let (mouse_x, mouse_y): (i16, i16) = unsafe { (*MOUSE_X, *MOUSE_Y) };
+		if matches!(self.screen, Screen::Scores)
+		{
+			if (gamepad & (BUTTON_1 | BUTTON_2) != 0
+				&& self.previous_gamepad & (BUTTON_1 | BUTTON_2) == 0)
+				|| (mousebuttons & MOUSE_LEFT != 0
+					&& self.previous_mousebuttons & MOUSE_LEFT == 0)
+			{
+				self.screen = Screen::Main;
+			}
+			self.previous_gamepad = gamepad;
+			self.previous_mousebuttons = mousebuttons;
+			return None;
+		}
+
+		let (mouse_x, mouse_y) = mouse_xy();
 		let mouse_x = mouse_x as i32;
 		let mouse_y = mouse_y as i32;
 
+		let pressed = gamepad & !self.previous_gamepad;
+		let is_clicked = mousebuttons & MOUSE_LEFT != 0
+			&& self.previous_mousebuttons & MOUSE_LEFT == 0;
+		let clicked = if is_clicked
+		{
+			Some((mouse_x, mouse_y))
+		}
+		else
+		{
+			None
+		};
+		if let Screen::Seed(entry) = &mut self.screen
+		{
+			let transition = match entry.update(pressed, clicked)
+			{
+				Some(ScreenAction::Play) => Some(Transition::Start {
+					scenario: &GENERATED,
+					rng_seed: entry.seed(),
+					mode: Mode::Freeplay,
+					preset: entry.preset,
+				}),
+				Some(ScreenAction::Back) =>
+				{
+					self.screen = Screen::Island(entry.preset as usize);
+					None
+				}
+				None => None,
+			};
+			self.loading_transition = transition;
+			self.previous_gamepad = gamepad;
+			self.previous_mousebuttons = mousebuttons;
+			return None;
+		}
+		else if matches!(self.screen, Screen::Daily)
+		{
+			let transition =
+				match update_day(&mut self.daily.day, pressed, clicked)
+				{
+					Some(ScreenAction::Play) =>
+					{
+						let day = self.daily.day;
+						self.daily.start(day);
+						Some(Transition::Start {
+							scenario: &GENERATED,
+							rng_seed: Daily::seed(day),
+							mode: Mode::Daily,
+							preset: Preset::Classic,
+						})
+					}
+					Some(ScreenAction::Back) =>
+					{
+						self.daily = Daily::load();
+						self.screen = Screen::Main;
+						None
+					}
+					None => None,
+				};
+			self.loading_transition = transition;
+			self.previous_gamepad = gamepad;
+			self.previous_mousebuttons = mousebuttons;
+			return None;
+		}
+		else if let Screen::Settings(selected) = &mut self.screen
+		{
+			match update_list(
+				selected,
+				SETTINGS.len(),
+				MENU_ITEM_WIDTH,
+				y_of_top_of_setting,
+				pressed,
+				(mouse_x, mouse_y),
+				clicked,
+			)
+			{
+				Some(ScreenAction::Play) =>
+				{
+					self.settings.toggle(SETTINGS[*selected]);
+				}
+				Some(ScreenAction::Back) => self.screen = Screen::Main,
+				None => (),
+			}
+			self.previous_gamepad = gamepad;
+			self.previous_mousebuttons = mousebuttons;
+			return None;
+		}
+		else if let Screen::Island(selected) = &mut self.screen
+		{
+			match update_list(
+				selected,
+				PRESETS.len(),
+				ISLAND_ITEM_WIDTH,
+				y_of_top_of_setting,
+				pressed,
+				(mouse_x, mouse_y),
+				clicked,
+			)
+			{
+				Some(ScreenAction::Play) =>
+				{
+					let entry =
+						SeedEntry::new(self.ticks as u64, PRESETS[*selected]);
+					self.screen = Screen::Seed(entry);
+				}
+				Some(ScreenAction::Back) => self.screen = Screen::Main,
+				None => (),
+			}
+			self.previous_gamepad = gamepad;
+			self.previous_mousebuttons = mousebuttons;
+			return None;
+		}
+		else if let Screen::Campaign(selected) = &mut self.screen
+		{
+			match update_list(
+				selected,
+				CHAPTERS.len(),
+				CHAPTER_ITEM_WIDTH,
+				y_of_top_of_chapter,
+				pressed,
+				(mouse_x, mouse_y),
+				clicked,
+			)
+			{
+				Some(ScreenAction::Play)
+					if self.progress.is_unlocked(*selected) =>
+				{
+					let chapter = &CHAPTERS[*selected];
+					self.loading_transition = Some(Transition::Start {
+						scenario: chapter.scenario,
+						rng_seed: chapter.rng_seed,
+						mode: Mode::Campaign,
+						preset: chapter.preset,
+					});
+				}
+				Some(ScreenAction::Play) => (),
+				Some(ScreenAction::Back) => self.screen = Screen::Main,
+				None => (),
+			}
+			self.previous_gamepad = gamepad;
+			self.previous_mousebuttons = mousebuttons;
+			return None;
+		}
+
 		let hovered_item = self
 			.items
 			.iter()
 			.enumerate()
 			.find(|(offset, _item)| {
 				let x = X_OF_CENTER_OF_MENU_ITEM - (MENU_ITEM_WIDTH as i32) / 2;
-				let y = Y_OF_TOP_OF_MENU_ITEM
-					+ (*offset as i32) * (MENU_ITEM_HEIGHT as i32);
+				let y = self.y_of_top_of_menu_item(*offset);
 				let w = MENU_ITEM_WIDTH as i32;
-				let h = MENU_ITEM_HEIGHT as i32;
+				let h = MAIN_MENU_ITEM_HEIGHT as i32;
 				mouse_x > x && mouse_y > y && mouse_x < x + w && mouse_y < y + h
 			})
 			.map(|(_offset, item)| *item);
@@ -128,10 +337,45 @@ impl Menu
 		};
 		self.loading_transition = match clicked_item
 		{
-			Some(MenuItem::Start) => Some(Transition::Start { rng_seed: 1 }),
-			Some(MenuItem::Freeplay) => Some(Transition::Start {
-				rng_seed: self.ticks as u64,
+			Some(MenuItem::Continue) => Some(Transition::Continue),
+			Some(MenuItem::Start) => Some(Transition::Start {
+				scenario: &TUTORIAL,
+				rng_seed: TUTORIAL.rng_seed,
+				mode: Mode::Start,
+				preset: TUTORIAL.preset,
 			}),
+			Some(MenuItem::Campaign) =>
+			{
+				self.progress = Progress::load();
+				let unlocked = (0..CHAPTERS.len())
+					.rev()
+					.find(|i| self.progress.is_unlocked(*i));
+				self.screen = Screen::Campaign(unlocked.unwrap_or(0));
+				None
+			}
+			Some(MenuItem::Freeplay) =>
+			{
+				self.screen = Screen::Island(0);
+				None
+			}
+			Some(MenuItem::Daily) =>
+			{
+				self.daily = Daily::load();
+				self.screen = Screen::Daily;
+				None
+			}
+			Some(MenuItem::Settings) =>
+			{
+				self.settings = Settings::load();
+				self.screen = Screen::Settings(0);
+				None
+			}
+			Some(MenuItem::Scores) =>
+			{
+				self.leaderboard = Leaderboard::load();
+				self.screen = Screen::Scores;
+				None
+			}
 			None => None,
 		};
 
@@ -147,14 +391,36 @@ impl Menu
 		None
 	}
 
+	pub fn music_cue(&self) -> Option<Cue>
+	{
+		if self.settings.get(Setting::Music)
+			&& self.loading_transition.is_none()
+		{
+			Some(Cue {
+				theme: Theme::Menu,
+				intensity: 0,
+			})
+		}
+		else
+		{
+			None
+		}
+	}
+
+	fn y_of_top_of_menu_item(&self, offset: usize) -> i32
+	{
+		let num_below = (self.items.len() - offset) as i32;
+		Y_OF_BOTTOM_OF_MENU - num_below * (MAIN_MENU_ITEM_HEIGHT as i32)
+	}
+
 	pub fn draw(&mut self)
 	{
 		if self.loading_transition.is_some()
 		{
-			unsafe { *PALETTE = palette::DEFAULT };
+			set_palette(palette::DEFAULT);
 			let x = 80 - 4;
 			let y = 80;
-			unsafe { *DRAW_COLORS = 0x1320 };
+			set_draw_colors(0x1320);
 			sprites::draw_tree(x, y + 6, 0);
 			sprites::draw_tree(x + 4, y + 7, 0);
 			sprites::draw_tree(x + 8, y + 6, 0);
@@ -163,11 +429,11 @@ impl Menu
 
 		if self.ticks >= NUM_INTRO_ANIMATION_TICKS
 		{
-			unsafe { *PALETTE = palette::MENU };
+			set_palette(palette::MENU);
 		}
 		else if self.ticks < 30
 		{
-			unsafe { *PALETTE = palette::MENU };
+			set_palette(palette::MENU);
 			return;
 		}
 		else if self.ticks % 15 == 0
@@ -188,20 +454,55 @@ impl Menu
 					work_palette[i] |= (x & 0xFF) << (8 * d);
 				}
 			}
-			unsafe { *PALETTE = work_palette };
+			set_palette(work_palette);
 		}
 
-		unsafe { *DRAW_COLORS = 0x2340 };
-		wreath::draw_laurel_wreath(80, 80);
+		match &self.screen
+		{
+			Screen::Main => (),
+			Screen::Scores =>
+			{
+				draw_scores(&self.leaderboard);
+				return;
+			}
+			Screen::Seed(entry) =>
+			{
+				entry.draw();
+				return;
+			}
+			Screen::Daily =>
+			{
+				draw_daily(&self.daily);
+				return;
+			}
+			Screen::Settings(selected) =>
+			{
+				draw_settings(&self.settings, *selected);
+				return;
+			}
+			Screen::Island(selected) =>
+			{
+				draw_islands(*selected);
+				return;
+			}
+			Screen::Campaign(selected) =>
+			{
+				draw_campaign(&self.progress, *selected);
+				return;
+			}
+		}
+
+		set_draw_colors(0x2340);
+		wreath::draw_laurel_wreath(80, Y_OF_CENTER_OF_WREATH);
 
-		unsafe { *DRAW_COLORS = 2 };
+		set_draw_colors(2);
 		{
 			text("THE", 16 + 1, 16 + 1);
 			text("ROMANS", 16 + 32 + 1, 16 + 1);
 			text("ARE", 64 - 8 + 1, 26 + 1);
 			text("COMING!", 64 + 24 + 1, 26 + 1);
 		}
-		unsafe { *DRAW_COLORS = 4 };
+		set_draw_colors(4);
 		{
 			text("THE", 16, 16);
 			text("ROMANS", 16 + 32, 16);
@@ -215,39 +516,42 @@ impl Menu
 			{
 				if self.selected_item == Some(*item)
 				{
-					unsafe { *DRAW_COLORS = 0x44 };
+					set_draw_colors(0x44);
 					rect(
 						X_OF_CENTER_OF_MENU_ITEM - (MENU_ITEM_WIDTH as i32) / 2,
-						Y_OF_TOP_OF_MENU_ITEM
-							+ (offset as i32) * (MENU_ITEM_HEIGHT as i32),
+						self.y_of_top_of_menu_item(offset),
 						MENU_ITEM_WIDTH,
-						MENU_ITEM_HEIGHT,
+						MAIN_MENU_ITEM_HEIGHT,
 					);
-					unsafe { *DRAW_COLORS = 1 };
+					set_draw_colors(1);
 				}
 				else
 				{
-					unsafe { *DRAW_COLORS = 3 };
+					set_draw_colors(3);
 				}
 				let txt = match item
 				{
+					MenuItem::Continue => "Continue",
 					MenuItem::Start => "Start",
+					MenuItem::Campaign => "Campaign",
 					MenuItem::Freeplay => "Freeplay",
+					MenuItem::Daily => "Daily",
+					MenuItem::Scores => "Scores",
+					MenuItem::Settings => "Settings",
 				};
 				let len = txt.len();
 				text(
 					txt,
 					80 - (8 * (len as i32)) / 2,
-					Y_OF_TOP_OF_MENU_ITEM
-						+ (offset as i32) * (MENU_ITEM_HEIGHT as i32)
-						- 4 + (MENU_ITEM_HEIGHT as i32) / 2,
+					self.y_of_top_of_menu_item(offset) - 4
+						+ (MAIN_MENU_ITEM_HEIGHT as i32) / 2,
 				);
 			}
 		}
 
-		if unsafe { *GAMEPAD1 } & BUTTON_2 != 0
+		if gamepad() & BUTTON_2 != 0
 		{
-			unsafe { *DRAW_COLORS = 2 }
+			set_draw_colors(2);
 			text("v1.1", 126, 150);
 		}
 	}
@@ -258,13 +562,773 @@ pub enum Transition
 {
 	Start
 	{
-		rng_seed: u64
+		scenario: &'static Scenario,
+		rng_seed: u64,
+		mode: Mode,
+		preset: Preset,
 	},
+	Continue,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum MenuItem
 {
+	Continue,
 	Start,
+	Campaign,
 	Freeplay,
+	Daily,
+	Scores,
+	Settings,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Screen
+{
+	Main,
+	Scores,
+	Seed(SeedEntry),
+	Daily,
+	Settings(usize),
+	Island(usize),
+	Campaign(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ScreenAction
+{
+	Play,
+	Back,
+}
+
+/// Lets the player pick a Freeplay seed one digit at a time, either by
+/// moving along the digits with the gamepad or by clicking the arrows
+/// above and below each digit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SeedEntry
+{
+	digits: [u8; NUM_SEED_DIGITS],
+	selected: usize,
+	preset: Preset,
+}
+
+impl SeedEntry
+{
+	fn new(seed: u64, preset: Preset) -> Self
+	{
+		let mut digits = [0; NUM_SEED_DIGITS];
+		let mut value = seed;
+		for digit in digits.iter_mut().rev()
+		{
+			*digit = (value % 10) as u8;
+			value /= 10;
+		}
+		Self {
+			digits,
+			selected: NUM_SEED_DIGITS - 1,
+			preset,
+		}
+	}
+
+	fn seed(&self) -> u64
+	{
+		self.digits
+			.iter()
+			.fold(0, |seed, &digit| 10 * seed + digit as u64)
+	}
+
+	fn update(
+		&mut self,
+		pressed: u8,
+		clicked: Option<(i32, i32)>,
+	) -> Option<ScreenAction>
+	{
+		if pressed & BUTTON_1 != 0
+		{
+			return Some(ScreenAction::Play);
+		}
+		else if pressed & BUTTON_2 != 0
+		{
+			return Some(ScreenAction::Back);
+		}
+		else if pressed & BUTTON_LEFT != 0
+		{
+			self.selected = self.selected.saturating_sub(1);
+		}
+		else if pressed & BUTTON_RIGHT != 0
+		{
+			if self.selected + 1 < NUM_SEED_DIGITS
+			{
+				self.selected += 1;
+			}
+		}
+		else if pressed & BUTTON_UP != 0
+		{
+			self.increment(self.selected);
+		}
+		else if pressed & BUTTON_DOWN != 0
+		{
+			self.decrement(self.selected);
+		}
+
+		let (x, y) = clicked?;
+		if is_on_button(x, y, Y_OF_TOP_OF_PLAY_BUTTON)
+		{
+			return Some(ScreenAction::Play);
+		}
+		else if is_on_button(x, y, Y_OF_TOP_OF_BACK_BUTTON)
+		{
+			return Some(ScreenAction::Back);
+		}
+		let dx = x - X_OF_LEFT_OF_SEED;
+		if dx < 0 || dx >= SEED_DIGIT_WIDTH * (NUM_SEED_DIGITS as i32)
+		{
+			return None;
+		}
+		let i = (dx / SEED_DIGIT_WIDTH) as usize;
+		if (Y_OF_SEED - 12..Y_OF_SEED - 2).contains(&y)
+		{
+			self.selected = i;
+			self.increment(i);
+		}
+		else if (Y_OF_SEED - 2..Y_OF_SEED + 10).contains(&y)
+		{
+			self.selected = i;
+		}
+		else if (Y_OF_SEED + 10..Y_OF_SEED + 20).contains(&y)
+		{
+			self.selected = i;
+			self.decrement(i);
+		}
+		None
+	}
+
+	fn increment(&mut self, i: usize)
+	{
+		self.digits[i] = (self.digits[i] + 1) % 10;
+	}
+
+	fn decrement(&mut self, i: usize)
+	{
+		self.digits[i] = (self.digits[i] + 9) % 10;
+	}
+
+	fn draw(&self)
+	{
+		set_draw_colors(4);
+		text("SEED", 64, 30);
+		set_draw_colors(3);
+		let name = self.preset.name();
+		text(name, 80 - 4 * (name.len() as i32), 42);
+
+		for (i, digit) in self.digits.iter().enumerate()
+		{
+			let x = X_OF_LEFT_OF_SEED + SEED_DIGIT_WIDTH * (i as i32) + 1;
+			if i == self.selected
+			{
+				set_draw_colors(0x44);
+				rect(x - 2, Y_OF_SEED - 2, 12, 12);
+				set_draw_colors(1);
+			}
+			else
+			{
+				set_draw_colors(3);
+			}
+			let txt = [b'0' + digit];
+			let txt = unsafe { std::str::from_utf8_unchecked(&txt) };
+			text(txt, x, Y_OF_SEED);
+			set_draw_colors(if i == self.selected { 4 } else { 2 });
+			text("^", x, Y_OF_SEED - 11);
+			text("v", x, Y_OF_SEED + 11);
+		}
+
+		draw_buttons(&[
+			(Y_OF_TOP_OF_PLAY_BUTTON, "Play"),
+			(Y_OF_TOP_OF_BACK_BUTTON, "Back"),
+		]);
+	}
+}
+
+fn update_day(
+	day: &mut u16,
+	pressed: u8,
+	clicked: Option<(i32, i32)>,
+) -> Option<ScreenAction>
+{
+	if pressed & BUTTON_1 != 0
+	{
+		return Some(ScreenAction::Play);
+	}
+	else if pressed & BUTTON_2 != 0
+	{
+		return Some(ScreenAction::Back);
+	}
+	else if pressed & (BUTTON_LEFT | BUTTON_DOWN) != 0
+	{
+		*day = std::cmp::max(*day - 1, 1);
+	}
+	else if pressed & (BUTTON_RIGHT | BUTTON_UP) != 0
+	{
+		*day = std::cmp::min(*day + 1, MAX_DAY);
+	}
+
+	let (x, y) = clicked?;
+	if is_on_button(x, y, Y_OF_TOP_OF_PLAY_BUTTON)
+	{
+		return Some(ScreenAction::Play);
+	}
+	else if is_on_button(x, y, Y_OF_TOP_OF_BACK_BUTTON)
+	{
+		return Some(ScreenAction::Back);
+	}
+	else if (Y_OF_DAY - 4..Y_OF_DAY + 12).contains(&y)
+	{
+		if (X_OF_DAY_ARROWS - 4..X_OF_DAY_ARROWS + 12).contains(&x)
+		{
+			*day = std::cmp::max(*day - 1, 1);
+		}
+		else if (156 - X_OF_DAY_ARROWS - 12..156 - X_OF_DAY_ARROWS + 4)
+			.contains(&x)
+		{
+			*day = std::cmp::min(*day + 1, MAX_DAY);
+		}
+	}
+	None
+}
+
+fn draw_daily(daily: &Daily)
+{
+	set_draw_colors(4);
+	text("DAILY", 60, 30);
+
+	set_draw_colors(3);
+	text("<", X_OF_DAY_ARROWS, Y_OF_DAY);
+	text(">", 152 - X_OF_DAY_ARROWS, Y_OF_DAY);
+	text("Day", 48, Y_OF_DAY);
+	set_draw_colors(4);
+	draw_number(daily.day.into(), 80, Y_OF_DAY);
+
+	set_draw_colors(2);
+	text("Best:", 48, Y_OF_DAY + 18);
+	match daily.best(daily.day)
+	{
+		Some(score) =>
+		{
+			draw_number(score.into(), 96, Y_OF_DAY + 18);
+		}
+		None => text("-", 96, Y_OF_DAY + 18),
+	}
+
+	draw_buttons(&[
+		(Y_OF_TOP_OF_PLAY_BUTTON, "Play"),
+		(Y_OF_TOP_OF_BACK_BUTTON, "Back"),
+	]);
+}
+
+fn y_of_top_of_setting(offset: usize) -> i32
+{
+	50 + 12 * (offset as i32)
+}
+
+fn draw_settings(settings: &Settings, selected: usize)
+{
+	set_draw_colors(4);
+	text("SETTINGS", 48, 30);
+
+	for (offset, setting) in SETTINGS.iter().enumerate()
+	{
+		let y = y_of_top_of_setting(offset);
+		if offset == selected
+		{
+			set_draw_colors(0x44);
+			rect(
+				X_OF_CENTER_OF_MENU_ITEM - (MENU_ITEM_WIDTH as i32) / 2,
+				y,
+				MENU_ITEM_WIDTH,
+				MENU_ITEM_HEIGHT,
+			);
+			set_draw_colors(1);
+		}
+		else
+		{
+			set_draw_colors(3);
+		}
+		let value = if settings.get(*setting) { "On" } else { "Off" };
+		text(setting.name(), 44, y + 1);
+		text(value, 116 - 8 * (value.len() as i32), y + 1);
+	}
+
+	draw_buttons(&[(Y_OF_TOP_OF_BACK_BUTTON, "Back")]);
+}
+
+/// Moves the selection in a list of rows such as the settings, and tells
+/// whether the selected row was picked or the player wants to go back.
+fn update_list(
+	selected: &mut usize,
+	len: usize,
+	width: u32,
+	y_of_top: fn(usize) -> i32,
+	pressed: u8,
+	(mouse_x, mouse_y): (i32, i32),
+	clicked: Option<(i32, i32)>,
+) -> Option<ScreenAction>
+{
+	let hovered =
+		(0..len).find(|i| is_in_row(mouse_x, mouse_y, y_of_top(*i), width));
+	if pressed & BUTTON_UP != 0
+	{
+		*selected = selected.saturating_sub(1);
+	}
+	else if pressed & BUTTON_DOWN != 0 && *selected + 1 < len
+	{
+		*selected += 1;
+	}
+	else if let Some(i) = hovered
+	{
+		*selected = i;
+	}
+	if pressed & BUTTON_1 != 0
+	{
+		return Some(ScreenAction::Play);
+	}
+	else if pressed & BUTTON_2 != 0
+	{
+		return Some(ScreenAction::Back);
+	}
+	let (x, y) = clicked?;
+	if hovered.is_some()
+	{
+		Some(ScreenAction::Play)
+	}
+	else if is_on_button(x, y, Y_OF_TOP_OF_BACK_BUTTON)
+	{
+		Some(ScreenAction::Back)
+	}
+	else
+	{
+		None
+	}
+}
+
+fn draw_islands(selected: usize)
+{
+	set_draw_colors(4);
+	text("ISLAND", 56, 30);
+
+	for (offset, preset) in PRESETS.iter().enumerate()
+	{
+		let y = y_of_top_of_setting(offset);
+		if offset == selected
+		{
+			set_draw_colors(0x44);
+			rect(
+				X_OF_CENTER_OF_MENU_ITEM - (ISLAND_ITEM_WIDTH as i32) / 2,
+				y,
+				ISLAND_ITEM_WIDTH,
+				MENU_ITEM_HEIGHT,
+			);
+			set_draw_colors(1);
+		}
+		else
+		{
+			set_draw_colors(3);
+		}
+		let name = preset.name();
+		text(name, 80 - 4 * (name.len() as i32), y + 1);
+	}
+
+	draw_buttons(&[(Y_OF_TOP_OF_BACK_BUTTON, "Back")]);
+}
+
+fn y_of_top_of_chapter(offset: usize) -> i32
+{
+	16 + 10 * (offset as i32)
+}
+
+fn draw_campaign(progress: &Progress, selected: usize)
+{
+	set_draw_colors(4);
+	text("CAMPAIGN", 48, 4);
+
+	for (offset, chapter) in CHAPTERS.iter().enumerate()
+	{
+		let y = y_of_top_of_chapter(offset);
+		if offset == selected
+		{
+			set_draw_colors(0x44);
+			rect(
+				X_OF_CENTER_OF_MENU_ITEM - (CHAPTER_ITEM_WIDTH as i32) / 2,
+				y,
+				CHAPTER_ITEM_WIDTH,
+				MENU_ITEM_HEIGHT,
+			);
+			set_draw_colors(1);
+		}
+		else if progress.is_unlocked(offset)
+		{
+			set_draw_colors(3);
+		}
+		else
+		{
+			set_draw_colors(2);
+		}
+		draw_number(offset as u64 + 1, 14, y + 1);
+		if progress.is_unlocked(offset)
+		{
+			text(chapter.name, 32, y + 1);
+		}
+		else
+		{
+			text("Locked", 32, y + 1);
+		}
+		if progress.is_completed(offset)
+		{
+			set_draw_colors(0x3210);
+			sprites::draw_wreath_icon(140, y);
+		}
+	}
+
+	if let Some(goal) = CHAPTERS[selected].scenario.goal
+	{
+		set_draw_colors(3);
+		goal.draw(14, 140);
+	}
+
+	draw_buttons(&[(Y_OF_TOP_OF_BACK_BUTTON, "Back")]);
+}
+
+fn draw_buttons(buttons: &[(i32, &str)])
+{
+	let (mouse_x, mouse_y) = mouse_xy();
+	for &(y, txt) in buttons
+	{
+		if is_on_button(mouse_x as i32, mouse_y as i32, y)
+		{
+			set_draw_colors(0x44);
+			rect(
+				X_OF_CENTER_OF_MENU_ITEM - (MENU_ITEM_WIDTH as i32) / 2,
+				y,
+				MENU_ITEM_WIDTH,
+				MENU_ITEM_HEIGHT,
+			);
+			set_draw_colors(1);
+		}
+		else
+		{
+			set_draw_colors(3);
+		}
+		text(txt, 80 - 4 * (txt.len() as i32), y + 1);
+	}
+}
+
+fn is_on_button(x: i32, y: i32, y_of_top: i32) -> bool
+{
+	is_in_row(x, y, y_of_top, MENU_ITEM_WIDTH)
+}
+
+fn is_in_row(x: i32, y: i32, y_of_top: i32, width: u32) -> bool
+{
+	let left = X_OF_CENTER_OF_MENU_ITEM - (width as i32) / 2;
+	x > left
+		&& y > y_of_top
+		&& x < left + width as i32
+		&& y < y_of_top + MENU_ITEM_HEIGHT as i32
+}
+
+fn draw_scores(leaderboard: &Leaderboard)
+{
+	set_draw_colors(4);
+	text("HIGH SCORES", 36, 6);
+	for (i, (mode, title)) in
+		[(Mode::Start, "Start"), (Mode::Freeplay, "Freeplay")]
+			.iter()
+			.enumerate()
+	{
+		let mut y = 20 + 70 * (i as i32);
+		set_draw_colors(4);
+		text(title, 4, y);
+		y += 10;
+		set_draw_colors(2);
+		// Leave room for seeds of as many digits as can be entered.
+		text("pts yr vl seed", 16, y);
+		for (rank, entry) in leaderboard.scores(*mode).enumerate()
+		{
+			y += 10;
+			set_draw_colors(3);
+			draw_number(rank as u64 + 1, 4, y);
+			draw_number(entry.score.into(), 16, y);
+			draw_number(entry.years_survived.into(), 48, y);
+			draw_number(entry.villages_built.into(), 72, y);
+			draw_number(entry.seed, 96, y);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::framebuffer::assert_snapshot;
+	use crate::level::MAP_LOCK;
+	use crate::mock::Mock;
+	use crate::platform::run_with;
+
+	#[test]
+	fn main_menu()
+	{
+		let mut mock = Mock::new();
+		let transition = run_with(&mut mock, || {
+			let mut menu = Menu::new();
+			// Wait until the menu items have appeared.
+			for _ in 0..(NUM_INTRO_ANIMATION_TICKS + 31)
+			{
+				menu.update();
+			}
+			menu.draw();
+			menu.update()
+		});
+		assert!(transition.is_none());
+		assert_snapshot(&mock.screen, "menu");
+	}
+
+	#[test]
+	fn main_menu_with_saved_game()
+	{
+		let _lock = MAP_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+		let mut mock = Mock::new();
+		run_with(&mut mock, || {
+			let mut level =
+				Level::new(&GENERATED, 1, Mode::Freeplay, Preset::Classic);
+			while !Level::has_saved_game()
+			{
+				level.update();
+			}
+			let mut menu = Menu::new();
+			menu.ticks = NUM_INTRO_ANIMATION_TICKS + 31;
+			menu.selected_item = Some(MenuItem::Continue);
+			menu.draw();
+		});
+		assert_snapshot(&mock.screen, "menu_continue");
+	}
+
+	#[test]
+	fn scores_screen()
+	{
+		let mut mock = Mock::new();
+		run_with(&mut mock, || {
+			let mut leaderboard = Leaderboard::load();
+			let entries = [
+				(Mode::Start, 120, 1),
+				(Mode::Start, 45, 1),
+				(Mode::Freeplay, 999, 99999999),
+			];
+			for (mode, score, seed) in entries
+			{
+				leaderboard.submit(
+					mode,
+					crate::scores::Score {
+						score,
+						seed,
+						years_survived: 7,
+						villages_built: 2,
+					},
+				);
+			}
+			let mut menu = Menu::new();
+			menu.screen = Screen::Scores;
+			menu.ticks = NUM_INTRO_ANIMATION_TICKS;
+			menu.draw();
+		});
+		assert_snapshot(&mock.screen, "menu_scores");
+	}
+
+	#[test]
+	fn enter_seed()
+	{
+		let mut mock = Mock::new();
+		let mut menu = Menu::new();
+		menu.screen = Screen::Seed(SeedEntry::new(1234, Preset::Highlands));
+		let mut press = |mock: &mut Mock, buttons: u8| {
+			mock.gamepad = buttons;
+			run_with(mock, || menu.update());
+			mock.gamepad = 0;
+			run_with(mock, || menu.update())
+		};
+		press(&mut mock, BUTTON_UP);
+		press(&mut mock, BUTTON_LEFT);
+		press(&mut mock, BUTTON_LEFT);
+		press(&mut mock, BUTTON_DOWN);
+		let transition = press(&mut mock, BUTTON_1);
+		assert!(matches!(
+			transition,
+			Some(Transition::Start {
+				scenario: &Scenario { id: 0, .. },
+				rng_seed: 1135,
+				mode: Mode::Freeplay,
+				preset: Preset::Highlands,
+			})
+		));
+		mock.clear_recordings();
+		run_with(&mut mock, || {
+			menu.loading_transition = None;
+			menu.ticks = NUM_INTRO_ANIMATION_TICKS;
+			menu.draw();
+		});
+		assert_snapshot(&mock.screen, "menu_seed");
+	}
+
+	#[test]
+	fn pick_island()
+	{
+		let mut mock = Mock::new();
+		let mut menu = Menu::new();
+		menu.screen = Screen::Island(0);
+		let mut press = |mock: &mut Mock, buttons: u8| {
+			mock.gamepad = buttons;
+			run_with(mock, || menu.update());
+			mock.gamepad = 0;
+			run_with(mock, || menu.update());
+		};
+		press(&mut mock, BUTTON_DOWN);
+		press(&mut mock, BUTTON_DOWN);
+		press(&mut mock, BUTTON_DOWN);
+		press(&mut mock, BUTTON_UP);
+		mock.clear_recordings();
+		run_with(&mut mock, || {
+			menu.ticks = NUM_INTRO_ANIMATION_TICKS;
+			menu.draw();
+		});
+		assert_snapshot(&mock.screen, "menu_island");
+		mock.gamepad = BUTTON_1;
+		run_with(&mut mock, || menu.update());
+		assert!(matches!(
+			menu.screen,
+			Screen::Seed(SeedEntry {
+				preset: Preset::Highlands,
+				..
+			})
+		));
+	}
+
+	#[test]
+	fn pick_chapter()
+	{
+		let mut mock = Mock::new();
+		let mut menu = run_with(&mut mock, || {
+			Progress::load().complete(0);
+			Menu::new()
+		});
+		menu.screen = Screen::Campaign(0);
+		let mut press = |mock: &mut Mock, buttons: u8| {
+			mock.gamepad = buttons;
+			run_with(mock, || menu.update());
+			mock.gamepad = 0;
+			run_with(mock, || menu.update())
+		};
+		press(&mut mock, BUTTON_DOWN);
+		press(&mut mock, BUTTON_DOWN);
+		assert!(press(&mut mock, BUTTON_1).is_none());
+		press(&mut mock, BUTTON_UP);
+		let transition = press(&mut mock, BUTTON_1);
+		assert!(matches!(
+			transition,
+			Some(Transition::Start {
+				scenario: &Scenario { id: 4, .. },
+				mode: Mode::Campaign,
+				preset: Preset::CoastalPlain,
+				..
+			})
+		));
+		mock.clear_recordings();
+		run_with(&mut mock, || {
+			menu.loading_transition = None;
+			menu.ticks = NUM_INTRO_ANIMATION_TICKS;
+			menu.draw();
+		});
+		assert_snapshot(&mock.screen, "menu_campaign");
+	}
+
+	#[test]
+	fn play_daily()
+	{
+		let mut mock = Mock::new();
+		let mut menu = Menu::new();
+		menu.screen = Screen::Daily;
+		let mut press = |mock: &mut Mock, buttons: u8| {
+			mock.gamepad = buttons;
+			run_with(mock, || menu.update());
+			mock.gamepad = 0;
+			run_with(mock, || menu.update())
+		};
+		press(&mut mock, BUTTON_LEFT);
+		press(&mut mock, BUTTON_RIGHT);
+		press(&mut mock, BUTTON_RIGHT);
+		let transition = press(&mut mock, BUTTON_1);
+		let expected_seed = Daily::seed(3);
+		assert!(matches!(
+			transition,
+			Some(Transition::Start {
+				scenario: &Scenario { id: 0, .. },
+				rng_seed,
+				mode: Mode::Daily,
+				preset: Preset::Classic,
+			}) if rng_seed == expected_seed
+		));
+		run_with(&mut mock, || {
+			let mut daily = Daily::load();
+			assert_eq!(daily.day, 3);
+			daily.submit(42);
+			let mut menu = Menu::new();
+			menu.screen = Screen::Daily;
+			menu.ticks = NUM_INTRO_ANIMATION_TICKS;
+			menu.draw();
+		});
+		assert_snapshot(&mock.screen, "menu_daily");
+	}
+
+	#[test]
+	fn toggle_undo()
+	{
+		let mut mock = Mock::new();
+		let mut menu = Menu::new();
+		menu.screen = Screen::Settings(0);
+		mock.mouse_x = X_OF_CENTER_OF_MENU_ITEM as i16;
+		mock.mouse_y = y_of_top_of_setting(0) as i16 + 4;
+		mock.mouse_buttons = MOUSE_LEFT;
+		run_with(&mut mock, || {
+			assert!(Settings::load().get(Setting::Undo));
+			menu.update();
+			assert!(!Settings::load().get(Setting::Undo));
+			menu.ticks = NUM_INTRO_ANIMATION_TICKS;
+			menu.draw();
+		});
+		assert_snapshot(&mock.screen, "menu_settings");
+	}
+
+	#[test]
+	fn click_start()
+	{
+		let mut mock = Mock::new();
+		mock.mouse_x = X_OF_CENTER_OF_MENU_ITEM as i16;
+		mock.mouse_y =
+			(Y_OF_BOTTOM_OF_MENU - 6 * MAIN_MENU_ITEM_HEIGHT as i32 + 4) as i16;
+		mock.mouse_buttons = MOUSE_LEFT;
+		let transition = run_with(&mut mock, || {
+			let mut menu = Menu::new();
+			for _ in 0..NUM_INTRO_ANIMATION_TICKS
+			{
+				menu.update();
+			}
+			menu.update()
+		});
+		assert!(matches!(
+			transition,
+			Some(Transition::Start {
+				scenario: &Scenario { id: 1, .. },
+				rng_seed: 1,
+				mode: Mode::Start,
+				preset: Preset::Classic,
+			})
+		));
+	}
 }
@@ -0,0 +1,207 @@
+//
+// Part of the-romans-are-coming
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+//! A headless platform for running the game natively, e.g. in tests.
+//! Input is whatever the test puts in the public fields; drawing goes to a
+//! software framebuffer, and text, tones and traces are recorded so that
+//! they can be inspected afterwards.
+
+#![allow(unused)]
+
+use crate::framebuffer::Framebuffer;
+use crate::platform::Platform;
+
+pub const DISK_SIZE: usize = 1024;
+
+pub struct Mock
+{
+	pub gamepad: u8,
+	pub mouse_x: i16,
+	pub mouse_y: i16,
+	pub mouse_buttons: u8,
+	pub screen: Framebuffer,
+	pub disk: Vec<u8>,
+	pub num_disk_writes: usize,
+	pub num_draw_calls: usize,
+	pub texts: Vec<(String, i32, i32)>,
+	pub tones: Vec<Tone>,
+	pub traces: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tone
+{
+	pub frequency: u32,
+	pub duration: u32,
+	pub volume: u32,
+	pub flags: u32,
+}
+
+impl Mock
+{
+	pub fn new() -> Self
+	{
+		Self {
+			gamepad: 0,
+			mouse_x: 0,
+			mouse_y: 0,
+			mouse_buttons: 0,
+			screen: Framebuffer::new(),
+			disk: Vec::new(),
+			num_disk_writes: 0,
+			num_draw_calls: 0,
+			texts: Vec::new(),
+			tones: Vec::new(),
+			traces: Vec::new(),
+		}
+	}
+
+	pub fn has_text(&self, needle: &str) -> bool
+	{
+		self.texts
+			.iter()
+			.any(|(text, _x, _y)| text.contains(needle))
+	}
+
+	pub fn clear_recordings(&mut self)
+	{
+		self.num_draw_calls = 0;
+		self.texts.clear();
+		self.tones.clear();
+		self.traces.clear();
+	}
+}
+
+impl Default for Mock
+{
+	fn default() -> Self
+	{
+		Self::new()
+	}
+}
+
+impl Platform for Mock
+{
+	fn blit_sub(
+		&mut self,
+		sprite: &[u8],
+		x: i32,
+		y: i32,
+		width: u32,
+		height: u32,
+		src_x: u32,
+		src_y: u32,
+		stride: u32,
+		flags: u32,
+	)
+	{
+		self.num_draw_calls += 1;
+		self.screen
+			.blit_sub(sprite, x, y, width, height, src_x, src_y, stride, flags);
+	}
+
+	fn line(&mut self, x1: i32, y1: i32, x2: i32, y2: i32)
+	{
+		self.num_draw_calls += 1;
+		self.screen.line(x1, y1, x2, y2);
+	}
+
+	fn oval(&mut self, x: i32, y: i32, width: u32, height: u32)
+	{
+		self.num_draw_calls += 1;
+		self.screen.oval(x, y, width, height);
+	}
+
+	fn rect(&mut self, x: i32, y: i32, width: u32, height: u32)
+	{
+		self.num_draw_calls += 1;
+		self.screen.rect(x, y, width, height);
+	}
+
+	fn text(&mut self, text: &str, x: i32, y: i32)
+	{
+		self.num_draw_calls += 1;
+		self.screen.text(text, x, y);
+		self.texts.push((text.to_string(), x, y));
+	}
+
+	fn hline(&mut self, x: i32, y: i32, len: u32)
+	{
+		self.num_draw_calls += 1;
+		self.screen.hline(x, y, len);
+	}
+
+	fn vline(&mut self, x: i32, y: i32, len: u32)
+	{
+		self.num_draw_calls += 1;
+		self.screen.vline(x, y, len);
+	}
+
+	fn draw_colors(&self) -> u16
+	{
+		self.screen.draw_colors
+	}
+
+	fn set_draw_colors(&mut self, draw_colors: u16)
+	{
+		self.screen.draw_colors = draw_colors;
+	}
+
+	fn palette(&self) -> [u32; 4]
+	{
+		self.screen.palette
+	}
+
+	fn set_palette(&mut self, palette: [u32; 4])
+	{
+		self.screen.palette = palette;
+	}
+
+	fn gamepad(&self) -> u8
+	{
+		self.gamepad
+	}
+
+	fn mouse_xy(&self) -> (i16, i16)
+	{
+		(self.mouse_x, self.mouse_y)
+	}
+
+	fn mouse_buttons(&self) -> u8
+	{
+		self.mouse_buttons
+	}
+
+	fn tone(&mut self, frequency: u32, duration: u32, volume: u32, flags: u32)
+	{
+		self.tones.push(Tone {
+			frequency,
+			duration,
+			volume,
+			flags,
+		});
+	}
+
+	fn diskr(&mut self, dest: &mut [u8]) -> u32
+	{
+		let n = std::cmp::min(dest.len(), self.disk.len());
+		dest[..n].copy_from_slice(&self.disk[..n]);
+		n as u32
+	}
+
+	fn diskw(&mut self, src: &[u8]) -> u32
+	{
+		let n = std::cmp::min(src.len(), DISK_SIZE);
+		self.disk = src[..n].to_vec();
+		self.num_disk_writes += 1;
+		n as u32
+	}
+
+	fn trace(&mut self, text: &str)
+	{
+		self.traces.push(text.to_string());
+	}
+}
@@ -0,0 +1,301 @@
+//
+// Part of the-romans-are-coming
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+//! A small tracker that plays looping patterns on the four tone channels.
+//! Each track is a list of steps holding a MIDI note number (0 for a rest)
+//! and the sequencer moves one step forward every few frames.
+
+use crate::platform::*;
+
+const NUM_STEPS: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Theme
+{
+	Menu,
+	Level,
+	Occupation,
+	GameOver,
+}
+
+/// What should be playing right now. For the Level theme, the intensity
+/// goes from 0 up to MAX_INTENSITY and adds layers and tempo.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cue
+{
+	pub theme: Theme,
+	pub intensity: u8,
+}
+
+pub const MAX_INTENSITY: u8 = 10;
+
+struct Track
+{
+	notes: [u8; NUM_STEPS],
+	/// How many steps each note is held for.
+	length: u8,
+	volume: u8,
+	flags: u32,
+	/// The Level theme only adds this track from this intensity onwards.
+	min_intensity: u8,
+}
+
+const BASS: Track = Track {
+	notes: [45, 0, 52, 0, 45, 0, 52, 0, 41, 0, 48, 0, 43, 0, 50, 0],
+	length: 2,
+	volume: 40,
+	flags: TONE_TRIANGLE,
+	min_intensity: 0,
+};
+
+const MELODY: Track = Track {
+	notes: [69, 0, 72, 0, 76, 0, 74, 72, 71, 0, 72, 74, 76, 0, 0, 0],
+	length: 1,
+	volume: 12,
+	flags: TONE_PULSE1 | TONE_MODE2,
+	min_intensity: 3,
+};
+
+const HARMONY: Track = Track {
+	notes: [57, 0, 0, 0, 60, 0, 0, 0, 53, 0, 0, 0, 55, 0, 0, 0],
+	length: 4,
+	volume: 8,
+	flags: TONE_PULSE2 | TONE_MODE3,
+	min_intensity: 5,
+};
+
+const DRUMS: Track = Track {
+	notes: [30, 0, 0, 0, 60, 0, 30, 0, 30, 0, 0, 0, 60, 0, 30, 30],
+	length: 1,
+	volume: 15,
+	flags: TONE_NOISE,
+	min_intensity: 7,
+};
+
+const MENU_BASS: Track = Track {
+	notes: [45, 0, 0, 0, 52, 0, 0, 0, 41, 0, 0, 0, 43, 0, 0, 0],
+	length: 4,
+	volume: 30,
+	flags: TONE_TRIANGLE,
+	min_intensity: 0,
+};
+
+const MENU_MELODY: Track = Track {
+	notes: [69, 0, 0, 72, 0, 0, 71, 0, 69, 0, 0, 0, 67, 0, 0, 0],
+	length: 3,
+	volume: 8,
+	flags: TONE_PULSE1 | TONE_MODE3,
+	min_intensity: 0,
+};
+
+const OCCUPATION_BASS: Track = Track {
+	notes: [45, 45, 0, 45, 44, 44, 0, 44, 45, 45, 0, 45, 44, 44, 0, 44],
+	length: 1,
+	volume: 45,
+	flags: TONE_TRIANGLE,
+	min_intensity: 0,
+};
+
+const OCCUPATION_DRUMS: Track = Track {
+	notes: [30, 0, 30, 0, 30, 0, 30, 0, 30, 0, 30, 0, 30, 30, 30, 30],
+	length: 1,
+	volume: 20,
+	flags: TONE_NOISE,
+	min_intensity: 0,
+};
+
+const GAME_OVER_BASS: Track = Track {
+	notes: [57, 0, 0, 0, 55, 0, 0, 0, 53, 0, 0, 0, 52, 0, 0, 0],
+	length: 4,
+	volume: 35,
+	flags: TONE_TRIANGLE,
+	min_intensity: 0,
+};
+
+impl Theme
+{
+	fn tracks(&self) -> &'static [Track]
+	{
+		match self
+		{
+			Theme::Menu => &[MENU_BASS, MENU_MELODY],
+			Theme::Level => &[BASS, MELODY, HARMONY, DRUMS],
+			Theme::Occupation => &[OCCUPATION_BASS, HARMONY, OCCUPATION_DRUMS],
+			Theme::GameOver => &[GAME_OVER_BASS],
+		}
+	}
+
+	fn frames_per_step(&self, intensity: u8) -> u8
+	{
+		match self
+		{
+			Theme::Menu => 15,
+			Theme::Level => 12 - intensity / 2,
+			Theme::Occupation => 7,
+			Theme::GameOver => 20,
+		}
+	}
+
+	fn is_looping(&self) -> bool
+	{
+		*self != Theme::GameOver
+	}
+}
+
+pub struct Sequencer
+{
+	theme: Option<Theme>,
+	step: usize,
+	ticks: u8,
+}
+
+impl Sequencer
+{
+	pub const fn new() -> Self
+	{
+		Self {
+			theme: None,
+			step: 0,
+			ticks: 0,
+		}
+	}
+
+	/// Should be called once per frame with whatever should be playing.
+	pub fn update(&mut self, cue: Option<Cue>)
+	{
+		let cue = match cue
+		{
+			Some(cue) => cue,
+			None =>
+			{
+				self.theme = None;
+				return;
+			}
+		};
+		if self.theme != Some(cue.theme)
+		{
+			self.theme = Some(cue.theme);
+			self.step = 0;
+			self.ticks = 0;
+		}
+		if self.step >= NUM_STEPS
+		{
+			return;
+		}
+		let intensity = std::cmp::min(cue.intensity, MAX_INTENSITY);
+		let frames_per_step = cue.theme.frames_per_step(intensity);
+		if self.ticks == 0
+		{
+			for track in cue.theme.tracks()
+			{
+				if cue.theme == Theme::Level && intensity < track.min_intensity
+				{
+					continue;
+				}
+				let note = track.notes[self.step];
+				if note == 0
+				{
+					continue;
+				}
+				let frames = (track.length * frames_per_step) as u32;
+				let sustain = frames * 3 / 4;
+				let release = frames - sustain;
+				tone(
+					note_frequency(note),
+					sustain | (release << 8),
+					track.volume.into(),
+					track.flags,
+				);
+			}
+		}
+		self.ticks += 1;
+		if self.ticks >= frames_per_step
+		{
+			self.ticks = 0;
+			self.step += 1;
+			if self.step == NUM_STEPS && cue.theme.is_looping()
+			{
+				self.step = 0;
+			}
+		}
+	}
+}
+
+/// The frequency in Hz of a MIDI note number, where 69 is A4 at 440 Hz.
+fn note_frequency(note: u8) -> u32
+{
+	const OCTAVE_4: [u32; 12] =
+		[262, 277, 294, 311, 330, 349, 370, 392, 415, 440, 466, 494];
+	let octave = (note / 12) as i32 - 5;
+	let frequency = OCTAVE_4[(note % 12) as usize];
+	if octave >= 0
+	{
+		frequency << octave
+	}
+	else
+	{
+		frequency >> (-octave)
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::mock::Mock;
+	use crate::platform::run_with;
+
+	fn count_tones(cue: Option<Cue>, flags: u32) -> usize
+	{
+		let mut mock = Mock::new();
+		let mut sequencer = Sequencer::new();
+		run_with(&mut mock, || {
+			for _ in 0..600
+			{
+				sequencer.update(cue);
+			}
+		});
+		mock.tones.iter().filter(|t| t.flags == flags).count()
+	}
+
+	#[test]
+	fn intensity_adds_layers()
+	{
+		let calm = Some(Cue {
+			theme: Theme::Level,
+			intensity: 0,
+		});
+		let tense = Some(Cue {
+			theme: Theme::Level,
+			intensity: MAX_INTENSITY,
+		});
+		assert_eq!(count_tones(calm, TONE_NOISE), 0);
+		assert!(count_tones(tense, TONE_NOISE) > 0);
+		assert!(
+			count_tones(tense, TONE_TRIANGLE)
+				> count_tones(calm, TONE_TRIANGLE)
+		);
+		assert_eq!(count_tones(None, TONE_TRIANGLE), 0);
+	}
+
+	#[test]
+	fn game_over_plays_once()
+	{
+		let cue = Some(Cue {
+			theme: Theme::GameOver,
+			intensity: 0,
+		});
+		assert_eq!(count_tones(cue, TONE_TRIANGLE), 4);
+	}
+
+	#[test]
+	fn note_frequencies()
+	{
+		assert_eq!(note_frequency(69), 440);
+		assert_eq!(note_frequency(57), 220);
+		assert_eq!(note_frequency(81), 880);
+	}
+}
@@ -4,7 +4,7 @@
 // License: MIT
 //
 
-use crate::wasm4::*;
+use crate::platform::*;
 
 // Painted Parchment 9 by skeddles
 // https://lospec.com/palette-list/painted-parchment-9
@@ -37,5 +37,5 @@ pub const MENU: [u32; 4] = [BLACK, MENU_BLACK, BROWN, PAPER];
 
 pub fn setup()
 {
-	unsafe { *PALETTE = DEFAULT };
+	set_palette(DEFAULT);
 }
@@ -0,0 +1,350 @@
+//
+// Part of the-romans-are-coming
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+//! Everything the game needs from the console, behind a trait so that the
+//! game can also run on the host. The free functions in this module mirror
+//! the WASM-4 API and forward to whichever platform is currently active:
+//! the real console when compiled to WebAssembly, and otherwise the platform
+//! passed to `run_with()` (or a headless mock if there is none).
+
+pub use crate::wasm4::SCREEN_SIZE;
+pub use crate::wasm4::BLIT_1BPP;
+#[cfg(not(target_arch = "wasm32"))]
+pub use crate::wasm4::{BLIT_2BPP, BLIT_FLIP_X, BLIT_FLIP_Y, BLIT_ROTATE};
+pub use crate::wasm4::{BUTTON_1, BUTTON_2};
+pub use crate::wasm4::{BUTTON_DOWN, BUTTON_LEFT, BUTTON_RIGHT, BUTTON_UP};
+pub use crate::wasm4::{MOUSE_LEFT, MOUSE_RIGHT};
+pub use crate::wasm4::{TONE_MODE2, TONE_MODE3};
+pub use crate::wasm4::{TONE_NOISE, TONE_TRIANGLE};
+pub use crate::wasm4::{TONE_PULSE1, TONE_PULSE2};
+
+pub trait Platform
+{
+	// Drawing.
+	#[allow(clippy::too_many_arguments)]
+	fn blit_sub(
+		&mut self,
+		sprite: &[u8],
+		x: i32,
+		y: i32,
+		width: u32,
+		height: u32,
+		src_x: u32,
+		src_y: u32,
+		stride: u32,
+		flags: u32,
+	);
+	fn line(&mut self, x1: i32, y1: i32, x2: i32, y2: i32);
+	#[allow(dead_code)]
+	fn oval(&mut self, x: i32, y: i32, width: u32, height: u32);
+	fn rect(&mut self, x: i32, y: i32, width: u32, height: u32);
+	fn text(&mut self, text: &str, x: i32, y: i32);
+	fn hline(&mut self, x: i32, y: i32, len: u32);
+	#[allow(dead_code)]
+	fn vline(&mut self, x: i32, y: i32, len: u32);
+	#[allow(dead_code)]
+	fn draw_colors(&self) -> u16;
+	fn set_draw_colors(&mut self, draw_colors: u16);
+
+	// Palette.
+	fn palette(&self) -> [u32; 4];
+	fn set_palette(&mut self, palette: [u32; 4]);
+
+	// Input.
+	fn gamepad(&self) -> u8;
+	fn mouse_xy(&self) -> (i16, i16);
+	fn mouse_buttons(&self) -> u8;
+
+	// Sound.
+	fn tone(&mut self, frequency: u32, duration: u32, volume: u32, flags: u32);
+
+	// Persistent storage.
+	fn diskr(&mut self, dest: &mut [u8]) -> u32;
+	fn diskw(&mut self, src: &[u8]) -> u32;
+
+	// Tracing.
+	fn trace(&mut self, text: &str);
+}
+
+#[cfg(target_arch = "wasm32")]
+pub struct Wasm4;
+
+#[cfg(target_arch = "wasm32")]
+impl Platform for Wasm4
+{
+	fn blit_sub(
+		&mut self,
+		sprite: &[u8],
+		x: i32,
+		y: i32,
+		width: u32,
+		height: u32,
+		src_x: u32,
+		src_y: u32,
+		stride: u32,
+		flags: u32,
+	)
+	{
+		crate::wasm4::blit_sub(
+			sprite, x, y, width, height, src_x, src_y, stride, flags,
+		)
+	}
+
+	fn line(&mut self, x1: i32, y1: i32, x2: i32, y2: i32)
+	{
+		crate::wasm4::line(x1, y1, x2, y2)
+	}
+
+	fn oval(&mut self, x: i32, y: i32, width: u32, height: u32)
+	{
+		crate::wasm4::oval(x, y, width, height)
+	}
+
+	fn rect(&mut self, x: i32, y: i32, width: u32, height: u32)
+	{
+		crate::wasm4::rect(x, y, width, height)
+	}
+
+	fn text(&mut self, text: &str, x: i32, y: i32)
+	{
+		crate::wasm4::text(text, x, y)
+	}
+
+	fn hline(&mut self, x: i32, y: i32, len: u32)
+	{
+		crate::wasm4::hline(x, y, len)
+	}
+
+	fn vline(&mut self, x: i32, y: i32, len: u32)
+	{
+		crate::wasm4::vline(x, y, len)
+	}
+
+	fn draw_colors(&self) -> u16
+	{
+		unsafe { *crate::wasm4::DRAW_COLORS }
+	}
+
+	fn set_draw_colors(&mut self, draw_colors: u16)
+	{
+		unsafe { *crate::wasm4::DRAW_COLORS = draw_colors }
+	}
+
+	fn palette(&self) -> [u32; 4]
+	{
+		unsafe { *crate::wasm4::PALETTE }
+	}
+
+	fn set_palette(&mut self, palette: [u32; 4])
+	{
+		unsafe { *crate::wasm4::PALETTE = palette }
+	}
+
+	fn gamepad(&self) -> u8
+	{
+		unsafe { *crate::wasm4::GAMEPAD1 }
+	}
+
+	fn mouse_xy(&self) -> (i16, i16)
+	{
+		unsafe { (*crate::wasm4::MOUSE_X, *crate::wasm4::MOUSE_Y) }
+	}
+
+	fn mouse_buttons(&self) -> u8
+	{
+		unsafe { *crate::wasm4::MOUSE_BUTTONS }
+	}
+
+	fn tone(&mut self, frequency: u32, duration: u32, volume: u32, flags: u32)
+	{
+		crate::wasm4::tone(frequency, duration, volume, flags)
+	}
+
+	fn diskr(&mut self, dest: &mut [u8]) -> u32
+	{
+		unsafe { crate::wasm4::diskr(dest.as_mut_ptr(), dest.len() as u32) }
+	}
+
+	fn diskw(&mut self, src: &[u8]) -> u32
+	{
+		unsafe { crate::wasm4::diskw(src.as_ptr(), src.len() as u32) }
+	}
+
+	fn trace(&mut self, text: &str)
+	{
+		crate::wasm4::trace(text)
+	}
+}
+
+#[cfg(target_arch = "wasm32")]
+fn with_current<R>(f: impl FnOnce(&mut Wasm4) -> R) -> R
+{
+	f(&mut Wasm4)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+type ActivePlatform = *mut (dyn Platform + 'static);
+
+#[cfg(not(target_arch = "wasm32"))]
+thread_local! {
+	static ACTIVE: std::cell::Cell<Option<ActivePlatform>> =
+		std::cell::Cell::new(None);
+	static HEADLESS: std::cell::RefCell<crate::mock::Mock> =
+		std::cell::RefCell::new(crate::mock::Mock::new());
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn with_current<R>(f: impl FnOnce(&mut dyn Platform) -> R) -> R
+{
+	match ACTIVE.with(|active| active.get())
+	{
+		// Safety: the pointer was created from a mutable reference that
+		// outlives the call to run_with() during which it is active.
+		Some(platform) => f(unsafe { &mut *platform }),
+		None => HEADLESS.with(|mock| f(&mut *mock.borrow_mut())),
+	}
+}
+
+/// Makes `platform` the current platform of this thread while running `f`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn run_with<R>(platform: &mut dyn Platform, f: impl FnOnce() -> R) -> R
+{
+	struct Restore(Option<ActivePlatform>);
+
+	impl Drop for Restore
+	{
+		fn drop(&mut self)
+		{
+			ACTIVE.with(|active| active.set(self.0));
+		}
+	}
+
+	// Safety: the lifetime is erased, but the pointer is only used until
+	// the previous platform is restored, which happens before we return.
+	let platform: ActivePlatform = unsafe { std::mem::transmute(platform) };
+	let _restore =
+		Restore(ACTIVE.with(|active| active.replace(Some(platform))));
+	f()
+}
+
+/// Copies pixels to the framebuffer.
+pub fn blit(sprite: &[u8], x: i32, y: i32, width: u32, height: u32, flags: u32)
+{
+	with_current(|p| {
+		p.blit_sub(sprite, x, y, width, height, 0, 0, width, flags)
+	})
+}
+
+/// Copies a subregion within a larger sprite atlas to the framebuffer.
+#[allow(clippy::too_many_arguments)]
+pub fn blit_sub(
+	sprite: &[u8],
+	x: i32,
+	y: i32,
+	width: u32,
+	height: u32,
+	src_x: u32,
+	src_y: u32,
+	stride: u32,
+	flags: u32,
+)
+{
+	with_current(|p| {
+		p.blit_sub(sprite, x, y, width, height, src_x, src_y, stride, flags)
+	})
+}
+
+/// Draws a line between two points.
+pub fn line(x1: i32, y1: i32, x2: i32, y2: i32)
+{
+	with_current(|p| p.line(x1, y1, x2, y2))
+}
+
+/// Draws an oval (or circle).
+#[allow(dead_code)]
+pub fn oval(x: i32, y: i32, width: u32, height: u32)
+{
+	with_current(|p| p.oval(x, y, width, height))
+}
+
+/// Draws a rectangle.
+pub fn rect(x: i32, y: i32, width: u32, height: u32)
+{
+	with_current(|p| p.rect(x, y, width, height))
+}
+
+/// Draws text using the built-in system font.
+pub fn text<T: AsRef<str>>(text: T, x: i32, y: i32)
+{
+	with_current(|p| p.text(text.as_ref(), x, y))
+}
+
+/// Draws a horizontal line.
+pub fn hline(x: i32, y: i32, len: u32)
+{
+	with_current(|p| p.hline(x, y, len))
+}
+
+/// Draws a vertical line.
+#[allow(dead_code)]
+pub fn vline(x: i32, y: i32, len: u32)
+{
+	with_current(|p| p.vline(x, y, len))
+}
+
+pub fn set_draw_colors(draw_colors: u16)
+{
+	with_current(|p| p.set_draw_colors(draw_colors))
+}
+
+pub fn get_palette() -> [u32; 4]
+{
+	with_current(|p| p.palette())
+}
+
+pub fn set_palette(palette: [u32; 4])
+{
+	with_current(|p| p.set_palette(palette))
+}
+
+pub fn gamepad() -> u8
+{
+	with_current(|p| p.gamepad())
+}
+
+pub fn mouse_xy() -> (i16, i16)
+{
+	with_current(|p| p.mouse_xy())
+}
+
+pub fn mouse_buttons() -> u8
+{
+	with_current(|p| p.mouse_buttons())
+}
+
+/// Plays a sound tone.
+pub fn tone(frequency: u32, duration: u32, volume: u32, flags: u32)
+{
+	with_current(|p| p.tone(frequency, duration, volume, flags))
+}
+
+/// Reads up to `dest.len()` bytes from persistent storage.
+pub fn diskr(dest: &mut [u8]) -> u32
+{
+	with_current(|p| p.diskr(dest))
+}
+
+/// Writes up to `src.len()` bytes into persistent storage.
+pub fn diskw(src: &[u8]) -> u32
+{
+	with_current(|p| p.diskw(src))
+}
+
+/// Prints a message to the debug console.
+pub fn trace<T: AsRef<str>>(text: T)
+{
+	with_current(|p| p.trace(text.as_ref()))
+}
@@ -0,0 +1,583 @@
+//
+// Part of the-romans-are-coming
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+//! Recording and playback of the input of a single level. The recording is
+//! traced line by line as the level is played, so there is no need to keep
+//! it in memory on the cart; a playtester can copy it from the console.
+//!
+//!     replay <rng_seed> <mode> <preset> <scenario> <undo> <legal>
+//!     input <frame> <gamepad> <mouse_x> <mouse_y> <mouse_buttons>
+//!     end <frame>
+//!
+//! An input line is only traced on frames where the input changed. The
+//! settings that change how the game is played are traced as 0 or 1. A level
+//! that was resumed starts with its saved game instead, eight bytes a line:
+//!
+//!     resume <undo> <legal>
+//!     save <byte> <byte> <byte> <byte> <byte> <byte> <byte> <byte>
+
+use crate::platform::*;
+
+use crate::disk;
+use crate::map::Preset;
+use crate::scenario::Scenario;
+use crate::scores::Mode;
+use crate::settings::{Setting, Settings};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Input
+{
+	pub gamepad: u8,
+	pub mouse_x: i16,
+	pub mouse_y: i16,
+	pub mouse_buttons: u8,
+}
+
+impl Input
+{
+	fn read() -> Self
+	{
+		let (mouse_x, mouse_y) = mouse_xy();
+		Self {
+			gamepad: gamepad(),
+			mouse_x,
+			mouse_y,
+			mouse_buttons: mouse_buttons(),
+		}
+	}
+}
+
+pub struct Recorder
+{
+	is_recording: bool,
+	frame: u32,
+	previous: Option<Input>,
+}
+
+impl Recorder
+{
+	pub const fn new() -> Self
+	{
+		Self {
+			is_recording: false,
+			frame: 0,
+			previous: None,
+		}
+	}
+
+	pub fn start(
+		&mut self,
+		scenario: &Scenario,
+		rng_seed: u64,
+		mode: Mode,
+		preset: Preset,
+	)
+	{
+		let mut line = Line::new("replay");
+		line.push_number(rng_seed as i64);
+		line.push_number(mode as i64);
+		line.push_number(preset as i64);
+		line.push_number(scenario.id.into());
+		self.begin(line);
+	}
+
+	/// Starts recording a level that is resumed from the saved game.
+	pub fn resume(&mut self)
+	{
+		self.begin(Line::new("resume"));
+		let mut saved_game = [0u8; disk::SAVED_GAME.size];
+		disk::read(disk::SAVED_GAME, &mut saved_game);
+		for chunk in saved_game.chunks(8)
+		{
+			let mut line = Line::new("save");
+			for byte in chunk
+			{
+				line.push_number((*byte).into());
+			}
+			line.trace();
+		}
+	}
+
+	fn begin(&mut self, mut line: Line)
+	{
+		self.is_recording = true;
+		self.frame = 0;
+		self.previous = None;
+		let settings = Settings::load();
+		line.push_number(settings.get(Setting::Undo).into());
+		line.push_number(settings.get(Setting::Strict).into());
+		line.trace();
+	}
+
+	/// Should be called once per frame, right before the level updates.
+	pub fn record(&mut self)
+	{
+		if !self.is_recording
+		{
+			return;
+		}
+		let input = Input::read();
+		if self.previous != Some(input)
+		{
+			let mut line = Line::new("input");
+			line.push_number(self.frame.into());
+			line.push_number(input.gamepad.into());
+			line.push_number(input.mouse_x.into());
+			line.push_number(input.mouse_y.into());
+			line.push_number(input.mouse_buttons.into());
+			line.trace();
+			self.previous = Some(input);
+		}
+		self.frame += 1;
+	}
+
+	pub fn stop(&mut self)
+	{
+		if !self.is_recording
+		{
+			return;
+		}
+		let mut line = Line::new("end");
+		line.push_number(self.frame.into());
+		line.trace();
+		self.is_recording = false;
+	}
+}
+
+struct Line
+{
+	buffer: [u8; 64],
+	len: usize,
+}
+
+impl Line
+{
+	fn new(keyword: &str) -> Self
+	{
+		let mut line = Self {
+			buffer: [0u8; 64],
+			len: keyword.len(),
+		};
+		line.buffer[..keyword.len()].copy_from_slice(keyword.as_bytes());
+		line
+	}
+
+	fn push_number(&mut self, value: i64)
+	{
+		self.buffer[self.len] = b' ';
+		self.len += 1;
+		if value < 0
+		{
+			self.buffer[self.len] = b'-';
+			self.len += 1;
+		}
+		let mut digits = [0u8; 20];
+		let mut start = digits.len();
+		let mut value = value.unsigned_abs();
+		loop
+		{
+			start -= 1;
+			digits[start] = b'0' + (value % 10) as u8;
+			value /= 10;
+			if value == 0
+			{
+				break;
+			}
+		}
+		let digits = &digits[start..];
+		self.buffer[self.len..(self.len + digits.len())]
+			.copy_from_slice(digits);
+		self.len += digits.len();
+	}
+
+	fn trace(&self)
+	{
+		let txt =
+			unsafe { std::str::from_utf8_unchecked(&self.buffer[..self.len]) };
+		trace(txt);
+	}
+}
+
+/// A recording parsed back from the traced lines, to be played natively.
+#[cfg(test)]
+#[derive(Debug, Clone)]
+pub struct Replay
+{
+	pub start: Start,
+	pub is_undo_enabled: bool,
+	pub is_strict: bool,
+	pub inputs: Vec<(u32, Input)>,
+	pub num_frames: Option<u32>,
+}
+
+#[cfg(test)]
+#[derive(Debug, Clone)]
+pub enum Start
+{
+	New
+	{
+		scenario: &'static Scenario,
+		rng_seed: u64,
+		mode: Mode,
+		preset: Preset,
+	},
+	Resume
+	{
+		saved_game: Vec<u8>
+	},
+}
+
+/// How many frames to keep playing after the last input of a recording
+/// that was cut off before the level ended.
+#[cfg(test)]
+const NUM_TRAILING_FRAMES: u32 = 240;
+
+#[cfg(test)]
+impl Replay
+{
+	/// Reads the settings at the end of the first line of a recording.
+	fn new<'a>(
+		start: Start,
+		mut words: impl Iterator<Item = &'a str>,
+	) -> Option<Replay>
+	{
+		let is_undo_enabled = words.next()? == "1";
+		let is_strict = words.next()? == "1";
+		Some(Replay {
+			start,
+			is_undo_enabled,
+			is_strict,
+			inputs: Vec::new(),
+			num_frames: None,
+		})
+	}
+
+	/// Parses the first recording in `text`, ignoring any unrelated lines.
+	pub fn parse(text: &str) -> Option<Replay>
+	{
+		let mut replay: Option<Replay> = None;
+		for line in text.lines()
+		{
+			let mut words = line.split_whitespace();
+			match (words.next(), replay.as_mut())
+			{
+				(Some("replay"), None) =>
+				{
+					let rng_seed = words.next()?.parse().ok()?;
+					let mode = Mode::from_u8(words.next()?.parse().ok()?)?;
+					let preset = Preset::from_u8(words.next()?.parse().ok()?)?;
+					let id = words.next()?.parse().ok()?;
+					let start = Start::New {
+						scenario: Scenario::from_id(id)?,
+						rng_seed,
+						mode,
+						preset,
+					};
+					replay = Some(Replay::new(start, words)?);
+				}
+				(Some("resume"), None) =>
+				{
+					let start = Start::Resume {
+						saved_game: Vec::new(),
+					};
+					replay = Some(Replay::new(start, words)?);
+				}
+				(Some("save"), Some(replay)) => match &mut replay.start
+				{
+					Start::Resume { saved_game } =>
+					{
+						for word in words
+						{
+							saved_game.push(word.parse().ok()?);
+						}
+					}
+					Start::New { .. } => return None,
+				},
+				(Some("input"), Some(replay)) =>
+				{
+					let frame = words.next()?.parse().ok()?;
+					let input = Input {
+						gamepad: words.next()?.parse().ok()?,
+						mouse_x: words.next()?.parse().ok()?,
+						mouse_y: words.next()?.parse().ok()?,
+						mouse_buttons: words.next()?.parse().ok()?,
+					};
+					replay.inputs.push((frame, input));
+				}
+				(Some("end"), Some(replay)) =>
+				{
+					replay.num_frames = Some(words.next()?.parse().ok()?);
+					break;
+				}
+				_ => (),
+			}
+		}
+		replay
+	}
+
+	/// Plays the recorded input into a fresh level, frame by frame, until
+	/// the level ends or the recording runs out.
+	pub fn play(&self) -> crate::level::Level
+	{
+		use crate::level::Level;
+		use crate::mock::Mock;
+
+		let mut mock = Mock::new();
+		let mut level = run_with(&mut mock, || {
+			let mut settings = Settings::load();
+			for (setting, value) in [
+				(Setting::Undo, self.is_undo_enabled),
+				(Setting::Strict, self.is_strict),
+			]
+			{
+				if settings.get(setting) != value
+				{
+					settings.toggle(setting);
+				}
+			}
+			match &self.start
+			{
+				Start::New {
+					scenario,
+					rng_seed,
+					mode,
+					preset,
+				} => Level::new(scenario, *rng_seed, *mode, *preset),
+				Start::Resume { saved_game } =>
+				{
+					disk::write(disk::SAVED_GAME, saved_game);
+					Level::resume().expect("saved game")
+				}
+			}
+		});
+		let num_frames = self.num_frames.unwrap_or_else(|| {
+			let last = self.inputs.last().map(|(frame, _)| *frame);
+			last.unwrap_or(0) + NUM_TRAILING_FRAMES
+		});
+		let mut inputs = self.inputs.iter().peekable();
+		for frame in 0..num_frames
+		{
+			while let Some((_, input)) =
+				inputs.next_if(|(start, _input)| *start <= frame)
+			{
+				mock.gamepad = input.gamepad;
+				mock.mouse_x = input.mouse_x;
+				mock.mouse_y = input.mouse_y;
+				mock.mouse_buttons = input.mouse_buttons;
+			}
+			if run_with(&mut mock, || level.update()).is_some()
+			{
+				break;
+			}
+		}
+		level
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::level::{Level, MAP_LOCK};
+	use crate::mock::Mock;
+	use crate::rules::State;
+	use crate::scenario::GENERATED;
+
+	/// A player that wanders around with the gamepad and presses the
+	/// button every so often, or does the same with the mouse.
+	fn scripted_input(frame: u32, use_mouse: bool) -> Input
+	{
+		let step = frame / 4;
+		let mut x = step.wrapping_mul(0x9e3779b9);
+		x ^= x >> 15;
+		x = x.wrapping_mul(0x85ebca6b);
+		x ^= x >> 13;
+		let is_pressed = frame % 4 < 2;
+		if use_mouse
+		{
+			Input {
+				gamepad: 0,
+				mouse_x: (4 + x % 152) as i16,
+				mouse_y: (4 + (x >> 8) % 152) as i16,
+				mouse_buttons: if is_pressed && x.is_multiple_of(3)
+				{
+					MOUSE_LEFT
+				}
+				else
+				{
+					0
+				},
+			}
+		}
+		else
+		{
+			let buttons =
+				[BUTTON_1, BUTTON_LEFT, BUTTON_RIGHT, BUTTON_UP, BUTTON_DOWN];
+			Input {
+				gamepad: if is_pressed
+				{
+					buttons[(x % 5) as usize]
+				}
+				else
+				{
+					0
+				},
+				mouse_x: 0,
+				mouse_y: 0,
+				mouse_buttons: 0,
+			}
+		}
+	}
+
+	/// Plays a level the way the cart does, recording it along the way.
+	fn record(
+		mut mock: Mock,
+		rng_seed: u64,
+		use_mouse: bool,
+	) -> (Level, Vec<String>)
+	{
+		let mut recorder = Recorder::new();
+		let mut level = run_with(&mut mock, || {
+			let scenario = &GENERATED;
+			recorder.start(scenario, rng_seed, Mode::Freeplay, Preset::Classic);
+			Level::new(scenario, rng_seed, Mode::Freeplay, Preset::Classic)
+		});
+		play(&mut mock, &mut recorder, &mut level, 0..100_000, use_mouse);
+		(level, mock.traces)
+	}
+
+	/// Feeds scripted input to the level until it ends, recording it if the
+	/// recorder was started.
+	fn play(
+		mock: &mut Mock,
+		recorder: &mut Recorder,
+		level: &mut Level,
+		frames: std::ops::Range<u32>,
+		use_mouse: bool,
+	)
+	{
+		for frame in frames
+		{
+			let input = scripted_input(frame, use_mouse);
+			mock.gamepad = input.gamepad;
+			mock.mouse_x = input.mouse_x;
+			mock.mouse_y = input.mouse_y;
+			mock.mouse_buttons = input.mouse_buttons;
+			let transition = run_with(mock, || {
+				recorder.record();
+				let transition = level.update();
+				if transition.is_some()
+				{
+					recorder.stop();
+				}
+				transition
+			});
+			if transition.is_some()
+			{
+				break;
+			}
+		}
+	}
+
+	fn assert_same_ending(replayed: &Level, level: &Level)
+	{
+		assert!(level.rules().is_over());
+		assert_eq!(replayed.rules().state(), level.rules().state());
+		assert_eq!(replayed.rules().score(), level.rules().score());
+		assert_eq!(
+			replayed.rules().years_survived(),
+			level.rules().years_survived()
+		);
+	}
+
+	#[test]
+	fn record_and_replay()
+	{
+		let _lock = MAP_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+		for use_mouse in [false, true]
+		{
+			let (level, traces) = record(Mock::new(), 4, use_mouse);
+			assert_eq!(level.rules().state(), State::GameOver);
+			let replay = Replay::parse(&traces.join("\n")).unwrap();
+			assert_same_ending(&replay.play(), &level);
+		}
+	}
+
+	#[test]
+	fn replay_with_the_recorded_settings()
+	{
+		let _lock = MAP_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+		let mut mock = Mock::new();
+		run_with(&mut mock, || {
+			let mut settings = Settings::load();
+			settings.toggle(Setting::Undo);
+			settings.toggle(Setting::Strict);
+		});
+		let (level, traces) = record(mock, 4, true);
+		let replay = Replay::parse(&traces.join("\n")).unwrap();
+		assert!(!replay.is_undo_enabled);
+		assert!(replay.is_strict);
+		assert_same_ending(&replay.play(), &level);
+	}
+
+	#[test]
+	fn record_and_replay_resumed_game()
+	{
+		let _lock = MAP_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+		let mut mock = Mock::new();
+		let mut recorder = Recorder::new();
+		let mut level = run_with(&mut mock, || {
+			Level::new(&GENERATED, 4, Mode::Freeplay, Preset::Classic)
+		});
+		play(&mut mock, &mut recorder, &mut level, 0..2000, false);
+		assert!(!level.rules().is_over());
+		assert!(mock.traces.is_empty());
+
+		let mut level = run_with(&mut mock, || {
+			recorder.resume();
+			Level::resume().unwrap()
+		});
+		play(&mut mock, &mut recorder, &mut level, 2000..100_000, false);
+		let replay = Replay::parse(&mock.traces.join("\n")).unwrap();
+		assert!(matches!(replay.start, Start::Resume { .. }));
+		assert_same_ending(&replay.play(), &level);
+	}
+
+	#[test]
+	fn recorded_games()
+	{
+		let _lock = MAP_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+		for (recording, score) in [
+			(include_str!("../tests/replays/gamepad.txt"), 28),
+			(include_str!("../tests/replays/mouse.txt"), 27),
+		]
+		{
+			let level = Replay::parse(recording).unwrap().play();
+			assert_eq!(level.rules().state(), State::GameOver);
+			assert_eq!(level.rules().score(), score);
+		}
+	}
+
+	/// Plays back a recording copied from the console of a playtester, e.g.
+	/// `REPLAY=bug.txt cargo test replay_from_env -- --nocapture`.
+	#[test]
+	fn replay_from_env()
+	{
+		let Ok(path) = std::env::var("REPLAY")
+		else
+		{
+			return;
+		};
+		let _lock = MAP_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+		let text = std::fs::read_to_string(path).unwrap();
+		let level = Replay::parse(&text).unwrap().play();
+		println!(
+			"state = {:?}, score = {}",
+			level.rules().state(),
+			level.rules().score()
+		);
+	}
+}
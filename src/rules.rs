@@ -0,0 +1,2502 @@
+//
+// Part of the-romans-are-coming
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+use crate::decree::{AllOrNone, Quantifier, Relation, Target};
+use crate::decree::{Decree, Issue, DECREES};
+use crate::decree::{DECREE_DECK, DECREE_DECK_SIZE};
+
+use crate::disk::{Reader, Writer};
+
+use crate::scenario::{Goal, Scenario};
+use crate::tutorial::{Effect, Step, Trigger};
+
+use bitmaps::Bitmap;
+
+pub const MAX_NUM_REGIONS: usize = 35;
+pub const MAX_NUM_CARDS: usize = 20;
+pub const MAX_NUM_DECREES: usize = 8;
+pub const MAX_NUM_EVENTS: usize = 2 * MAX_NUM_REGIONS + 8;
+
+pub const MAX_THREAT_LEVEL: u8 = 10;
+pub const MAX_NUM_UNDOS: usize = 5;
+
+/// How many bytes `Rules::save` writes on a map with the most regions.
+pub const MAX_SAVED_SIZE: usize = 8
+	+ (1 + MAX_NUM_REGIONS)
+	+ (1 + 1 + 4 + 8)
+	+ (1 + 2 * MAX_NUM_DECREES + DECREE_DECK_SIZE)
+	+ (6 + 2 + 3 + 2)
+	+ (1 + 1);
+
+const MAX_TRIBUTE: u8 = 8;
+
+const VILLAGE_WOOD_COST: u8 = 10;
+const VILLAGE_GOLD_COST: u8 = 5;
+const MAX_STORED_GRAIN: u8 = 20;
+const MAX_STORED_WOOD: u8 = 20;
+const MAX_STORED_WINE: u8 = 50;
+const MAX_STORED_GOLD: u8 = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TerrainType
+{
+	Village,
+	Grass,
+	Forest,
+	Hill,
+	Mountain,
+	Water,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Marker
+{
+	Worker,
+	DeadWorker,
+	Roman,
+	DeadRoman,
+	Occupied,
+	FogOfWar,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Card
+{
+	Worker,
+	Roman,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Region
+{
+	terrain_type: TerrainType,
+	marker: Option<Marker>,
+}
+
+const EMPTY_REGION: Region = Region {
+	terrain_type: TerrainType::Water,
+	marker: None,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State
+{
+	Setup,
+	NewObjectives,
+	NewDecrees,
+	Placement,
+	Shuffling,
+	Resolution,
+	Occupation,
+	Cleanup,
+	DecreeViolated
+	{
+		decree_offset: u8,
+	},
+	TributePaid,
+	TributeFailed,
+	TributeSkipped,
+	NoLegalPlacement,
+	GameOver,
+	Victory,
+}
+
+/// Something the player (or the passing of time) does to the game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action
+{
+	/// Place the current card on the given region.
+	Place
+	{
+		region_id: i8
+	},
+	/// Dismiss the popup or objectives that are being shown.
+	Acknowledge,
+	/// Let the automatic part of the current state take its next step.
+	Proceed,
+	/// Take back the most recent placement, if it is not too late.
+	Undo,
+}
+
+/// A change to the board that a frontend might want to show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event
+{
+	MarkerChanged
+	{
+		region_id: i8,
+		marker: Option<Marker>,
+	},
+	VillageBuilt
+	{
+		region_id: i8
+	},
+	RegionOccupied
+	{
+		region_id: i8
+	},
+	DayStarted
+	{
+		day: u8
+	},
+}
+
+pub struct Events
+{
+	buffer: [Event; MAX_NUM_EVENTS],
+	num_events: usize,
+}
+
+impl Events
+{
+	fn new() -> Self
+	{
+		Self {
+			buffer: [Event::RegionOccupied { region_id: -1 }; MAX_NUM_EVENTS],
+			num_events: 0,
+		}
+	}
+
+	fn push(&mut self, event: Event)
+	{
+		if self.num_events < MAX_NUM_EVENTS
+		{
+			self.buffer[self.num_events] = event;
+			self.num_events += 1;
+		}
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = &Event>
+	{
+		self.buffer.iter().take(self.num_events)
+	}
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Combat
+{
+	pub kill: Bitmap<MAX_NUM_REGIONS>,
+	pub attack: Bitmap<MAX_NUM_REGIONS>,
+	pub support: Bitmap<MAX_NUM_REGIONS>,
+	pub gather: Bitmap<MAX_NUM_REGIONS>,
+}
+
+/// Everything that placing a card can change, except for the terrain:
+/// building a village cannot be taken back.
+#[derive(Debug, Clone, Copy)]
+struct Snapshot
+{
+	markers: [Option<Marker>; MAX_NUM_REGIONS],
+	placed_today: Bitmap<MAX_NUM_REGIONS>,
+	num_cards: u8,
+	card_offset: u8,
+	threat_level: u8,
+	grain: u8,
+	wood: u8,
+	wine: u8,
+	gold: u8,
+	score: u16,
+	state: State,
+	tutorial: Option<u8>,
+}
+
+#[derive(Clone)]
+pub struct Rules
+{
+	region_data: [Region; MAX_NUM_REGIONS],
+	adjacency: [Bitmap<MAX_NUM_REGIONS>; MAX_NUM_REGIONS],
+	border_adjacency: Bitmap<MAX_NUM_REGIONS>,
+	/// The regions where a card was placed since the last shuffle.
+	placed_today: Bitmap<MAX_NUM_REGIONS>,
+	card_deck: [Card; MAX_NUM_CARDS],
+	decree_data: [Decree; MAX_NUM_DECREES],
+	decree_deck: [u8; DECREE_DECK_SIZE],
+	num_regions: u8,
+	num_cards: u8,
+	num_decrees: u8,
+	fixed_decrees: &'static [Decree],
+	card_offset: u8,
+	threat_level: u8,
+	tribute: u8,
+	grain: u8,
+	wood: u8,
+	wine: u8,
+	gold: u8,
+	score: u16,
+	years_survived: u8,
+	villages_built: u8,
+	romans_defeated: u8,
+	/// The current year, counting from 1.
+	year: u8,
+	/// The current day of the year, counting from 1 once cards are dealt.
+	day: u8,
+	goal: Option<Goal>,
+	state: State,
+	script: &'static [Step],
+	/// The step of the script that the player is at, if any.
+	tutorial: Option<u8>,
+	is_strict: bool,
+	undo_history: [Option<Snapshot>; MAX_NUM_UNDOS],
+	rng: fastrand::Rng,
+}
+
+impl Rules
+{
+	pub fn new(
+		scenario: &Scenario,
+		regions: impl Iterator<Item = (i8, TerrainType, Option<Marker>)>,
+		adjacency: [Bitmap<MAX_NUM_REGIONS>; MAX_NUM_REGIONS],
+		border_adjacency: Bitmap<MAX_NUM_REGIONS>,
+		rng: fastrand::Rng,
+	) -> Rules
+	{
+		let decree_data = [Decree::Dummy; MAX_NUM_DECREES];
+		let mut num_regions = 0;
+		let mut region_data = [EMPTY_REGION; MAX_NUM_REGIONS];
+		for (id, terrain_type, marker) in regions
+		{
+			region_data[id as usize] = Region {
+				terrain_type,
+				marker,
+			};
+			num_regions += 1;
+		}
+		for &(region_id, marker) in scenario.markers
+		{
+			region_data[region_id as usize].marker = Some(marker);
+		}
+		let roman_spawn = if scenario.has_roman_spawn
+		{
+			(0..(num_regions as usize))
+				.rev()
+				.filter(|i| border_adjacency.get(*i))
+				.find(|i| match region_data[*i].terrain_type
+				{
+					TerrainType::Water => false,
+					TerrainType::Mountain => false,
+					_ => true,
+				})
+		}
+		else
+		{
+			None
+		};
+		if let Some(i) = roman_spawn
+		{
+			region_data[i].marker = Some(Marker::Occupied);
+		}
+		Rules {
+			num_regions,
+			region_data,
+			adjacency,
+			border_adjacency,
+			placed_today: Bitmap::new(),
+			num_cards: 0,
+			card_offset: 0,
+			card_deck: [Card::Worker; MAX_NUM_CARDS],
+			decree_data,
+			decree_deck: DECREE_DECK,
+			num_decrees: 1,
+			fixed_decrees: scenario.decrees,
+			threat_level: scenario.threat_level,
+			tribute: scenario.tribute,
+			grain: scenario.grain,
+			wood: scenario.wood,
+			wine: 0,
+			gold: scenario.gold,
+			score: 0,
+			years_survived: 0,
+			villages_built: 0,
+			romans_defeated: 0,
+			year: 1,
+			day: 0,
+			goal: scenario.goal,
+			state: State::Setup,
+			script: scenario.tutorial,
+			tutorial: if scenario.tutorial.is_empty()
+			{
+				None
+			}
+			else
+			{
+				Some(0)
+			},
+			is_strict: false,
+			undo_history: [None; MAX_NUM_UNDOS],
+			rng,
+		}
+	}
+
+	/// In strict mode, Romans cannot be placed where they would violate a
+	/// decree, and are sent away if there is no such place.
+	pub fn set_strict(&mut self, is_strict: bool)
+	{
+		self.is_strict = is_strict;
+	}
+
+	pub fn state(&self) -> State
+	{
+		self.state
+	}
+
+	/// Whether the game has ended, either in defeat or in victory.
+	pub fn is_over(&self) -> bool
+	{
+		matches!(self.state, State::GameOver | State::Victory)
+	}
+
+	pub fn tutorial(&self) -> Option<&'static Step>
+	{
+		self.script.get(self.tutorial? as usize)
+	}
+
+	/// The regions where the tutorial wants the current card to go, unless
+	/// one of them has already been filled.
+	pub fn tutorial_targets(&self) -> Bitmap<MAX_NUM_REGIONS>
+	{
+		match self.tutorial().and_then(|step| step.expects)
+		{
+			Some((card, mask)) if self.active_card() == Some(card) =>
+			{
+				let targets = Bitmap::from_value(mask);
+				for i in targets.into_iter()
+				{
+					if self.region_data[i].marker.is_some()
+					{
+						return Bitmap::new();
+					}
+				}
+				targets
+			}
+			_ => Bitmap::new(),
+		}
+	}
+
+	pub fn num_regions(&self) -> u8
+	{
+		self.num_regions
+	}
+
+	pub fn terrain_type(&self, region_id: i8) -> TerrainType
+	{
+		self.region_data[region_id as usize].terrain_type
+	}
+
+	pub fn marker(&self, region_id: i8) -> Option<Marker>
+	{
+		self.region_data[region_id as usize].marker
+	}
+
+	pub fn active_card(&self) -> Option<Card>
+	{
+		if self.card_offset < self.num_cards
+		{
+			Some(self.card_deck[self.card_offset as usize])
+		}
+		else
+		{
+			None
+		}
+	}
+
+	pub fn num_cards(&self) -> u8
+	{
+		self.num_cards
+	}
+
+	pub fn remaining_cards(&self) -> &[Card]
+	{
+		self.card_deck
+			.get((self.card_offset as usize)..(self.num_cards as usize))
+			.unwrap_or(&[])
+	}
+
+	pub fn decrees(&self) -> &[Decree]
+	{
+		self.decree_data
+			.get(..(self.num_decrees as usize))
+			.unwrap_or(&[])
+	}
+
+	pub fn decree(&self, decree_offset: u8) -> &Decree
+	{
+		&self.decree_data[decree_offset as usize]
+	}
+
+	pub fn threat_level(&self) -> u8
+	{
+		self.threat_level
+	}
+
+	pub fn tribute(&self) -> u8
+	{
+		self.tribute
+	}
+
+	pub fn grain(&self) -> u8
+	{
+		self.grain
+	}
+
+	pub fn wood(&self) -> u8
+	{
+		self.wood
+	}
+
+	pub fn wine(&self) -> u8
+	{
+		self.wine
+	}
+
+	pub fn gold(&self) -> u8
+	{
+		self.gold
+	}
+
+	pub fn score(&self) -> u16
+	{
+		self.score
+	}
+
+	pub fn years_survived(&self) -> u8
+	{
+		self.years_survived
+	}
+
+	pub fn villages_built(&self) -> u8
+	{
+		self.villages_built
+	}
+
+	pub fn romans_defeated(&self) -> u8
+	{
+		self.romans_defeated
+	}
+
+	pub fn year(&self) -> u8
+	{
+		self.year
+	}
+
+	pub fn day(&self) -> u8
+	{
+		self.day
+	}
+
+	/// Whether the current card can be placed on this region at all,
+	/// regardless of what the decrees say about it.
+	pub fn can_place(&self, region_id: i8) -> bool
+	{
+		let region = self.region_data[region_id as usize];
+		if region.marker.is_some()
+		{
+			return false;
+		}
+		match region.terrain_type
+		{
+			TerrainType::Village => true,
+			TerrainType::Grass => true,
+			TerrainType::Forest => true,
+			TerrainType::Hill => true,
+			TerrainType::Mountain => true,
+			_ => false,
+		}
+	}
+
+	/// Whether the current card can be placed on this region, taking strict
+	/// mode and the tutorial into account.
+	pub fn is_legal(&self, region_id: i8) -> bool
+	{
+		let targets = self.tutorial_targets();
+		self.can_place(region_id)
+			&& (targets.is_empty() || targets.get(region_id as usize))
+			&& !(self.is_strict
+				&& self.active_card() == Some(Card::Roman)
+				&& self.preview_violation(region_id).is_some())
+	}
+
+	/// Writes everything that can change during play, so that a game can be
+	/// resumed with `load()` on the Rules of a newly generated map.
+	pub fn save(&self, writer: &mut Writer)
+	{
+		writer.write_u64(self.rng.get_seed());
+		writer.write_u8(self.num_regions);
+		for region in &self.region_data[0..(self.num_regions as usize)]
+		{
+			let marker = match region.marker
+			{
+				Some(marker) => marker as u8 + 1,
+				None => 0,
+			};
+			writer.write_u8(((region.terrain_type as u8) << 4) | marker);
+		}
+		writer.write_u8(self.num_cards);
+		writer.write_u8(self.card_offset);
+		let mut roman_cards = 0u32;
+		for (i, card) in self.card_deck.iter().enumerate()
+		{
+			if *card == Card::Roman
+			{
+				roman_cards |= 1 << i;
+			}
+		}
+		writer.write_u32(roman_cards);
+		writer.write_u64(self.placed_today.into_value());
+		writer.write_u8(self.num_decrees);
+		for decree in self.decree_data
+		{
+			writer.write_u16(encode_decree(decree));
+		}
+		for i in self.decree_deck
+		{
+			writer.write_u8(i);
+		}
+		writer.write_u8(self.threat_level);
+		writer.write_u8(self.tribute);
+		writer.write_u8(self.grain);
+		writer.write_u8(self.wood);
+		writer.write_u8(self.wine);
+		writer.write_u8(self.gold);
+		writer.write_u16(self.score);
+		writer.write_u8(self.years_survived);
+		writer.write_u8(self.villages_built);
+		writer.write_u8(self.romans_defeated);
+		writer.write_u8(self.year);
+		writer.write_u8(self.day);
+		let (state, decree_offset) = encode_state(self.state);
+		writer.write_u8((state << 4) | decree_offset);
+		let tutorial = match self.tutorial
+		{
+			Some(step) => step + 1,
+			None => 0,
+		};
+		writer.write_u8(((self.is_strict as u8) << 7) | tutorial);
+	}
+
+	/// Restores what was written by `save()`. Gives None if the data is
+	/// corrupt or does not belong to this map, in which case the rules
+	/// should no longer be used.
+	pub fn load(&mut self, reader: &mut Reader) -> Option<()>
+	{
+		self.rng = fastrand::Rng::with_seed(reader.read_u64()?);
+		if reader.read_u8()? != self.num_regions
+		{
+			return None;
+		}
+		for region in &mut self.region_data[0..(self.num_regions as usize)]
+		{
+			let x = reader.read_u8()?;
+			region.terrain_type = *TERRAIN_TYPES.get((x >> 4) as usize)?;
+			region.marker = match x & 0x0F
+			{
+				0 => None,
+				m => Some(*MARKERS.get((m - 1) as usize)?),
+			};
+		}
+		self.num_cards = reader.read_u8()?;
+		self.card_offset = reader.read_u8()?;
+		if self.num_cards as usize > MAX_NUM_CARDS
+			|| self.card_offset > self.num_cards
+		{
+			return None;
+		}
+		let roman_cards = reader.read_u32()?;
+		self.placed_today = Bitmap::from_value(reader.read_u64()?);
+		for (i, card) in self.card_deck.iter_mut().enumerate()
+		{
+			*card = if roman_cards & (1 << i) != 0
+			{
+				Card::Roman
+			}
+			else
+			{
+				Card::Worker
+			};
+		}
+		self.num_decrees = reader.read_u8()?;
+		if self.num_decrees as usize > MAX_NUM_DECREES
+		{
+			return None;
+		}
+		for decree in self.decree_data.iter_mut()
+		{
+			*decree = decode_decree(reader.read_u16()?)?;
+		}
+		for i in self.decree_deck.iter_mut()
+		{
+			*i = reader.read_u8()?;
+			if *i as usize >= DECREES.len()
+			{
+				return None;
+			}
+		}
+		self.threat_level = reader.read_u8()?;
+		self.tribute = reader.read_u8()?;
+		self.grain = reader.read_u8()?;
+		self.wood = reader.read_u8()?;
+		self.wine = reader.read_u8()?;
+		self.gold = reader.read_u8()?;
+		self.score = reader.read_u16()?;
+		self.years_survived = reader.read_u8()?;
+		self.villages_built = reader.read_u8()?;
+		self.romans_defeated = reader.read_u8()?;
+		self.year = reader.read_u8()?;
+		self.day = reader.read_u8()?;
+		let x = reader.read_u8()?;
+		self.state = decode_state(x >> 4, x & 0x0F)?;
+		let x = reader.read_u8()?;
+		self.is_strict = x & 0x80 != 0;
+		self.tutorial = match x & 0x7F
+		{
+			0 => None,
+			t if t as usize <= self.script.len() => Some(t - 1),
+			_ => return None,
+		};
+		Some(())
+	}
+
+	pub fn apply(&mut self, action: Action) -> Events
+	{
+		let mut events = Events::new();
+		match action
+		{
+			Action::Place { region_id } =>
+			{
+				if self.state == State::Placement
+					&& region_id >= 0
+					&& region_id < self.num_regions as i8
+					&& self.is_legal(region_id)
+				{
+					if let Some(card) = self.active_card()
+					{
+						self.place_marker(region_id, card, &mut events);
+					}
+				}
+			}
+			Action::Acknowledge => self.acknowledge(&mut events),
+			Action::Proceed => self.proceed(&mut events),
+			Action::Undo => self.undo(&mut events),
+		}
+		if self.num_decrees == 0
+		{
+			self.renew_decrees(&mut events);
+		}
+		if self.state == State::Placement
+			&& self.is_strict
+			&& self.active_card() == Some(Card::Roman)
+			&& !(0..(self.num_regions as i8)).any(|i| self.is_legal(i))
+		{
+			self.state = State::NoLegalPlacement;
+		}
+		if !self.can_undo()
+		{
+			self.undo_history = [None; MAX_NUM_UNDOS];
+		}
+		events
+	}
+
+	fn renew_decrees(&mut self, events: &mut Events)
+	{
+		self.num_cards = 0;
+		self.pick_decrees();
+
+		if self.decree_data[0] == Decree::Dummy
+		{
+			self.state = State::Shuffling;
+		}
+		else if self.threat_level < MAX_THREAT_LEVEL
+		{
+			self.state = State::NewDecrees;
+		}
+		else
+		{
+			for i in 0..self.num_regions
+			{
+				events.push(Event::RegionOccupied { region_id: i as i8 });
+			}
+			self.state = State::Occupation;
+			self.start_occupation(events);
+		}
+	}
+
+	fn acknowledge(&mut self, events: &mut Events)
+	{
+		match self.state
+		{
+			State::NewObjectives =>
+			{
+				let trigger = Trigger::Acknowledged(State::NewObjectives);
+				if !self.advance_tutorial(trigger, events)
+				{
+					self.num_decrees = 0;
+					self.state = State::Shuffling;
+				}
+			}
+			State::NewDecrees =>
+			{
+				let trigger = Trigger::Acknowledged(State::NewDecrees);
+				if !self.advance_tutorial(trigger, events)
+				{
+					self.state = State::Shuffling;
+				}
+			}
+			State::TributePaid | State::TributeSkipped =>
+			{
+				self.start_new_year(events);
+			}
+			State::DecreeViolated { .. } =>
+			{
+				self.num_decrees = 0;
+			}
+			State::NoLegalPlacement =>
+			{
+				self.card_offset += 1;
+				self.state = State::Placement;
+			}
+			State::TributeFailed =>
+			{
+				let trigger = Trigger::Acknowledged(State::TributeFailed);
+				if !self.advance_tutorial(trigger, events)
+				{
+					self.start_new_year(events);
+				}
+			}
+			_ => (),
+		}
+	}
+
+	fn proceed(&mut self, events: &mut Events)
+	{
+		if self.active_card().is_some()
+		{
+			return;
+		}
+		match self.state
+		{
+			State::Setup =>
+			{
+				self.pick_decrees();
+				if self.advance_tutorial(Trigger::Start, events)
+				{
+					// The tutorial shows what it is about.
+				}
+				else if self.tutorial.is_some()
+				{
+					self.state = State::NewObjectives;
+				}
+				else
+				{
+					self.state = State::Shuffling;
+				}
+			}
+			State::Placement =>
+			{
+				if let Some(offset) = self.find_unmet_decree()
+				{
+					self.violate_decree(offset);
+				}
+				else if self.count_remaining_spaces() == 0
+				{
+					if !self.advance_tutorial(Trigger::MapFilled, events)
+					{
+						self.state = State::Resolution;
+					}
+				}
+				else if !self.advance_tutorial(Trigger::DayEnded, events)
+				{
+					self.state = State::Shuffling;
+				}
+			}
+			State::Shuffling =>
+			{
+				// The decrees stay until one is broken, but the map changes
+				// in the meantime, so they might no longer go together.
+				if self.tutorial.is_none() && !self.is_satisfiable()
+				{
+					let issued = (self.decree_data, self.num_decrees);
+					let (deck, seed) = (self.decree_deck, self.rng.get_seed());
+					self.pick_decrees();
+					if self.is_satisfiable() || self.num_decrees < issued.1
+					{
+						self.state = State::NewDecrees;
+						return;
+					}
+					// Redrawing did not help, so keep the decrees that the
+					// player has already seen, as if nothing was drawn.
+					(self.decree_data, self.num_decrees) = issued;
+					self.decree_deck = deck;
+					self.rng.seed(seed);
+				}
+				self.shuffle();
+				events.push(Event::DayStarted { day: self.day });
+				self.state = State::Placement;
+			}
+			State::Resolution =>
+			{
+				let survivor = (0..(self.num_regions as usize)).find(|i| {
+					self.region_data[*i].marker == Some(Marker::Worker)
+				});
+				if let Some(i) = survivor
+				{
+					match self.region_data[i].terrain_type
+					{
+						TerrainType::Village => self.grain += 1,
+						TerrainType::Grass => self.grain += 1,
+						TerrainType::Forest => self.wood += 1,
+						TerrainType::Hill => self.wine += 1,
+						TerrainType::Mountain => self.gold += 1,
+						TerrainType::Water => (),
+					}
+					self.set_marker(i, None, events);
+				}
+				else
+				{
+					self.state = State::Occupation;
+					self.start_occupation(events);
+				}
+			}
+			State::Occupation =>
+			{
+				if self.threat_level < MAX_THREAT_LEVEL
+				{
+					self.state = State::Cleanup;
+				}
+				else
+				{
+					self.state = State::GameOver;
+				}
+			}
+			State::Cleanup => self.clean_up(events),
+			State::TributePaid | State::TributeSkipped =>
+			{
+				self.start_new_year(events);
+			}
+			State::NewObjectives
+			| State::NewDecrees
+			| State::DecreeViolated { .. }
+			| State::NoLegalPlacement
+			| State::TributeFailed
+			| State::GameOver
+			| State::Victory =>
+			{
+				// Wait for user to finish reading.
+			}
+		}
+	}
+
+	fn start_occupation(&mut self, events: &mut Events)
+	{
+		let mut any = false;
+		for i in 0..(self.num_regions as usize)
+		{
+			if self.region_data[i].marker == Some(Marker::Roman)
+			{
+				events.push(Event::RegionOccupied { region_id: i as i8 });
+				self.set_marker(i, Some(Marker::Occupied), events);
+				any = true;
+			}
+		}
+		if !any
+		{
+			// Find a battlefield (empty squares are either
+			// water or had workers on them) on the border.
+			let roman_spawn = (0..(self.num_regions as usize))
+				.rev()
+				.filter(|i| self.border_adjacency.get(*i))
+				.find(|i| match self.region_data[*i].marker
+				{
+					Some(Marker::DeadRoman) => true,
+					Some(Marker::DeadWorker) => true,
+					_ => false,
+				});
+			if let Some(i) = roman_spawn
+			{
+				events.push(Event::RegionOccupied { region_id: i as i8 });
+				self.set_marker(i, Some(Marker::Occupied), events);
+			}
+		}
+	}
+
+	fn clean_up(&mut self, events: &mut Events)
+	{
+		let trash = (0..(self.num_regions as usize)).find(|i| {
+			match self.region_data[*i].marker
+			{
+				Some(Marker::Occupied) => false,
+				Some(Marker::FogOfWar) => false,
+				Some(Marker::Roman) => true,
+				Some(_) => true,
+				None => false,
+			}
+		});
+		if let Some(i) = trash
+		{
+			if self.region_data[i].marker == Some(Marker::DeadRoman)
+			{
+				self.romans_defeated = self.romans_defeated.saturating_add(1);
+			}
+			self.set_marker(i, None, events);
+			return;
+		}
+
+		if !self.advance_tutorial(Trigger::YearEnded, events)
+		{
+			self.years_survived = self.years_survived.saturating_add(1);
+			self.collect_tribute(events);
+		}
+		if self.grain > MAX_STORED_GRAIN
+		{
+			self.grain = MAX_STORED_GRAIN;
+		}
+		if self.wood > MAX_STORED_WOOD
+		{
+			self.wood = MAX_STORED_WOOD;
+		}
+		if self.wine > MAX_STORED_WINE
+		{
+			self.wine = MAX_STORED_WINE;
+		}
+		if self.gold > MAX_STORED_GOLD
+		{
+			self.gold = MAX_STORED_GOLD;
+		}
+		if self.goal.is_some_and(|goal| goal.is_met(self))
+		{
+			self.state = State::Victory;
+		}
+	}
+
+	fn collect_tribute(&mut self, events: &mut Events)
+	{
+		if self.tribute == 0
+		{
+			self.state = State::TributeSkipped;
+		}
+		else if self.wine >= self.tribute
+		{
+			self.wine -= self.tribute;
+			self.tribute += 2;
+			if self.tribute > MAX_TRIBUTE
+			{
+				self.tribute = MAX_TRIBUTE;
+			}
+			self.state = State::TributePaid;
+		}
+		else
+		{
+			self.tribute += 1;
+			self.advance_tutorial(Trigger::TributeFailed, events);
+			if self.tribute > MAX_TRIBUTE
+			{
+				self.tribute = MAX_TRIBUTE;
+			}
+			if self.threat_level < MAX_THREAT_LEVEL
+			{
+				self.threat_level += 1;
+			}
+			else
+			{
+				self.num_decrees = 0;
+			}
+			self.state = State::TributeFailed;
+		}
+	}
+
+	fn start_new_year(&mut self, events: &mut Events)
+	{
+		self.year = self.year.saturating_add(1);
+		self.day = 0;
+		if !self.advance_tutorial(Trigger::YearStarted, events)
+		{
+			self.state = State::Shuffling;
+		}
+	}
+
+	/// Finishes the current step of the tutorial if it was waiting for this
+	/// moment, and returns whether the tutorial has decided what happens next.
+	fn advance_tutorial(
+		&mut self,
+		trigger: Trigger,
+		events: &mut Events,
+	) -> bool
+	{
+		let step = match self.tutorial()
+		{
+			Some(step) if step.until == Some(trigger) => step,
+			_ => return false,
+		};
+		for effect in step.effects
+		{
+			match *effect
+			{
+				Effect::SetMarker(region_id, marker) =>
+				{
+					self.set_marker(region_id, Some(marker), events);
+				}
+				Effect::SetTribute(tribute) => self.tribute = tribute,
+				Effect::Occupy => self.start_occupation(events),
+				Effect::EndDay => self.num_cards = 0,
+				Effect::RenewDecrees => self.num_decrees = 0,
+			}
+		}
+		self.tutorial = self
+			.tutorial
+			.map(|i| i + 1)
+			.filter(|i| (*i as usize) < self.script.len());
+		if let Some(state) = step.then
+		{
+			self.state = state;
+		}
+		step.then.is_some()
+	}
+
+	fn set_marker(
+		&mut self,
+		i: usize,
+		marker: Option<Marker>,
+		events: &mut Events,
+	)
+	{
+		self.region_data[i].marker = marker;
+		events.push(Event::MarkerChanged {
+			region_id: i as i8,
+			marker,
+		});
+	}
+
+	/// Placements can be taken back until the next shuffle, and also right
+	/// after one that violated a decree or left a Roman nowhere to go.
+	pub fn can_undo(&self) -> bool
+	{
+		match self.state
+		{
+			State::Placement
+			| State::DecreeViolated { .. }
+			| State::NoLegalPlacement => self.undo_history[0].is_some(),
+			_ => false,
+		}
+	}
+
+	fn push_undo(&mut self)
+	{
+		let mut markers = [None; MAX_NUM_REGIONS];
+		for (marker, region) in markers.iter_mut().zip(self.region_data.iter())
+		{
+			*marker = region.marker;
+		}
+		let snapshot = Snapshot {
+			markers,
+			placed_today: self.placed_today,
+			num_cards: self.num_cards,
+			card_offset: self.card_offset,
+			threat_level: self.threat_level,
+			grain: self.grain,
+			wood: self.wood,
+			wine: self.wine,
+			gold: self.gold,
+			score: self.score,
+			state: self.state,
+			tutorial: self.tutorial,
+		};
+		// Forget the oldest placement if the history is full.
+		for i in (1..MAX_NUM_UNDOS).rev()
+		{
+			self.undo_history[i] = self.undo_history[i - 1];
+		}
+		self.undo_history[0] = Some(snapshot);
+	}
+
+	fn undo(&mut self, events: &mut Events)
+	{
+		if !self.can_undo()
+		{
+			return;
+		}
+		let snapshot = match self.undo_history[0].take()
+		{
+			Some(snapshot) => snapshot,
+			None => return,
+		};
+		for i in 1..MAX_NUM_UNDOS
+		{
+			self.undo_history[i - 1] = self.undo_history[i];
+		}
+		self.undo_history[MAX_NUM_UNDOS - 1] = None;
+		for i in 0..(self.num_regions as usize)
+		{
+			if self.region_data[i].marker != snapshot.markers[i]
+			{
+				self.set_marker(i, snapshot.markers[i], events);
+			}
+		}
+		self.placed_today = snapshot.placed_today;
+		self.num_cards = snapshot.num_cards;
+		self.card_offset = snapshot.card_offset;
+		self.threat_level = snapshot.threat_level;
+		self.grain = snapshot.grain;
+		self.wood = snapshot.wood;
+		self.wine = snapshot.wine;
+		self.gold = snapshot.gold;
+		self.score = snapshot.score;
+		self.state = snapshot.state;
+		self.tutorial = snapshot.tutorial;
+	}
+
+	pub fn figure_out_combat(&self, region_id: i8, card: Card) -> Combat
+	{
+		let mut combat = Combat::default();
+		let mut supporters = Bitmap::<MAX_NUM_REGIONS>::default();
+		let mut enemies = Bitmap::<MAX_NUM_REGIONS>::default();
+		let mut occupants = Bitmap::<MAX_NUM_REGIONS>::default();
+		let mut num_supporters = 0;
+		let mut num_enemies = 0;
+		let mut num_occupants = 0;
+		for i in (0..(self.num_regions as usize))
+			.filter(|i| *i as i8 != region_id)
+			.filter(|i| self.adjacency[region_id as usize].get(*i))
+		{
+			match (self.region_data[i].marker, card)
+			{
+				(Some(Marker::Worker), Card::Worker) =>
+				{
+					if self.region_data[i].terrain_type != TerrainType::Village
+					{
+						supporters.set(i, true);
+						num_supporters += 1;
+					}
+				}
+				(Some(Marker::Roman), Card::Worker) =>
+				{
+					enemies.set(i, true);
+					num_enemies += 1;
+				}
+				(Some(Marker::Occupied), Card::Worker) =>
+				{
+					occupants.set(i, true);
+					num_occupants += 1;
+				}
+				(Some(Marker::Roman), Card::Roman) =>
+				{
+					supporters.set(i, true);
+					num_supporters += 1;
+				}
+				(Some(Marker::Worker), Card::Roman) =>
+				{
+					enemies.set(i, true);
+					num_enemies += 1;
+				}
+				(Some(Marker::Occupied), Card::Roman) =>
+				{
+					supporters.set(i, true);
+					num_supporters += 1;
+				}
+				(Some(Marker::DeadRoman), _) => (),
+				(Some(Marker::DeadWorker), _) => (),
+				(Some(Marker::FogOfWar), _) => (),
+				(None, _) => (),
+			}
+		}
+		if num_enemies == 1 && num_occupants == 0
+		{
+			combat.kill = enemies;
+			if num_supporters == 0
+			{
+				combat.kill.set(region_id as usize, true);
+			}
+			else
+			{
+				combat.attack.set(region_id as usize, true);
+			}
+			combat.support = supporters;
+		}
+		else if num_enemies + num_occupants > 0
+		{
+			if num_supporters < num_enemies + num_occupants
+			{
+				combat.kill.set(region_id as usize, true);
+				combat.attack = enemies | occupants;
+			}
+			else
+			{
+				combat.kill = enemies;
+				if num_occupants > 0
+				{
+					combat.kill.set(region_id as usize, true);
+				}
+				else if num_enemies > 0
+				{
+					combat.attack.set(region_id as usize, true);
+				}
+			}
+			combat.support = supporters;
+		}
+		else
+		{
+			combat.gather = supporters;
+		}
+		combat
+	}
+
+	fn count_remaining_spaces(&self) -> usize
+	{
+		self.region_data
+			.iter()
+			.take(self.num_regions as usize)
+			.filter(|region| region.marker.is_none())
+			.filter(|region| region.terrain_type != TerrainType::Water)
+			.count()
+	}
+
+	/// How many Workers the next shuffle deals, one more for each village.
+	fn num_workers_to_deal(&self) -> u8
+	{
+		let num_villages = self
+			.region_data
+			.iter()
+			.take(self.num_regions as usize)
+			.filter(|region| region.terrain_type == TerrainType::Village)
+			.filter(|region| region.marker == Some(Marker::Worker))
+			.count();
+		4 + num_villages as u8
+	}
+
+	fn num_cards_to_deal(&self, num_workers: u8) -> u8
+	{
+		std::cmp::min(
+			num_workers + self.threat_level,
+			self.count_remaining_spaces() as u8,
+		)
+	}
+
+	fn shuffle(&mut self)
+	{
+		self.undo_history = [None; MAX_NUM_UNDOS];
+		self.placed_today = Bitmap::new();
+		self.day = self.day.saturating_add(1);
+		let num_workers = self.num_workers_to_deal();
+		if self.grain >= num_workers
+		{
+			self.grain -= num_workers;
+		}
+		else if self.grain + self.wine >= num_workers
+		{
+			self.wine += self.grain;
+			self.wine -= num_workers;
+			self.grain = 0;
+		}
+		else
+		{
+			self.grain = 0;
+			self.wine = 0;
+		}
+		self.num_cards = self.num_cards_to_deal(num_workers);
+		for i in 0..self.num_cards
+		{
+			self.card_deck[i as usize] =
+				card_to_deal(i, num_workers, self.threat_level);
+		}
+		self.card_offset = 0;
+	}
+
+	fn pick_decrees(&mut self)
+	{
+		self.num_decrees = 0;
+		let is_tutorial = self.tutorial.is_some();
+		let threat_level = self.threat_level;
+		let year = self.year;
+		let in_play = DECREES
+			.iter()
+			.filter(|d| d.is_in_play(is_tutorial, threat_level, year));
+		for definition in in_play.clone()
+		{
+			if definition.issue == Issue::First
+			{
+				self.issue_decree(definition.decree);
+			}
+		}
+		for &decree in self.fixed_decrees
+		{
+			self.issue_decree(decree);
+		}
+		// Drawn decrees are checked together with the ones issued last.
+		let num_issued = self.num_decrees as usize;
+		for definition in in_play
+		{
+			if definition.issue == Issue::Last
+			{
+				self.issue_decree(definition.decree);
+			}
+		}
+		let mut difficulty_level = match self.threat_level
+		{
+			_ if is_tutorial => 0,
+			0..=1 => 0,
+			2..=3 => 1,
+			4..=5 => 2,
+			6 => 3,
+			7.. => 4,
+		};
+		// The deck is shuffled whenever any of it is in play, even if no
+		// decree is drawn, so that a seed keeps giving the same decrees.
+		let is_in_play = |i: u8| {
+			DECREES[i as usize].is_in_play(is_tutorial, threat_level, year)
+		};
+		if self.decree_deck.iter().any(|&i| is_in_play(i))
+		{
+			self.rng.shuffle(&mut self.decree_deck);
+		}
+		let mut num_drawn = 0;
+		for i in 0..DECREE_DECK_SIZE
+		{
+			let definition = &DECREES[self.decree_deck[i] as usize];
+			match definition.issue
+			{
+				Issue::Drawn { weight }
+					if weight <= difficulty_level
+						&& is_in_play(self.decree_deck[i]) =>
+				{
+					// Put the decree back if it cannot be obeyed today
+					// together with all of the ones already issued.
+					let num_decrees = self.num_decrees;
+					self.issue_decree(definition.decree);
+					if self.num_decrees == num_decrees || !self.is_satisfiable()
+					{
+						self.num_decrees = num_decrees;
+						continue;
+					}
+					difficulty_level -= weight;
+					// It goes before the decrees issued last, and to the
+					// front of the deck.
+					for j in
+						((num_issued + num_drawn)..(num_decrees as usize)).rev()
+					{
+						self.decree_data.swap(j, j + 1);
+					}
+					self.decree_deck.swap(num_drawn, i);
+					num_drawn += 1;
+				}
+				_ => (),
+			}
+		}
+		// The decrees issued last go right behind the drawn ones.
+		let mut end = num_drawn;
+		for i in num_drawn..DECREE_DECK_SIZE
+		{
+			if DECREES[self.decree_deck[i] as usize].issue == Issue::Last
+			{
+				self.decree_deck.swap(end, i);
+				end += 1;
+			}
+		}
+		if self.num_decrees == 0
+		{
+			self.issue_decree(Decree::Dummy);
+		}
+	}
+
+	/// Whether the next shuffle can be played without violating any of the
+	/// current decrees about terrain. For both kinds of card, there need to
+	/// be enough empty regions where such a card may go, and enough of them
+	/// counted by each counting decree to reach its count without going over.
+	pub fn is_satisfiable(&self) -> bool
+	{
+		let num_workers = self.num_workers_to_deal();
+		let num_cards = self.num_cards_to_deal(num_workers);
+		let decrees = self.decrees();
+		[(Card::Worker, Marker::Worker), (Card::Roman, Marker::Roman)]
+			.into_iter()
+			.all(|(card, marker)| {
+				let num = (0..num_cards)
+					.filter(|i| {
+						card_to_deal(*i, num_workers, self.threat_level) == card
+					})
+					.count();
+				let mut allowed: Bitmap<MAX_NUM_REGIONS> = Bitmap::new();
+				for i in 0..self.num_regions
+				{
+					let is_allowed = self.can_place(i as i8)
+						&& !decrees.iter().any(|decree| match decree
+						{
+							Decree::Order {
+								target: Target::Terrain(_),
+								..
+							} => self.violates(
+								*decree,
+								i as i8,
+								marker,
+								&Combat::default(),
+							),
+							_ => false,
+						});
+					allowed.set(i as usize, is_allowed);
+				}
+				num <= allowed.len()
+					&& decrees.iter().all(|decree| match *decree
+					{
+						Decree::Count {
+							quantifier,
+							count,
+							marker: m,
+							relation,
+							terrain_type,
+						} if m == marker =>
+						{
+							let num_counted = allowed
+								.into_iter()
+								.filter(|i| {
+									self.is_counted(*i, relation, terrain_type)
+								})
+								.count();
+							let count = count as usize;
+							let is_enough = num_counted >= count.min(num);
+							let is_avoidable = allowed.len() - num_counted
+								>= num.saturating_sub(count);
+							match quantifier
+							{
+								Quantifier::AtLeast => is_enough,
+								Quantifier::AtMost => is_avoidable,
+								Quantifier::Exactly =>
+								{
+									is_enough && is_avoidable
+								}
+							}
+						}
+						_ => true,
+					})
+			})
+	}
+
+	fn issue_decree(&mut self, decree: Decree)
+	{
+		if let Some(slot) = self.decree_data.get_mut(self.num_decrees as usize)
+		{
+			*slot = decree;
+			self.num_decrees += 1;
+		}
+	}
+
+	fn place_marker(&mut self, region_id: i8, card: Card, events: &mut Events)
+	{
+		self.push_undo();
+		self.placed_today.set(region_id as usize, true);
+		let combat = self.figure_out_combat(region_id, card);
+		let terrain_type = self.region_data[region_id as usize].terrain_type;
+		let marker = match card
+		{
+			Card::Worker =>
+			{
+				self.score += 1;
+				if combat.kill.get(region_id as usize)
+				{
+					Marker::DeadWorker
+				}
+				else if terrain_type == TerrainType::Grass
+					&& combat.gather.len() >= 2
+					&& self.wood >= VILLAGE_WOOD_COST
+					&& self.gold >= VILLAGE_GOLD_COST
+				{
+					self.score += 9;
+					self.grain += 1;
+					self.wood -= VILLAGE_WOOD_COST;
+					self.gold -= VILLAGE_GOLD_COST;
+					self.gather_from(combat.gather);
+					self.region_data[region_id as usize].terrain_type =
+						TerrainType::Village;
+					events.push(Event::VillageBuilt { region_id });
+					self.villages_built = self.villages_built.saturating_add(1);
+					self.undo_history = [None; MAX_NUM_UNDOS];
+					Marker::Worker
+				}
+				else
+				{
+					match terrain_type
+					{
+						TerrainType::Village =>
+						{
+							self.grain += 1;
+							self.gather_from(combat.gather);
+						}
+						TerrainType::Grass => self.grain += 1,
+						TerrainType::Forest => self.wood += 1,
+						TerrainType::Hill => self.wine += 1,
+						TerrainType::Mountain => self.gold += 1,
+						TerrainType::Water => (),
+					}
+					Marker::Worker
+				}
+			}
+			Card::Roman =>
+			{
+				if combat.kill.get(region_id as usize)
+				{
+					Marker::DeadRoman
+				}
+				else
+				{
+					Marker::Roman
+				}
+			}
+		};
+		self.set_marker(region_id as usize, Some(marker), events);
+		let mut has_killed_roman = marker == Marker::DeadRoman;
+		for i in combat.kill.into_iter()
+		{
+			if i != region_id as usize
+			{
+				let killed = match self.region_data[i].marker
+				{
+					Some(Marker::Roman) =>
+					{
+						has_killed_roman = true;
+						Some(Marker::DeadRoman)
+					}
+					Some(Marker::Worker) => Some(Marker::DeadWorker),
+					_ => None,
+				};
+				self.set_marker(i, killed, events);
+			}
+		}
+		let violated_decree_offset =
+			(0..(self.num_decrees as usize)).find(|offset| {
+				self.violates(
+					self.decree_data[*offset],
+					region_id,
+					marker,
+					&combat,
+				)
+			});
+		if let Some(offset) = violated_decree_offset
+		{
+			self.violate_decree(offset);
+		}
+		else
+		{
+			self.card_offset += 1;
+		}
+
+		if has_killed_roman
+		{
+			self.advance_tutorial(Trigger::RomanKilled, events);
+		}
+	}
+
+	/// Whether placing a marker (that was possibly killed immediately)
+	/// goes against the given decree.
+	fn violates(
+		&self,
+		decree: Decree,
+		region_id: i8,
+		marker: Marker,
+		combat: &Combat,
+	) -> bool
+	{
+		let (all_or_none, relation, target) = match decree
+		{
+			Decree::Order {
+				all_or_none,
+				marker: m,
+				relation,
+				target,
+			} if m == alive_marker(marker) => (all_or_none, relation, target),
+			Decree::Count {
+				quantifier: Quantifier::AtMost | Quantifier::Exactly,
+				count,
+				marker: m,
+				relation,
+				terrain_type,
+			} if m == alive_marker(marker) =>
+			{
+				return self.is_counted(
+					region_id as usize,
+					relation,
+					terrain_type,
+				) && self.count_placed_today(m, relation, terrain_type)
+					> count as usize;
+			}
+			_ => return false,
+		};
+		let region = &self.region_data[region_id as usize];
+		let matches = match (relation, target)
+		{
+			(_, Target::Terrain(_))
+				if region.terrain_type == TerrainType::Village =>
+			{
+				return false;
+			}
+			(Relation::In, Target::Terrain(terrain_type)) =>
+			{
+				region.terrain_type == terrain_type
+			}
+			(Relation::Near, Target::Terrain(terrain_type)) => self.adjacency
+				[region_id as usize]
+				.into_iter()
+				.any(|i| self.region_data[i].terrain_type == terrain_type),
+			(Relation::Near, Target::Marker(m))
+				if m == alive_marker(marker) =>
+			{
+				!(combat.support | combat.gather).is_empty()
+			}
+			(Relation::Near, Target::Marker(m)) => self.adjacency
+				[region_id as usize]
+				.into_iter()
+				.filter(|i| *i != region_id as usize)
+				.any(|i| self.region_data[i].marker == Some(m)),
+			(Relation::As, Target::Marker(m)) => marker == m,
+			(_, Target::Marker(_)) => false,
+			(Relation::As, Target::Terrain(_)) => false,
+		};
+		match all_or_none
+		{
+			AllOrNone::All => !matches,
+			AllOrNone::None => matches,
+		}
+	}
+
+	/// Which decree, if any, would be violated by placing the active card
+	/// in the given region. This is found out by placing it on a copy of
+	/// the rules, including the end of the day if it is the last card.
+	pub fn preview_violation(&self, region_id: i8) -> Option<u8>
+	{
+		let card = self.active_card()?;
+		// Cloning a fastrand::Rng draws from it, so put the seed back to keep
+		// the game deterministic regardless of what was hovered.
+		let seed = self.rng.get_seed();
+		let mut rules = self.clone();
+		self.rng.seed(seed);
+		rules.place_marker(region_id, card, &mut Events::new());
+		let offset = match rules.state
+		{
+			State::DecreeViolated { decree_offset } => decree_offset as usize,
+			_ if rules.active_card().is_none() => rules.find_unmet_decree()?,
+			_ => return None,
+		};
+		Some(offset as u8)
+	}
+
+	fn find_unmet_decree(&self) -> Option<usize>
+	{
+		(0..(self.num_decrees as usize))
+			.find(|offset| self.is_unmet(self.decree_data[*offset]))
+	}
+
+	/// Whether a decree that counts placements has not been met by the end
+	/// of the day. Fewer placements are required if fewer such cards were
+	/// dealt, not counting the Romans that were sent away in strict mode.
+	fn is_unmet(&self, decree: Decree) -> bool
+	{
+		match decree
+		{
+			Decree::Count {
+				quantifier: Quantifier::AtLeast | Quantifier::Exactly,
+				count,
+				marker,
+				relation,
+				terrain_type,
+			} =>
+			{
+				let card = match marker
+				{
+					Marker::Roman => Card::Roman,
+					_ => Card::Worker,
+				};
+				let mut num_dealt = self
+					.card_deck
+					.iter()
+					.take(self.num_cards as usize)
+					.filter(|c| **c == card)
+					.count();
+				if card == Card::Roman
+				{
+					// Every card that was not placed has been sent away.
+					let num_skipped = (self.card_offset as usize)
+						.saturating_sub(self.placed_today.len());
+					num_dealt = num_dealt.saturating_sub(num_skipped);
+				}
+				let required = std::cmp::min(count as usize, num_dealt);
+				self.count_placed_today(marker, relation, terrain_type)
+					< required
+			}
+			_ => false,
+		}
+	}
+
+	fn count_placed_today(
+		&self,
+		marker: Marker,
+		relation: Relation,
+		terrain_type: TerrainType,
+	) -> usize
+	{
+		self.placed_today
+			.into_iter()
+			.filter(|i| self.is_counted(*i, relation, terrain_type))
+			.filter(|i| match self.region_data[*i].marker
+			{
+				Some(m) => alive_marker(m) == marker,
+				None => false,
+			})
+			.count()
+	}
+
+	/// Whether a marker placed in the given region counts towards a decree
+	/// about a terrain type. Like other decrees, villages are exempt.
+	fn is_counted(
+		&self,
+		i: usize,
+		relation: Relation,
+		terrain_type: TerrainType,
+	) -> bool
+	{
+		match relation
+		{
+			_ if self.region_data[i].terrain_type == TerrainType::Village =>
+			{
+				false
+			}
+			Relation::In => self.region_data[i].terrain_type == terrain_type,
+			Relation::Near => self.adjacency[i]
+				.into_iter()
+				.any(|j| self.region_data[j].terrain_type == terrain_type),
+			Relation::As => false,
+		}
+	}
+
+	fn violate_decree(&mut self, offset: usize)
+	{
+		self.num_cards = 0;
+		if self.threat_level < MAX_THREAT_LEVEL
+		{
+			self.threat_level += 1;
+		}
+		self.state = State::DecreeViolated {
+			decree_offset: offset as u8,
+		};
+	}
+
+	fn gather_from(&mut self, gatherers: Bitmap<MAX_NUM_REGIONS>)
+	{
+		for j in gatherers.into_iter()
+		{
+			if self.region_data[j].marker != Some(Marker::Worker)
+			{
+				continue;
+			}
+			match self.region_data[j].terrain_type
+			{
+				TerrainType::Village => (),
+				TerrainType::Grass => self.grain += 1,
+				TerrainType::Forest => self.wood += 1,
+				TerrainType::Hill => self.wine += 1,
+				TerrainType::Mountain => self.gold += 1,
+				TerrainType::Water => (),
+			}
+		}
+	}
+}
+
+/// The card at the given offset in a deck with this many Workers, where one
+/// in every three cards is a Roman for each level of threat.
+fn card_to_deal(i: u8, num_workers: u8, threat_level: u8) -> Card
+{
+	if 2 * (i / 3) >= num_workers
+	{
+		Card::Roman
+	}
+	else if i / 3 >= threat_level
+	{
+		Card::Worker
+	}
+	else if i % 3 == 2
+	{
+		Card::Roman
+	}
+	else
+	{
+		Card::Worker
+	}
+}
+
+fn alive_marker(marker: Marker) -> Marker
+{
+	match marker
+	{
+		Marker::DeadRoman => Marker::Roman,
+		Marker::DeadWorker => Marker::Worker,
+		x => x,
+	}
+}
+
+// These are in the same order as the enums, so that `x as u8` indexes them.
+const TERRAIN_TYPES: [TerrainType; 6] = [
+	TerrainType::Village,
+	TerrainType::Grass,
+	TerrainType::Forest,
+	TerrainType::Hill,
+	TerrainType::Mountain,
+	TerrainType::Water,
+];
+const MARKERS: [Marker; 6] = [
+	Marker::Worker,
+	Marker::DeadWorker,
+	Marker::Roman,
+	Marker::DeadRoman,
+	Marker::Occupied,
+	Marker::FogOfWar,
+];
+
+/// Decrees are stored as two bytes each. The lower byte has whether it
+/// is All or None in the highest bit, then whether it is about Romans or
+/// Workers, two bits for the relation and four bits for the target terrain
+/// type or marker. For decrees that count, the upper byte holds the count
+/// in six bits and the quantifier in the other two.
+const ENCODED_DUMMY_DECREE: u16 = 0xFFFF;
+
+fn encode_decree(decree: Decree) -> u16
+{
+	let (quantifier, count, all_or_none, marker, relation, target) =
+		match decree
+		{
+			Decree::Order {
+				all_or_none,
+				marker,
+				relation,
+				target,
+			} => (0, 0, all_or_none, marker, relation, target),
+			Decree::Count {
+				quantifier,
+				count,
+				marker,
+				relation,
+				terrain_type,
+			} => (
+				quantifier as u16 + 1,
+				count as u16,
+				AllOrNone::All,
+				marker,
+				relation,
+				Target::Terrain(terrain_type),
+			),
+			Decree::Dummy => return ENCODED_DUMMY_DECREE,
+		};
+	let target = match target
+	{
+		Target::Terrain(terrain_type) => terrain_type as u16,
+		Target::Marker(marker) => 0x8 | marker as u16,
+	};
+	(count << 10)
+		| (quantifier << 8)
+		| ((all_or_none as u16) << 7)
+		| (((marker == Marker::Roman) as u16) << 6)
+		| ((relation as u16) << 4)
+		| target
+}
+
+fn decode_decree(x: u16) -> Option<Decree>
+{
+	if x == ENCODED_DUMMY_DECREE
+	{
+		return Some(Decree::Dummy);
+	}
+	let all_or_none =
+		[AllOrNone::All, AllOrNone::None][((x >> 7) & 0x1) as usize];
+	let marker = [Marker::Worker, Marker::Roman][((x >> 6) & 0x1) as usize];
+	let relations = [Relation::In, Relation::Near, Relation::As];
+	let relation = *relations.get(((x >> 4) & 0x3) as usize)?;
+	let target = match x & 0xF
+	{
+		t @ 0x0..=0x7 => Target::Terrain(*TERRAIN_TYPES.get(t as usize)?),
+		t => Target::Marker(*MARKERS.get((t & 0x7) as usize)?),
+	};
+	let quantifiers =
+		[Quantifier::AtLeast, Quantifier::AtMost, Quantifier::Exactly];
+	match ((x >> 8) & 0x3, target)
+	{
+		(0, _) => Some(Decree::Order {
+			all_or_none,
+			marker,
+			relation,
+			target,
+		}),
+		(q, Target::Terrain(terrain_type)) => Some(Decree::Count {
+			quantifier: quantifiers[(q - 1) as usize],
+			count: (x >> 10) as u8,
+			marker,
+			relation,
+			terrain_type,
+		}),
+		_ => None,
+	}
+}
+
+fn encode_state(state: State) -> (u8, u8)
+{
+	match state
+	{
+		State::Setup => (0, 0),
+		State::NewObjectives => (1, 0),
+		State::NewDecrees => (2, 0),
+		State::Placement => (3, 0),
+		State::Shuffling => (4, 0),
+		State::Resolution => (5, 0),
+		State::Occupation => (6, 0),
+		State::Cleanup => (7, 0),
+		State::DecreeViolated { decree_offset } => (8, decree_offset),
+		State::TributePaid => (9, 0),
+		State::TributeFailed => (10, 0),
+		State::TributeSkipped => (11, 0),
+		State::GameOver => (12, 0),
+		State::NoLegalPlacement => (13, 0),
+		State::Victory => (14, 0),
+	}
+}
+
+fn decode_state(x: u8, decree_offset: u8) -> Option<State>
+{
+	match x
+	{
+		0 => Some(State::Setup),
+		1 => Some(State::NewObjectives),
+		2 => Some(State::NewDecrees),
+		3 => Some(State::Placement),
+		4 => Some(State::Shuffling),
+		5 => Some(State::Resolution),
+		6 => Some(State::Occupation),
+		7 => Some(State::Cleanup),
+		8 if (decree_offset as usize) < MAX_NUM_DECREES =>
+		{
+			Some(State::DecreeViolated { decree_offset })
+		}
+		9 => Some(State::TributePaid),
+		10 => Some(State::TributeFailed),
+		11 => Some(State::TributeSkipped),
+		12 => Some(State::GameOver),
+		13 => Some(State::NoLegalPlacement),
+		14 => Some(State::Victory),
+		_ => None,
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::scenario::GENERATED;
+
+	/// No Romans on the board yet, but one Roman card every day.
+	static SKIRMISH: Scenario = Scenario {
+		threat_level: 1,
+		has_roman_spawn: false,
+		..GENERATED
+	};
+
+	/// A row of regions, each bordering the next, with the last one on the
+	/// border of the map.
+	fn row_of(scenario: &Scenario, terrain_types: &[TerrainType]) -> Rules
+	{
+		let mut adjacency = [Bitmap::new(); MAX_NUM_REGIONS];
+		for i in 1..terrain_types.len()
+		{
+			adjacency[i].set(i - 1, true);
+			adjacency[i - 1].set(i, true);
+		}
+		let mut border_adjacency = Bitmap::new();
+		border_adjacency.set(terrain_types.len() - 1, true);
+		let regions = terrain_types
+			.iter()
+			.enumerate()
+			.map(|(i, terrain_type)| (i as i8, *terrain_type, None));
+		let rng = fastrand::Rng::with_seed(1);
+		Rules::new(scenario, regions, adjacency, border_adjacency, rng)
+	}
+
+	fn place_all(rules: &mut Rules, region_ids: &[i8])
+	{
+		for &region_id in region_ids
+		{
+			assert_eq!(rules.state(), State::Placement);
+			apply(rules, Action::Place { region_id });
+		}
+	}
+
+	fn broken_decree(rules: &Rules) -> Option<Decree>
+	{
+		match rules.state()
+		{
+			State::DecreeViolated { decree_offset } =>
+			{
+				Some(*rules.decree(decree_offset))
+			}
+			_ => None,
+		}
+	}
+
+	const fn counting_decree(
+		quantifier: Quantifier,
+		count: u8,
+		marker: Marker,
+		terrain_type: TerrainType,
+	) -> Decree
+	{
+		Decree::Count {
+			quantifier,
+			count,
+			marker,
+			relation: Relation::In,
+			terrain_type,
+		}
+	}
+
+	fn grassland() -> Rules
+	{
+		row_of(&SKIRMISH, &[TerrainType::Grass; 8])
+	}
+
+	fn apply(rules: &mut Rules, action: Action) -> Vec<Event>
+	{
+		rules.apply(action).iter().copied().collect()
+	}
+
+	fn start_day(rules: &mut Rules)
+	{
+		while rules.state() != State::Placement
+		{
+			apply(rules, Action::Proceed);
+		}
+	}
+
+	fn saved(rules: &Rules) -> Vec<u8>
+	{
+		let mut buffer = [0u8; 256];
+		let mut writer = Writer::new(&mut buffer);
+		rules.save(&mut writer);
+		buffer.to_vec()
+	}
+
+	#[test]
+	fn placing_a_worker_gathers()
+	{
+		let mut rules =
+			row_of(&SKIRMISH, &[TerrainType::Forest, TerrainType::Grass]);
+		start_day(&mut rules);
+		assert_eq!(rules.day(), 1);
+		assert_eq!(rules.active_card(), Some(Card::Worker));
+		let events = apply(&mut rules, Action::Place { region_id: 0 });
+		let placed = Event::MarkerChanged {
+			region_id: 0,
+			marker: Some(Marker::Worker),
+		};
+		assert_eq!(events, [placed]);
+		assert_eq!(rules.wood(), GENERATED.wood + 1);
+		assert_eq!(rules.score(), 1);
+		assert_eq!(rules.state(), State::Placement);
+		assert_eq!(rules.active_card(), Some(Card::Worker));
+	}
+
+	#[test]
+	fn illegal_placements_are_ignored()
+	{
+		let mut rules =
+			row_of(&SKIRMISH, &[TerrainType::Water, TerrainType::Grass]);
+		apply(&mut rules, Action::Place { region_id: 1 });
+		assert_eq!(rules.marker(1), None);
+		start_day(&mut rules);
+		for region_id in [-1, 0, 2]
+		{
+			let events = apply(&mut rules, Action::Place { region_id });
+			assert!(events.is_empty());
+		}
+		assert_eq!(rules.active_card(), Some(Card::Worker));
+	}
+
+	#[test]
+	fn lone_worker_and_roman_kill_each_other()
+	{
+		static AMBUSH: Scenario = Scenario {
+			markers: &[(1, Marker::Roman)],
+			..SKIRMISH
+		};
+		let mut rules = row_of(&AMBUSH, &[TerrainType::Grass; 3]);
+		start_day(&mut rules);
+		let events = apply(&mut rules, Action::Place { region_id: 0 });
+		assert!(events.contains(&Event::MarkerChanged {
+			region_id: 0,
+			marker: Some(Marker::DeadWorker),
+		}));
+		assert!(events.contains(&Event::MarkerChanged {
+			region_id: 1,
+			marker: Some(Marker::DeadRoman),
+		}));
+		assert_eq!(rules.marker(2), None);
+	}
+
+	#[test]
+	fn undo_takes_back_a_placement()
+	{
+		let mut rules = grassland();
+		start_day(&mut rules);
+		assert!(!rules.can_undo());
+		let grain = rules.grain();
+		apply(&mut rules, Action::Place { region_id: 3 });
+		assert_eq!(rules.grain(), grain + 1);
+		assert!(rules.can_undo());
+		let events = apply(&mut rules, Action::Undo);
+		let removed = Event::MarkerChanged {
+			region_id: 3,
+			marker: None,
+		};
+		assert_eq!(events, [removed]);
+		assert_eq!(rules.score(), 0);
+		assert_eq!(rules.grain(), grain);
+		assert!(!rules.can_undo());
+	}
+
+	#[test]
+	fn breaking_a_decree_raises_the_threat()
+	{
+		let mut rules = grassland();
+		start_day(&mut rules);
+		// The first two cards are Workers and the third one is a Roman,
+		// which has to be placed near another Roman.
+		apply(&mut rules, Action::Place { region_id: 0 });
+		apply(&mut rules, Action::Place { region_id: 2 });
+		assert_eq!(rules.active_card(), Some(Card::Roman));
+		apply(&mut rules, Action::Place { region_id: 5 });
+		assert!(matches!(rules.state(), State::DecreeViolated { .. }));
+		assert_eq!(rules.threat_level(), SKIRMISH.threat_level + 1);
+		assert_eq!(rules.active_card(), None);
+	}
+
+	#[test]
+	fn save_and_load_round_trip()
+	{
+		let mut rules = grassland();
+		start_day(&mut rules);
+		apply(&mut rules, Action::Place { region_id: 1 });
+		apply(&mut rules, Action::Place { region_id: 6 });
+		let data = saved(&rules);
+		let mut loaded = grassland();
+		assert_eq!(loaded.load(&mut Reader::new(&data)), Some(()));
+		assert_eq!(saved(&loaded), data);
+		for action in [
+			Action::Place { region_id: 3 },
+			Action::Undo,
+			Action::Place { region_id: 4 },
+			Action::Proceed,
+		]
+		{
+			assert_eq!(apply(&mut loaded, action), apply(&mut rules, action));
+			assert_eq!(saved(&loaded), saved(&rules));
+		}
+
+		let mut elsewhere = row_of(&SKIRMISH, &[TerrainType::Grass; 5]);
+		assert_eq!(elsewhere.load(&mut Reader::new(&data)), None);
+	}
+
+	/// The Romans hold the last region, so Romans can be placed next to it.
+	/// Each day deals a Worker, a Worker, a Roman and two more Workers.
+	static FRONTIER: Scenario = Scenario {
+		has_roman_spawn: true,
+		..SKIRMISH
+	};
+
+	#[test]
+	fn at_most_breaks_on_the_placement_that_goes_over()
+	{
+		const DECREE: Decree = counting_decree(
+			Quantifier::AtMost,
+			1,
+			Marker::Worker,
+			TerrainType::Grass,
+		);
+		static SCENARIO: Scenario = Scenario {
+			decrees: &[DECREE],
+			..FRONTIER
+		};
+		let mut rules = row_of(&SCENARIO, &[TerrainType::Grass; 8]);
+		start_day(&mut rules);
+		apply(&mut rules, Action::Place { region_id: 0 });
+		assert_eq!(broken_decree(&rules), None);
+		apply(&mut rules, Action::Place { region_id: 2 });
+		assert_eq!(broken_decree(&rules), Some(DECREE));
+	}
+
+	#[test]
+	fn at_least_is_only_checked_at_the_end_of_the_day()
+	{
+		const DECREE: Decree = counting_decree(
+			Quantifier::AtLeast,
+			2,
+			Marker::Worker,
+			TerrainType::Forest,
+		);
+		static SCENARIO: Scenario = Scenario {
+			decrees: &[DECREE],
+			..FRONTIER
+		};
+		let mut terrain_types = [TerrainType::Grass; 8];
+		terrain_types[4] = TerrainType::Forest;
+		terrain_types[5] = TerrainType::Forest;
+		let mut rules = row_of(&SCENARIO, &terrain_types);
+		start_day(&mut rules);
+		place_all(&mut rules, &[0, 1, 6, 2, 3]);
+		assert_eq!(rules.state(), State::Placement);
+		assert_eq!(rules.active_card(), None);
+		apply(&mut rules, Action::Proceed);
+		assert_eq!(broken_decree(&rules), Some(DECREE));
+	}
+
+	#[test]
+	fn counts_are_capped_by_the_cards_dealt()
+	{
+		const DECREE: Decree = counting_decree(
+			Quantifier::Exactly,
+			3,
+			Marker::Roman,
+			TerrainType::Grass,
+		);
+		static SCENARIO: Scenario = Scenario {
+			decrees: &[DECREE],
+			..FRONTIER
+		};
+		let mut rules = row_of(&SCENARIO, &[TerrainType::Grass; 8]);
+		start_day(&mut rules);
+		assert_eq!(rules.remaining_cards().len(), 5);
+		place_all(&mut rules, &[0, 1, 6, 2, 3]);
+		apply(&mut rules, Action::Proceed);
+		assert_eq!(broken_decree(&rules), None);
+		assert_eq!(rules.state(), State::Shuffling);
+	}
+
+	#[test]
+	fn romans_sent_away_are_not_required()
+	{
+		const DECREE: Decree = counting_decree(
+			Quantifier::AtLeast,
+			1,
+			Marker::Roman,
+			TerrainType::Grass,
+		);
+		static SCENARIO: Scenario = Scenario {
+			decrees: &[DECREE],
+			..FRONTIER
+		};
+		let mut rules = row_of(&SCENARIO, &[TerrainType::Grass; 8]);
+		rules.set_strict(true);
+		start_day(&mut rules);
+		// The only region next to the Romans is taken before their card.
+		place_all(&mut rules, &[6, 0]);
+		assert_eq!(rules.state(), State::NoLegalPlacement);
+		assert!(rules.can_undo());
+		apply(&mut rules, Action::Undo);
+		assert_eq!(rules.state(), State::Placement);
+		assert_eq!(rules.marker(0), None);
+		place_all(&mut rules, &[0]);
+		assert_eq!(rules.state(), State::NoLegalPlacement);
+		apply(&mut rules, Action::Acknowledge);
+		place_all(&mut rules, &[2, 4]);
+		assert_eq!(rules.active_card(), None);
+		apply(&mut rules, Action::Proceed);
+		assert_eq!(broken_decree(&rules), None);
+		assert_eq!(rules.state(), State::Shuffling);
+	}
+
+	#[test]
+	fn each_day_is_announced()
+	{
+		let mut rules =
+			row_of(&SKIRMISH, &[TerrainType::Forest, TerrainType::Grass]);
+		for expected_day in 1..=2
+		{
+			let mut days = Vec::new();
+			while rules.state() != State::Placement
+			{
+				for event in apply(&mut rules, Action::Proceed)
+				{
+					if let Event::DayStarted { day } = event
+					{
+						days.push(day);
+					}
+				}
+			}
+			assert_eq!(days, [expected_day]);
+			rules.card_offset = rules.num_cards;
+			rules.state = State::Shuffling;
+		}
+	}
+
+	#[test]
+	fn decrees_that_no_longer_go_together_are_renewed()
+	{
+		let mut rules = grassland();
+		apply(&mut rules, Action::Proceed);
+		assert_eq!(rules.state(), State::Shuffling);
+		let issued = rules.decrees().to_vec();
+		// No Worker can be placed in a Mountain, as there are none.
+		let impossible = counting_decree(
+			Quantifier::AtLeast,
+			1,
+			Marker::Worker,
+			TerrainType::Mountain,
+		);
+		rules.issue_decree(impossible);
+		assert!(!rules.is_satisfiable());
+		apply(&mut rules, Action::Proceed);
+		assert_eq!(rules.state(), State::NewDecrees);
+		assert_eq!(rules.decrees(), issued);
+		apply(&mut rules, Action::Acknowledge);
+		start_day(&mut rules);
+		assert_eq!(rules.day(), 1);
+	}
+
+	#[test]
+	fn fixed_decrees_are_kept_even_if_they_cannot_be_obeyed()
+	{
+		static SCENARIO: Scenario = Scenario {
+			decrees: &[counting_decree(
+				Quantifier::AtLeast,
+				1,
+				Marker::Worker,
+				TerrainType::Mountain,
+			)],
+			..SKIRMISH
+		};
+		let mut rules = row_of(&SCENARIO, &[TerrainType::Grass; 8]);
+		apply(&mut rules, Action::Proceed);
+		assert!(!rules.is_satisfiable());
+		apply(&mut rules, Action::Proceed);
+		assert_eq!(rules.state(), State::Placement);
+		assert_eq!(rules.day(), 1);
+	}
+
+	#[test]
+	fn decrees_are_kept_if_redrawing_does_not_help()
+	{
+		let impossible = counting_decree(
+			Quantifier::AtLeast,
+			1,
+			Marker::Worker,
+			TerrainType::Mountain,
+		);
+		static SCENARIO: Scenario = Scenario {
+			decrees: &[counting_decree(
+				Quantifier::AtLeast,
+				1,
+				Marker::Worker,
+				TerrainType::Mountain,
+			)],
+			..SKIRMISH
+		};
+		let mut rules = row_of(&SCENARIO, &[TerrainType::Grass; 8]);
+		apply(&mut rules, Action::Proceed);
+		assert_eq!(rules.state(), State::Shuffling);
+		// Decrees from before the threat level went up, which a redraw
+		// would replace with just as many that are no easier to obey.
+		let issued = [
+			Decree::Order {
+				all_or_none: AllOrNone::None,
+				marker: Marker::Worker,
+				relation: Relation::Near,
+				target: Target::Marker(Marker::Worker),
+			},
+			impossible,
+			counting_decree(
+				Quantifier::AtMost,
+				2,
+				Marker::Roman,
+				TerrainType::Grass,
+			),
+		];
+		rules.num_decrees = 0;
+		for decree in issued
+		{
+			rules.issue_decree(decree);
+		}
+		let mut redrawn = rules.clone();
+		redrawn.pick_decrees();
+		assert!(!redrawn.is_satisfiable());
+		assert_eq!(redrawn.decrees().len(), issued.len());
+		assert_ne!(redrawn.decrees(), issued);
+		apply(&mut rules, Action::Proceed);
+		assert_eq!(rules.state(), State::Placement);
+		assert_eq!(rules.decrees(), issued);
+		assert_eq!(rules.day(), 1);
+	}
+
+	/// Clears up the board at the end of a year with this goal, after
+	/// `setup`, and returns the state it ends up in.
+	fn year_end(goal: Goal, setup: impl Fn(&mut Rules)) -> State
+	{
+		let mut rules = grassland();
+		apply(&mut rules, Action::Proceed);
+		rules.goal = Some(goal);
+		setup(&mut rules);
+		rules.state = State::Cleanup;
+		while rules.state() == State::Cleanup
+		{
+			apply(&mut rules, Action::Proceed);
+		}
+		rules.state()
+	}
+
+	#[test]
+	fn surviving_enough_years_is_a_victory()
+	{
+		let goal = Goal::SurviveYears(3);
+		let state = year_end(goal, |rules| rules.years_survived = 1);
+		assert_eq!(state, State::TributeFailed);
+		let state = year_end(goal, |rules| rules.years_survived = 2);
+		assert_eq!(state, State::Victory);
+	}
+
+	#[test]
+	fn reaching_the_score_is_a_victory()
+	{
+		let goal = Goal::ReachScore(100);
+		let state = year_end(goal, |rules| rules.score = 99);
+		assert_eq!(state, State::TributeFailed);
+		let state = year_end(goal, |rules| rules.score = 100);
+		assert_eq!(state, State::Victory);
+	}
+
+	#[test]
+	fn building_enough_villages_is_a_victory()
+	{
+		let goal = Goal::BuildVillages(2);
+		let state = year_end(goal, |rules| rules.villages_built = 1);
+		assert_eq!(state, State::TributeFailed);
+		let state = year_end(goal, |rules| rules.villages_built = 2);
+		assert_eq!(state, State::Victory);
+	}
+
+	#[test]
+	fn defeating_enough_romans_is_a_victory()
+	{
+		let goal = Goal::DefeatRomans(2);
+		// The dead Roman still on the board counts as it is cleared away.
+		let state = year_end(goal, |rules| {
+			rules.region_data[0].marker = Some(Marker::DeadRoman);
+		});
+		assert_eq!(state, State::TributeFailed);
+		let state = year_end(goal, |rules| {
+			rules.region_data[0].marker = Some(Marker::DeadRoman);
+			rules.romans_defeated = 1;
+		});
+		assert_eq!(state, State::Victory);
+	}
+
+	#[test]
+	fn largest_map_fits_in_a_save()
+	{
+		let terrain_types = [TerrainType::Grass; MAX_NUM_REGIONS];
+		let mut rules = row_of(&SKIRMISH, &terrain_types);
+		start_day(&mut rules);
+		let mut buffer = [0u8; MAX_SAVED_SIZE];
+		rules.save(&mut Writer::new(&mut buffer));
+		let mut loaded = row_of(&SKIRMISH, &terrain_types);
+		assert_eq!(loaded.load(&mut Reader::new(&buffer)), Some(()));
+		assert_eq!(saved(&loaded), saved(&rules));
+	}
+}
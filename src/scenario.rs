@@ -0,0 +1,224 @@
+//
+// Part of the-romans-are-coming
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+//! The conditions that a level starts with. Most levels are played on a
+//! generated island, but the tutorials are made by hand.
+
+use crate::platform::*;
+
+use crate::decree::{AllOrNone, Decree, Relation, Target};
+use crate::level::draw_number;
+use crate::map;
+use crate::map::{AuthoredMap, Preset};
+use crate::rules::{Marker, Rules, TerrainType};
+use crate::tutorial;
+use crate::tutorial::Step;
+
+#[derive(Debug)]
+pub struct Scenario
+{
+	/// The position in `SCENARIOS`, so that the scenario can be saved and
+	/// recorded.
+	pub id: u8,
+	/// The map drawn by hand, or `None` to generate one from the preset.
+	pub map: Option<&'static AuthoredMap>,
+	/// The script of the tutorial, or nothing if this is not a tutorial.
+	pub tutorial: &'static [Step],
+	/// The island of a tutorial is always the same one. Other levels get
+	/// their seed and preset from the menu or from their chapter.
+	pub rng_seed: u64,
+	pub preset: Preset,
+	pub threat_level: u8,
+	pub tribute: u8,
+	pub grain: u8,
+	pub wood: u8,
+	pub gold: u8,
+	/// Whether the Romans start out occupying a region on the border.
+	pub has_roman_spawn: bool,
+	/// Markers placed on top of those of the map, such as fog of war.
+	pub markers: &'static [(u8, Marker)],
+	/// Decrees that are issued every day, on top of the usual ones.
+	pub decrees: &'static [Decree],
+	/// What has to be achieved to complete this chapter of the campaign.
+	pub goal: Option<Goal>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Goal
+{
+	SurviveYears(u8),
+	ReachScore(u16),
+	BuildVillages(u8),
+	DefeatRomans(u8),
+}
+
+impl Goal
+{
+	pub fn is_met(&self, rules: &Rules) -> bool
+	{
+		match *self
+		{
+			Goal::SurviveYears(years) => rules.years_survived() >= years,
+			Goal::ReachScore(score) => rules.score() >= score,
+			Goal::BuildVillages(villages) => rules.villages_built() >= villages,
+			Goal::DefeatRomans(romans) => rules.romans_defeated() >= romans,
+		}
+	}
+
+	pub fn draw(&self, x: i32, y: i32)
+	{
+		let (verb, count, noun) = match *self
+		{
+			Goal::SurviveYears(years) => ("Survive", years as u64, "years"),
+			Goal::ReachScore(score) => ("Score", score as u64, "pts"),
+			Goal::BuildVillages(villages) =>
+			{
+				("Build", villages as u64, "villages")
+			}
+			Goal::DefeatRomans(romans) => ("Defeat", romans as u64, "Romans"),
+		};
+		text(verb, x, y);
+		let x = x + 8 * (verb.len() as i32 + 1);
+		let x = x + draw_number(count, x, y) + 8;
+		text(noun, x, y);
+	}
+}
+
+/// A game on a generated island, as in Freeplay or the daily challenge.
+pub static GENERATED: Scenario = Scenario {
+	id: 0,
+	map: None,
+	tutorial: &[],
+	rng_seed: 0,
+	preset: Preset::Classic,
+	threat_level: 0,
+	tribute: 2,
+	grain: 24,
+	wood: 10,
+	gold: 10,
+	has_roman_spawn: true,
+	markers: &[],
+	decrees: &[],
+	goal: None,
+};
+
+pub static TUTORIAL: Scenario = Scenario {
+	id: 1,
+	map: Some(&map::TUTORIAL),
+	tutorial: &tutorial::INTRODUCTION,
+	rng_seed: 1,
+	tribute: 0,
+	wood: 0,
+	gold: 0,
+	has_roman_spawn: false,
+	..GENERATED
+};
+
+pub static VILLAGE_TUTORIAL: Scenario = Scenario {
+	id: 2,
+	tutorial: &tutorial::VILLAGE,
+	rng_seed: 202,
+	tribute: 1,
+	wood: 15,
+	..GENERATED
+};
+
+/// The chapters of the campaign, in the order in which they unlock.
+pub static CAMPAIGN: [Scenario; 10] = [
+	Scenario {
+		id: 3,
+		threat_level: 0,
+		tribute: 0,
+		goal: Some(Goal::SurviveYears(2)),
+		..GENERATED
+	},
+	Scenario {
+		id: 4,
+		wood: 20,
+		gold: 15,
+		goal: Some(Goal::BuildVillages(2)),
+		..GENERATED
+	},
+	Scenario {
+		id: 5,
+		goal: Some(Goal::ReachScore(30)),
+		..GENERATED
+	},
+	Scenario {
+		id: 6,
+		grain: 16,
+		goal: Some(Goal::SurviveYears(4)),
+		..GENERATED
+	},
+	Scenario {
+		id: 7,
+		wood: 0,
+		gold: 5,
+		goal: Some(Goal::BuildVillages(3)),
+		..GENERATED
+	},
+	Scenario {
+		id: 8,
+		tribute: 4,
+		goal: Some(Goal::ReachScore(60)),
+		..GENERATED
+	},
+	Scenario {
+		id: 9,
+		threat_level: 2,
+		goal: Some(Goal::SurviveYears(5)),
+		..GENERATED
+	},
+	Scenario {
+		id: 10,
+		decrees: &[Decree::Order {
+			all_or_none: AllOrNone::None,
+			marker: Marker::Worker,
+			relation: Relation::In,
+			target: Target::Terrain(TerrainType::Forest),
+		}],
+		goal: Some(Goal::ReachScore(80)),
+		..GENERATED
+	},
+	Scenario {
+		id: 11,
+		threat_level: 4,
+		goal: Some(Goal::DefeatRomans(6)),
+		..GENERATED
+	},
+	Scenario {
+		id: 12,
+		threat_level: 6,
+		tribute: 6,
+		grain: 30,
+		goal: Some(Goal::ReachScore(100)),
+		..GENERATED
+	},
+];
+
+static SCENARIOS: [&Scenario; 13] = [
+	&GENERATED,
+	&TUTORIAL,
+	&VILLAGE_TUTORIAL,
+	&CAMPAIGN[0],
+	&CAMPAIGN[1],
+	&CAMPAIGN[2],
+	&CAMPAIGN[3],
+	&CAMPAIGN[4],
+	&CAMPAIGN[5],
+	&CAMPAIGN[6],
+	&CAMPAIGN[7],
+	&CAMPAIGN[8],
+	&CAMPAIGN[9],
+];
+
+impl Scenario
+{
+	pub fn from_id(id: u8) -> Option<&'static Scenario>
+	{
+		SCENARIOS.get(id as usize).copied()
+	}
+}
@@ -0,0 +1,337 @@
+//
+// Part of the-romans-are-coming
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+use crate::disk;
+use crate::disk::{Reader, Writer};
+
+pub const NUM_SCORES_PER_MODE: usize = 5;
+pub const NUM_DAILY_BESTS: usize = 16;
+pub const MAX_DAY: u16 = 9999;
+
+const SCORES_FORMAT_VERSION: u8 = 1;
+const DAILY_FORMAT_VERSION: u8 = 1;
+
+/// Daily games keep one best score per day instead of a high score table,
+/// and the campaign only remembers which chapters were completed.
+const NUM_LEADERBOARDS: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode
+{
+	Start,
+	Freeplay,
+	Daily,
+	Campaign,
+}
+
+const MODES: [Mode; 4] =
+	[Mode::Start, Mode::Freeplay, Mode::Daily, Mode::Campaign];
+
+impl Mode
+{
+	pub fn from_u8(x: u8) -> Option<Mode>
+	{
+		MODES.get(x as usize).copied()
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Score
+{
+	pub score: u16,
+	pub seed: u64,
+	pub years_survived: u8,
+	pub villages_built: u8,
+}
+
+pub struct Leaderboard
+{
+	scores: [[Option<Score>; NUM_SCORES_PER_MODE]; NUM_LEADERBOARDS],
+}
+
+impl Leaderboard
+{
+	pub fn load() -> Self
+	{
+		let mut leaderboard = Self {
+			scores: [[None; NUM_SCORES_PER_MODE]; NUM_LEADERBOARDS],
+		};
+		let mut buffer = [0u8; disk::SCORES.size];
+		disk::read(disk::SCORES, &mut buffer);
+		let mut reader = Reader::new(&buffer);
+		if reader.read_u8() == Some(SCORES_FORMAT_VERSION)
+		{
+			for entry in leaderboard.scores.iter_mut().flatten()
+			{
+				*entry = read_score(&mut reader);
+			}
+		}
+		leaderboard
+	}
+
+	fn save(&self)
+	{
+		let mut buffer = [0u8; disk::SCORES.size];
+		let mut writer = Writer::new(&mut buffer);
+		writer.write_u8(SCORES_FORMAT_VERSION);
+		for entry in self.scores.iter().flatten()
+		{
+			write_score(&mut writer, entry);
+		}
+		disk::write(disk::SCORES, &buffer);
+	}
+
+	/// The best scores for this mode, from high to low.
+	pub fn scores(&self, mode: Mode) -> impl Iterator<Item = &Score>
+	{
+		self.scores
+			.get(mode as usize)
+			.into_iter()
+			.flatten()
+			.flatten()
+	}
+
+	/// Adds the score to the table (and the disk) if it is high enough,
+	/// and returns its rank.
+	pub fn submit(&mut self, mode: Mode, score: Score) -> Option<usize>
+	{
+		let entries = self.scores.get_mut(mode as usize)?;
+		let rank = entries.iter().position(|entry| match entry
+		{
+			Some(entry) => score.score > entry.score,
+			None => true,
+		})?;
+		entries[rank..].rotate_right(1);
+		entries[rank] = Some(score);
+		self.save();
+		Some(rank)
+	}
+}
+
+/// WASM-4 has no clock, so the player tells us what day it is once and we
+/// remember it. Everyone who plays the same day gets the same seed.
+pub struct Daily
+{
+	pub day: u16,
+	bests: [Option<(u16, u16)>; NUM_DAILY_BESTS],
+}
+
+impl Daily
+{
+	pub fn load() -> Self
+	{
+		let mut daily = Self {
+			day: 1,
+			bests: [None; NUM_DAILY_BESTS],
+		};
+		let mut buffer = [0u8; disk::DAILY.size];
+		disk::read(disk::DAILY, &mut buffer);
+		let mut reader = Reader::new(&buffer);
+		if reader.read_u8() == Some(DAILY_FORMAT_VERSION)
+		{
+			daily.day = reader.read_u16().unwrap_or(1).clamp(1, MAX_DAY);
+			for entry in daily.bests.iter_mut()
+			{
+				let day = reader.read_u16().unwrap_or(0);
+				let score = reader.read_u16().unwrap_or(0);
+				if day > 0
+				{
+					*entry = Some((day, score));
+				}
+			}
+		}
+		daily
+	}
+
+	fn save(&self)
+	{
+		let mut buffer = [0u8; disk::DAILY.size];
+		let mut writer = Writer::new(&mut buffer);
+		writer.write_u8(DAILY_FORMAT_VERSION);
+		writer.write_u16(self.day);
+		for entry in self.bests.iter()
+		{
+			let (day, score) = entry.unwrap_or((0, 0));
+			writer.write_u16(day);
+			writer.write_u16(score);
+		}
+		disk::write(disk::DAILY, &buffer);
+	}
+
+	/// A seed that fits in the seed entry screen, so that a daily map can
+	/// also be replayed in Freeplay.
+	pub fn seed(day: u16) -> u64
+	{
+		let mut x = (day as u64).wrapping_add(0x9e3779b97f4a7c15);
+		x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+		x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+		x = x ^ (x >> 31);
+		x % 100_000_000
+	}
+
+	/// Remembers the day that is about to be played.
+	pub fn start(&mut self, day: u16)
+	{
+		self.day = day;
+		self.save();
+	}
+
+	pub fn best(&self, day: u16) -> Option<u16>
+	{
+		self.bests
+			.iter()
+			.flatten()
+			.find(|(d, _score)| *d == day)
+			.map(|(_day, score)| *score)
+	}
+
+	/// Records the score for the current day, forgetting the oldest day if
+	/// needed, and returns whether it is a new best.
+	pub fn submit(&mut self, score: u16) -> bool
+	{
+		let day = self.day;
+		if let Some(best) = self.best(day)
+		{
+			if score <= best
+			{
+				return false;
+			}
+		}
+		let slot = self
+			.bests
+			.iter()
+			.position(|entry| matches!(entry, Some((d, _)) if *d == day))
+			.or_else(|| self.bests.iter().position(|entry| entry.is_none()))
+			.or_else(|| {
+				self.bests
+					.iter()
+					.enumerate()
+					.min_by_key(|(_i, entry)| entry.map(|(d, _)| d))
+					.map(|(i, _entry)| i)
+			});
+		if let Some(i) = slot
+		{
+			self.bests[i] = Some((day, score));
+		}
+		self.save();
+		true
+	}
+}
+
+fn write_score(writer: &mut Writer, entry: &Option<Score>)
+{
+	match entry
+	{
+		Some(entry) =>
+		{
+			writer.write_u8(1);
+			writer.write_u16(entry.score);
+			writer.write_u64(entry.seed);
+			writer.write_u8(entry.years_survived);
+			writer.write_u8(entry.villages_built);
+		}
+		None =>
+		{
+			writer.write_u8(0);
+			writer.write_u16(0);
+			writer.write_u64(0);
+			writer.write_u8(0);
+			writer.write_u8(0);
+		}
+	}
+}
+
+fn read_score(reader: &mut Reader) -> Option<Score>
+{
+	let is_present = reader.read_u8()? != 0;
+	let score = Score {
+		score: reader.read_u16()?,
+		seed: reader.read_u64()?,
+		years_survived: reader.read_u8()?,
+		villages_built: reader.read_u8()?,
+	};
+	if is_present
+	{
+		Some(score)
+	}
+	else
+	{
+		None
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::mock::Mock;
+	use crate::platform::run_with;
+
+	fn score(score: u16) -> Score
+	{
+		Score {
+			score,
+			seed: 1000 + score as u64,
+			years_survived: 3,
+			villages_built: 1,
+		}
+	}
+
+	#[test]
+	fn keeps_the_best_scores_per_mode()
+	{
+		let mut mock = Mock::new();
+		run_with(&mut mock, || {
+			let mut leaderboard = Leaderboard::load();
+			assert_eq!(leaderboard.scores(Mode::Start).count(), 0);
+			for x in [5, 3, 9, 0, 7, 1]
+			{
+				leaderboard.submit(Mode::Freeplay, score(x));
+			}
+			assert_eq!(leaderboard.submit(Mode::Freeplay, score(0)), None);
+			assert_eq!(leaderboard.submit(Mode::Start, score(4)), Some(0));
+
+			let leaderboard = Leaderboard::load();
+			let freeplay: Vec<Score> =
+				leaderboard.scores(Mode::Freeplay).copied().collect();
+			assert_eq!(
+				freeplay,
+				[score(9), score(7), score(5), score(3), score(1)]
+			);
+			let start: Vec<Score> =
+				leaderboard.scores(Mode::Start).copied().collect();
+			assert_eq!(start, [score(4)]);
+		});
+	}
+
+	#[test]
+	fn keeps_the_best_score_per_day()
+	{
+		let mut mock = Mock::new();
+		run_with(&mut mock, || {
+			let mut daily = Daily::load();
+			assert_eq!(daily.day, 1);
+			assert!(daily.submit(30));
+			assert!(!daily.submit(20));
+			daily.start(2);
+			assert!(daily.submit(10));
+			for day in 3..(2 + NUM_DAILY_BESTS as u16)
+			{
+				daily.start(day);
+				daily.submit(day);
+			}
+
+			let daily = Daily::load();
+			assert_eq!(daily.day, 1 + NUM_DAILY_BESTS as u16);
+			assert_eq!(daily.best(1), None);
+			assert_eq!(daily.best(2), Some(10));
+			assert_eq!(daily.best(3), Some(3));
+			assert_eq!(Daily::seed(7), Daily::seed(7));
+			assert_ne!(Daily::seed(7), Daily::seed(8));
+			assert!(Daily::seed(MAX_DAY) < 100_000_000);
+		});
+	}
+}
@@ -0,0 +1,117 @@
+//
+// Part of the-romans-are-coming
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+use crate::disk;
+
+const SETTINGS_FORMAT_VERSION: u8 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setting
+{
+	Undo,
+	Sound,
+	Music,
+	Strict,
+}
+
+pub const SETTINGS: [Setting; 4] = [
+	Setting::Undo,
+	Setting::Strict,
+	Setting::Sound,
+	Setting::Music,
+];
+
+impl Setting
+{
+	pub fn name(&self) -> &'static str
+	{
+		match self
+		{
+			Setting::Undo => "Undo",
+			Setting::Sound => "Sound",
+			Setting::Music => "Music",
+			Setting::Strict => "Legal",
+		}
+	}
+
+	fn default_value(&self) -> bool
+	{
+		match self
+		{
+			Setting::Undo => true,
+			Setting::Sound => true,
+			Setting::Music => true,
+			Setting::Strict => false,
+		}
+	}
+}
+
+/// Options that the player can turn on or off from the menu. Each one is
+/// a bit on disk, next to a bit that says whether it was ever stored, so
+/// that settings added later start out with their default value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Settings
+{
+	flags: u8,
+}
+
+impl Settings
+{
+	pub fn load() -> Self
+	{
+		let mut buffer = [0u8; 3];
+		disk::read(disk::SETTINGS, &mut buffer);
+		let (flags, known) = if buffer[0] == SETTINGS_FORMAT_VERSION
+		{
+			(buffer[1], buffer[2])
+		}
+		else
+		{
+			(0, 0)
+		};
+		let mut settings = Self { flags };
+		for setting in SETTINGS
+		{
+			if known & (1 << (setting as u8)) == 0
+			{
+				settings.set(setting, setting.default_value());
+			}
+		}
+		settings
+	}
+
+	fn save(&self)
+	{
+		let known = SETTINGS
+			.iter()
+			.fold(0, |known, setting| known | (1 << (*setting as u8)));
+		let buffer = [SETTINGS_FORMAT_VERSION, self.flags, known];
+		disk::write(disk::SETTINGS, &buffer);
+	}
+
+	pub fn get(&self, setting: Setting) -> bool
+	{
+		self.flags & (1 << (setting as u8)) != 0
+	}
+
+	fn set(&mut self, setting: Setting, value: bool)
+	{
+		if value
+		{
+			self.flags |= 1 << (setting as u8);
+		}
+		else
+		{
+			self.flags &= !(1 << (setting as u8));
+		}
+	}
+
+	pub fn toggle(&mut self, setting: Setting)
+	{
+		self.set(setting, !self.get(setting));
+		self.save();
+	}
+}
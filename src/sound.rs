@@ -0,0 +1,127 @@
+//
+// Part of the-romans-are-coming
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+use crate::platform::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sound
+{
+	PlaceWorker,
+	Kill,
+	DecreeViolated,
+	TributePaid,
+	TributeFailed,
+	VillageBuilt,
+	Occupation,
+}
+
+/// A single call to `tone()`: a slide from one frequency to another that
+/// is held for `sustain` frames and then fades out over `release` frames.
+struct Note
+{
+	from: u16,
+	to: u16,
+	sustain: u8,
+	release: u8,
+	volume: u8,
+	flags: u32,
+}
+
+impl Sound
+{
+	fn notes(&self) -> &'static [Note]
+	{
+		match self
+		{
+			Sound::PlaceWorker => &[Note {
+				from: 440,
+				to: 520,
+				sustain: 2,
+				release: 4,
+				volume: 25,
+				flags: TONE_PULSE1 | TONE_MODE3,
+			}],
+			Sound::Kill => &[Note {
+				from: 320,
+				to: 80,
+				sustain: 4,
+				release: 10,
+				volume: 50,
+				flags: TONE_NOISE,
+			}],
+			Sound::DecreeViolated => &[
+				Note {
+					from: 220,
+					to: 110,
+					sustain: 20,
+					release: 20,
+					volume: 40,
+					flags: TONE_PULSE2 | TONE_MODE2,
+				},
+				Note {
+					from: 110,
+					to: 55,
+					sustain: 20,
+					release: 20,
+					volume: 60,
+					flags: TONE_TRIANGLE,
+				},
+			],
+			Sound::TributePaid => &[
+				Note {
+					from: 523,
+					to: 523,
+					sustain: 10,
+					release: 15,
+					volume: 30,
+					flags: TONE_PULSE1 | TONE_MODE2,
+				},
+				Note {
+					from: 659,
+					to: 784,
+					sustain: 10,
+					release: 15,
+					volume: 50,
+					flags: TONE_TRIANGLE,
+				},
+			],
+			Sound::TributeFailed => &[Note {
+				from: 196,
+				to: 147,
+				sustain: 20,
+				release: 20,
+				volume: 60,
+				flags: TONE_TRIANGLE,
+			}],
+			Sound::VillageBuilt => &[Note {
+				from: 523,
+				to: 1046,
+				sustain: 8,
+				release: 12,
+				volume: 30,
+				flags: TONE_PULSE1 | TONE_MODE2,
+			}],
+			Sound::Occupation => &[Note {
+				from: 120,
+				to: 60,
+				sustain: 30,
+				release: 30,
+				volume: 40,
+				flags: TONE_NOISE,
+			}],
+		}
+	}
+
+	pub fn play(&self)
+	{
+		for note in self.notes()
+		{
+			let frequency = (note.from as u32) | ((note.to as u32) << 16);
+			let duration = (note.sustain as u32) | ((note.release as u32) << 8);
+			tone(frequency, duration, note.volume.into(), note.flags);
+		}
+	}
+}
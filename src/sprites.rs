@@ -4,7 +4,7 @@
 // License: MIT
 //
 
-use crate::wasm4::*;
+use crate::platform::*;
 
 pub fn draw_surface(x: i32, y: i32, alt: u8)
 {
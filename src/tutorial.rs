@@ -0,0 +1,365 @@
+//
+// Part of the-romans-are-coming
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+//! The tutorials are scripts: a list of steps that each explain something,
+//! wait for a moment in the game and then nudge the rules along. The rules
+//! only need to say when such a moment happens.
+
+use crate::decree::{draw_parts, Icon, Part};
+use crate::map::TUTORIAL_SECOND_ROMAN;
+use crate::map::{TUTORIAL_FIRST_ROMAN, TUTORIAL_FIRST_WORKER};
+use crate::rules::{Card, Marker, State};
+
+#[derive(Debug, PartialEq)]
+pub struct Step
+{
+	/// What is shown when the objectives are hovered during this step.
+	pub text: &'static [Part],
+	/// The card that the player should place during this step, and a mask
+	/// of the regions where it should go. These regions are highlighted and
+	/// the card cannot go anywhere else until one of them is filled.
+	pub expects: Option<(Card, u64)>,
+	/// The moment at which this step is done, or None for the last step.
+	pub until: Option<Trigger>,
+	/// What happens to the board once this step is done.
+	pub effects: &'static [Effect],
+	/// The state that the game continues in once this step is done, instead
+	/// of whatever the rules would do at that moment.
+	pub then: Option<State>,
+}
+
+impl Step
+{
+	pub fn draw(&self, x: i32, y: i32)
+	{
+		draw_parts(self.text, x, y);
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Trigger
+{
+	/// The first decrees have been picked.
+	Start,
+	/// All cards have been placed, but there is still room on the map.
+	DayEnded,
+	/// All cards have been placed and there is no room left on the map.
+	MapFilled,
+	/// A Roman has just been killed.
+	RomanKilled,
+	/// The map has been cleaned up after harvest.
+	YearEnded,
+	/// A new year is about to start, after the tribute.
+	YearStarted,
+	/// The tribute was not paid and it has just been raised.
+	TributeFailed,
+	/// The player has dismissed the popup of this state.
+	Acknowledged(State),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Effect
+{
+	SetMarker(usize, Marker),
+	SetTribute(u8),
+	/// Lets the Romans occupy the regions that they are in.
+	Occupy,
+	/// Discards the cards that have not been placed yet.
+	EndDay,
+	/// Has new decrees issued at the next opportunity.
+	RenewDecrees,
+}
+
+const NOTHING: &[Effect] = &[];
+
+/// The tutorial that is played when starting the game for the first time.
+pub static INTRODUCTION: [Step; 12] = [
+	Step {
+		text: PLACE_BANNERS,
+		expects: Some((Card::Worker, 1 << TUTORIAL_FIRST_WORKER)),
+		until: Some(Trigger::DayEnded),
+		effects: NOTHING,
+		then: Some(State::NewObjectives),
+	},
+	Step {
+		text: FEED_WORKERS,
+		expects: None,
+		until: Some(Trigger::MapFilled),
+		effects: NOTHING,
+		then: Some(State::NewObjectives),
+	},
+	Step {
+		text: HARVEST,
+		expects: None,
+		until: Some(Trigger::YearStarted),
+		effects: &[Effect::SetMarker(TUTORIAL_SECOND_ROMAN, Marker::Roman)],
+		then: None,
+	},
+	Step {
+		text: HARVEST,
+		expects: None,
+		until: Some(Trigger::RomanKilled),
+		effects: &[Effect::EndDay],
+		then: Some(State::NewObjectives),
+	},
+	Step {
+		text: FIRST_KILL,
+		expects: None,
+		until: Some(Trigger::YearEnded),
+		effects: &[
+			Effect::SetMarker(TUTORIAL_FIRST_ROMAN, Marker::Roman),
+			Effect::Occupy,
+		],
+		then: Some(State::Occupation),
+	},
+	Step {
+		text: ROMANS_HAVE_COME,
+		expects: None,
+		until: Some(Trigger::YearStarted),
+		effects: NOTHING,
+		then: Some(State::NewObjectives),
+	},
+	Step {
+		text: ROMANS_HAVE_COME,
+		expects: None,
+		until: Some(Trigger::MapFilled),
+		effects: &[Effect::SetTribute(15)],
+		then: Some(State::NewObjectives),
+	},
+	// The tribute cannot be paid, after which it starts over at 1.
+	Step {
+		text: TRIBUTE,
+		expects: None,
+		until: Some(Trigger::TributeFailed),
+		effects: &[Effect::SetTribute(1)],
+		then: None,
+	},
+	Step {
+		text: TRIBUTE,
+		expects: None,
+		until: Some(Trigger::Acknowledged(State::TributeFailed)),
+		effects: &[Effect::RenewDecrees],
+		then: None,
+	},
+	Step {
+		text: TRIBUTE,
+		expects: None,
+		until: Some(Trigger::Acknowledged(State::NewDecrees)),
+		effects: NOTHING,
+		then: Some(State::NewObjectives),
+	},
+	Step {
+		text: PLACE_ROMANS,
+		expects: None,
+		until: Some(Trigger::Acknowledged(State::NewObjectives)),
+		effects: NOTHING,
+		then: Some(State::Placement),
+	},
+	Step {
+		text: PLACE_ROMANS,
+		expects: None,
+		until: None,
+		effects: NOTHING,
+		then: None,
+	},
+];
+
+/// The village tutorial only holds back the decrees for the first day.
+pub static VILLAGE: [Step; 1] = [Step {
+	text: &[],
+	expects: None,
+	until: Some(Trigger::Start),
+	effects: NOTHING,
+	then: Some(State::NewObjectives),
+}];
+
+// The parts are written out as constants (without `into()`) so that each
+// text is stored in the data section instead of being assembled by code.
+
+const PLACE_BANNERS: &[Part] = &[
+	Part::Word("Greetings,"),
+	Part::Newline,
+	Part::Word("your highness!"),
+	Part::Newline,
+	Part::Newline,
+	Part::Word("Place"),
+	Part::Marker(Marker::Worker),
+	Part::Word("on"),
+	Part::Icon(Icon::Town),
+	Part::Newline,
+	Part::Word("to score 1"),
+	Part::Icon(Icon::Score),
+	Part::Newline,
+	Part::Word("and gather"),
+	Part::Newline,
+	Part::Word("1"),
+	Part::Icon(Icon::Grain),
+	Part::Word("/"),
+	Part::Icon(Icon::Wood),
+	Part::Word("/"),
+	Part::Icon(Icon::Gold),
+	Part::Word("/"),
+	Part::Icon(Icon::Wine),
+	Part::Period,
+	Part::Newline,
+	Part::Newline,
+	Part::Word("After 4"),
+	Part::Marker(Marker::Worker),
+	Part::Newline,
+	Part::Word("a new day"),
+	Part::Newline,
+	Part::Word("begins."),
+];
+
+const FEED_WORKERS: &[Part] = &[
+	Part::Word("Each"),
+	Part::Marker(Marker::Worker),
+	Part::Word("consumes"),
+	Part::Newline,
+	Part::Word("1"),
+	Part::Icon(Icon::Grain),
+	Part::Word(" (or 1"),
+	Part::Icon(Icon::Wine),
+	Part::Word(")"),
+	Part::Newline,
+	Part::Word("per day."),
+	Part::Newline,
+	Part::Newline,
+	Part::Word("Keep placing"),
+	Part::Marker(Marker::Worker),
+	Part::Newline,
+	Part::Word("until the map"),
+	Part::Newline,
+	Part::Word("is full."),
+	Part::Newline,
+	Part::Newline,
+	Part::Word("(Hover the"),
+	Part::Newline,
+	Part::Word("resource bar"),
+	Part::Newline,
+	Part::Word("for hints.)"),
+];
+
+const HARVEST: &[Part] = &[
+	Part::Word("Wonderful!"),
+	Part::Newline,
+	Part::Newline,
+	Part::Word("During harvest"),
+	Part::Newline,
+	Part::Word("each"),
+	Part::Marker(Marker::Worker),
+	Part::Word("gathers"),
+	Part::Newline,
+	Part::Word("an additional"),
+	Part::Newline,
+	Part::Word("1"),
+	Part::Icon(Icon::Grain),
+	Part::Word("/"),
+	Part::Icon(Icon::Wood),
+	Part::Word("/"),
+	Part::Icon(Icon::Gold),
+	Part::Word("/"),
+	Part::Icon(Icon::Wine),
+	Part::Period,
+];
+
+const FIRST_KILL: &[Part] = &[
+	Part::Word("Ah!"),
+	Part::Newline,
+	Part::Newline,
+	Part::Word("Your grace,"),
+	Part::Newline,
+	Part::Word("one of your"),
+	Part::Marker(Marker::Worker),
+	Part::Newline,
+	Part::Word("seems to have"),
+	Part::Newline,
+	Part::Word("fought and"),
+	Part::Newline,
+	Part::Word("killed a"),
+	Part::Marker(Marker::Roman),
+	Part::Period,
+	Part::Newline,
+	Part::Newline,
+	Part::Word("Let us pray"),
+	Part::Newline,
+	Part::Word("the Romans"),
+	Part::Newline,
+	Part::Word("forgive you..."),
+];
+
+const ROMANS_HAVE_COME: &[Part] = &[
+	Part::Word("The Romans"),
+	Part::Newline,
+	Part::Word("have come!"),
+	Part::Newline,
+	Part::Newline,
+	Part::Word("Your highness,"),
+	Part::Newline,
+	Part::Word("a"),
+	Part::Marker(Marker::Roman),
+	Part::Word("has seized"),
+	Part::Newline,
+	Part::Word("control of the"),
+	Part::Newline,
+	Part::Word("beach."),
+	Part::Newline,
+	Part::Word("Our"),
+	Part::Marker(Marker::Worker),
+	Part::Word("cannot"),
+	Part::Newline,
+	Part::Word("defeat"),
+	Part::Marker(Marker::Roman),
+	Part::Word("in"),
+	Part::Newline,
+	Part::Word("a region in"),
+	Part::Newline,
+	Part::Word("Roman hands."),
+];
+
+const TRIBUTE: &[Part] = &[
+	Part::Word("The Romans"),
+	Part::Newline,
+	Part::Word("demand that"),
+	Part::Newline,
+	Part::Word("a tribute of"),
+	Part::Newline,
+	Part::Word("15"),
+	Part::Icon(Icon::Wine),
+	Part::Word("is paid."),
+	Part::Newline,
+	Part::Newline,
+	Part::Word("What they ask"),
+	Part::Newline,
+	Part::Word("is impossible!"),
+];
+
+const PLACE_ROMANS: &[Part] = &[
+	Part::Word("Each day, we"),
+	Part::Newline,
+	Part::Word("must now also"),
+	Part::Newline,
+	Part::Word("place"),
+	Part::Marker(Marker::Roman),
+	Part::Word("on"),
+	Part::Icon(Icon::Town),
+	Part::Newline,
+	Part::Word("according to"),
+	Part::Newline,
+	Part::Word("their decrees."),
+	Part::Newline,
+	Part::Newline,
+	Part::Word("If we do not"),
+	Part::Newline,
+	Part::Word("obey, the"),
+	Part::Newline,
+	Part::Word("Roman Emperor"),
+	Part::Newline,
+	Part::Word("will send more"),
+	Part::Newline,
+	Part::Marker(Marker::Roman),
+	Part::Period,
+];
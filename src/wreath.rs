@@ -6,7 +6,7 @@
 // Original artwork by scribbles pixels
 // https://scrixels.tumblr.com/
 
-use crate::wasm4::*;
+use crate::platform::*;
 
 pub fn draw_laurel_wreath(x: i32, y: i32)
 {
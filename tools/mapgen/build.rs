@@ -0,0 +1,17 @@
+//
+// Part of the-romans-are-coming
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+//! Runs the build script of the cart, which expects the assets to be found
+//! relative to the root of the repository.
+
+#[path = "../../build.rs"]
+mod cart;
+
+fn main()
+{
+	std::env::set_current_dir("../..").unwrap();
+	cart::main();
+}
@@ -0,0 +1,18 @@
+//
+// Part of the-romans-are-coming
+// Copyright (c) 2022 Sander in 't Veld
+// License: MIT
+//
+
+//! Exports the maps of a range of seeds for browsing, as described in the
+//! README. Run it with `--help` for the options.
+
+fn main()
+{
+	let args: Vec<String> = std::env::args().skip(1).collect();
+	if let Err(message) = cart::export::run(&args)
+	{
+		eprintln!("{}", message);
+		std::process::exit(1);
+	}
+}